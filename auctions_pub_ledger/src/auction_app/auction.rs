@@ -1,18 +1,197 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
+use sha256::digest;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
 use std::{fs, io};
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Bid {
     pub bidder: String,
     pub amount: f32,
     pub signature: String,
     pub auction_signature: String,
+    // set by the node that accepts the bid, not covered by the bidder signature
+    #[serde(with = "chrono::serde::ts_seconds", default)]
+    pub timestamp: DateTime<Utc>,
+    // salt of a revealed sealed bid, replicas open the commitment with it. Empty for others.
+    #[serde(default)]
+    pub salt: String,
 }
+// Sealed bid commitment: only the hash of the bid is public until the reveal phase
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Notification {
+pub struct BidCommitment {
+    pub bidder: String,
+    pub auction_signature: String,
+    pub commitment: String,
+    pub signature: String,
+}
+
+// Opening of a commitment after the bidding window, the bid is signed as a regular one
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BidReveal {
+    pub bid: Bid,
+    pub salt: String,
+}
+// Acceptance of a Dutch auction price, the price is derived from the signed timestamp
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DutchAccept {
     pub bidder: String,
-    pub amount: f32,
     pub auction_signature: String,
+    #[serde(with = "chrono::serde::ts_seconds")]
+    pub timestamp: DateTime<Utc>,
+    pub signature: String,
+}
+// Seller withdrawal of an auction that has not received any bids yet
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuctionCancel {
+    pub seller: String,
+    pub auction_signature: String,
+    pub signature: String,
+}
+// Signed request to start or stop following an auction. Subscriptions are keyed on the
+// user's public key, where notifications are delivered is registered separately.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Subscription {
+    pub subscriber: String,
+    pub auction_signature: String,
+    // false to unsubscribe
+    pub subscribe: bool,
+    pub timestamp: DateTime<Utc>,
+    pub signature: String,
+}
+// Address a user wants their notifications delivered to, signed so nobody else can point
+// them somewhere else
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Endpoint {
+    pub user_id: String,
+    pub addr: String,
+    pub timestamp: DateTime<Utc>,
+    pub signature: String,
+}
+// Messages pushed to the notification server of auction subscribers. `Bid`, `BiddingOpen`,
+// `Extended` and `EndedWithoutBids` go to every subscriber, the others to the bidder or
+// seller they concern.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Notification {
+    Bid(Bid),
+    BiddingOpen {
+        auction_signature: String,
+        item_name: String,
+    },
+    // `bid` is the one that took the recipient's place
+    Outbid {
+        auction_signature: String,
+        item_name: String,
+        bid: Bid,
+    },
+    Extended {
+        auction_signature: String,
+        item_name: String,
+        closing_time: DateTime<Utc>,
+    },
+    Won {
+        auction_signature: String,
+        item_name: String,
+        price: f32,
+    },
+    Lost {
+        auction_signature: String,
+        item_name: String,
+    },
+    // settled without a winning bid, none was placed or none met the reserve
+    EndedWithoutBids {
+        auction_signature: String,
+        item_name: String,
+    },
+    SellerPayout {
+        auction_signature: String,
+        item_name: String,
+        amount: f32,
+        units_sold: u32,
+    },
+}
+
+// Minimum raise over the current price, either a fixed amount or a percentage of it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum BidIncrement {
+    Absolute(f32),
+    Percent(f32),
+}
+
+// Price paid by each winner: its own bid (discriminatory on multi-unit auctions), the best
+// losing bid (Vickrey), or the lowest winning bid for every unit (uniform)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+pub enum Pricing {
+    #[default]
+    FirstPrice,
+    SecondPrice,
+    Uniform,
+}
+
+// Anti-sniping: a bid placed in the last `window_seconds` pushes the close back by
+// `extension_seconds`, never beyond `max_extension_seconds` past the original `end_time`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SoftClose {
+    pub window_seconds: i64,
+    pub extension_seconds: i64,
+    pub max_extension_seconds: i64,
+}
+
+// Per-auction bidding rules chosen by the seller
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuctionRules {
+    pub reserve_price: f32,
+    pub min_increment: BidIncrement,
+    #[serde(default)]
+    pub pricing: Pricing,
+    #[serde(default)]
+    pub soft_close: Option<SoftClose>,
+    #[serde(default)]
+    pub buy_now_price: Option<f32>,
+}
+
+impl Default for AuctionRules {
+    fn default() -> Self {
+        AuctionRules {
+            reserve_price: 0.0,
+            min_increment: BidIncrement::Absolute(0.0),
+            pricing: Pricing::FirstPrice,
+            soft_close: None,
+            buy_now_price: None,
+        }
+    }
+}
+
+// Descriptive details chosen by the seller. Images live in the DHT, the auction only keeps
+// their sha256 content hashes so the signature covers them too.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+pub struct AuctionMetadata {
+    pub description: String,
+    pub category: String,
+    pub tags: Vec<String>,
+    pub attributes: BTreeMap<String, String>,
+    pub images: Vec<String>,
+}
+
+// English auctions take open ascending bids. Sealed-bid auctions take commitments until
+// `end_time` and reveals during the following `reveal_seconds`; bidders that never reveal
+// forfeit the deposit. Dutch auctions start at `start_price` and drop by `decrement` every
+// `decrement_seconds` down to `floor_price`, the first accept wins at the current price.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+pub enum AuctionKind {
+    #[default]
+    English,
+    SealedBid {
+        deposit: f32,
+        reveal_seconds: i64,
+    },
+    Dutch {
+        start_price: f32,
+        decrement: f32,
+        decrement_seconds: i64,
+        floor_price: f32,
+    },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -23,44 +202,490 @@ pub struct Auction {
     #[serde(with = "chrono::serde::ts_seconds")]
     pub end_time: DateTime<Utc>,
     pub starting_bid: f32,
+    // identical units on sale, each bidder can win one of them
+    #[serde(default = "single_unit")]
+    pub quantity: u32,
+    // items bundled and sold together under `item_name`
+    #[serde(default)]
+    pub lot: Vec<String>,
+    #[serde(default)]
+    pub metadata: AuctionMetadata,
+    #[serde(default)]
+    pub rules: AuctionRules,
+    #[serde(default)]
+    pub kind: AuctionKind,
     pub bids: Vec<Bid>,
+    #[serde(default)]
+    pub commitments: Vec<BidCommitment>,
     pub active: bool,
+    #[serde(default)]
+    pub cancelled: bool,
+    // signed withdrawal of the seller, replicas only take `cancelled` from it
+    #[serde(default)]
+    pub cancellation: Option<AuctionCancel>,
+    // set once subscribers were told that bidding opened
+    #[serde(default)]
+    pub opening_notified: bool,
     pub user_id: String,
     pub signature: String,
+    // public keys of the users following the auction
     pub subscribers: Vec<String>,
+    // latest signed subscribe or unsubscribe of every user that sent one
+    #[serde(default)]
+    pub subscriptions: Vec<Subscription>,
+    // latest endpoint signed by each user that transacted on the auction, so the replica
+    // notifying them knows where they are
+    #[serde(default)]
+    pub endpoints: Vec<Endpoint>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum Transaction {
-    Auction(Auction),
+    Auction(Box<Auction>),
     Bid(Bid),
+    Commit(BidCommitment),
+    Reveal(BidReveal),
+    Accept(DutchAccept),
+    Cancel(AuctionCancel),
+    Subscription(Subscription),
+}
+
+impl Transaction {
+    // ID of the auction the transaction applies to
+    pub fn auction_signature(&self) -> &str {
+        match self {
+            Transaction::Auction(auction) => &auction.signature,
+            Transaction::Bid(bid) => &bid.auction_signature,
+            Transaction::Commit(commitment) => &commitment.auction_signature,
+            Transaction::Reveal(reveal) => &reveal.bid.auction_signature,
+            Transaction::Accept(accept) => &accept.auction_signature,
+            Transaction::Cancel(cancel) => &cancel.auction_signature,
+            Transaction::Subscription(subscription) => &subscription.auction_signature,
+        }
+    }
+
+    // uid of the user that signed the transaction
+    pub fn signer(&self) -> &str {
+        match self {
+            Transaction::Auction(auction) => &auction.user_id,
+            Transaction::Bid(bid) => &bid.bidder,
+            Transaction::Commit(commitment) => &commitment.bidder,
+            Transaction::Reveal(reveal) => &reveal.bid.bidder,
+            Transaction::Accept(accept) => &accept.bidder,
+            Transaction::Cancel(cancel) => &cancel.seller,
+            Transaction::Subscription(subscription) => &subscription.subscriber,
+        }
+    }
+
+    // signature of the signer over `signed_content`
+    pub fn signature(&self) -> &str {
+        match self {
+            Transaction::Auction(auction) => &auction.signature,
+            Transaction::Bid(bid) => &bid.signature,
+            Transaction::Commit(commitment) => &commitment.signature,
+            Transaction::Reveal(reveal) => &reveal.bid.signature,
+            Transaction::Accept(accept) => &accept.signature,
+            Transaction::Cancel(cancel) => &cancel.signature,
+            Transaction::Subscription(subscription) => &subscription.signature,
+        }
+    }
+
+    pub fn signed_content(&self) -> String {
+        match self {
+            Transaction::Auction(auction) => auction.signed_content(),
+            Transaction::Bid(bid) => bid.signed_content(),
+            Transaction::Commit(commitment) => commitment.signed_content(),
+            Transaction::Reveal(reveal) => reveal.bid.signed_content(),
+            Transaction::Accept(accept) => accept.signed_content(),
+            Transaction::Cancel(cancel) => cancel.signed_content(),
+            Transaction::Subscription(subscription) => subscription.signed_content(),
+        }
+    }
+}
+
+fn single_unit() -> u32 {
+    1
+}
+
+impl Bid {
+    // content signed by the bidder: sha256(auction signature + bidder uid + amount)
+    pub fn signed_content(&self) -> String {
+        digest(self.auction_signature.clone() + &self.bidder + &self.amount.to_string())
+    }
+}
+
+impl BidCommitment {
+    // content signed by the bidder: sha256(auction signature + bidder uid + commitment)
+    pub fn signed_content(&self) -> String {
+        digest(self.auction_signature.clone() + &self.bidder + &self.commitment)
+    }
+}
+
+impl BidReveal {
+    // hash published in the commitment: sha256(signed bid content + salt)
+    pub fn commitment(&self) -> String {
+        digest(self.bid.signed_content() + &self.salt)
+    }
+}
+
+impl DutchAccept {
+    // content signed by the bidder: sha256(auction signature + bidder uid + unix timestamp)
+    pub fn signed_content(&self) -> String {
+        digest(
+            self.auction_signature.clone() + &self.bidder + &self.timestamp.timestamp().to_string(),
+        )
+    }
+}
+
+impl AuctionCancel {
+    // content signed by the seller: sha256(auction signature + seller uid + "cancel")
+    pub fn signed_content(&self) -> String {
+        digest(self.auction_signature.clone() + &self.seller + "cancel")
+    }
+}
+
+impl Subscription {
+    // content signed by the subscriber: sha256(auction signature + subscriber uid +
+    // "subscribe" or "unsubscribe" + unix timestamp)
+    pub fn signed_content(&self) -> String {
+        let action = if self.subscribe {
+            "subscribe"
+        } else {
+            "unsubscribe"
+        };
+        digest(
+            self.auction_signature.clone()
+                + &self.subscriber
+                + action
+                + &self.timestamp.timestamp().to_string(),
+        )
+    }
+}
+
+impl Endpoint {
+    // content signed by the user: sha256(uid + address + unix timestamp)
+    pub fn signed_content(&self) -> String {
+        digest(self.user_id.clone() + &self.addr + &self.timestamp.timestamp().to_string())
+    }
+}
+
+impl BidIncrement {
+    pub fn over(&self, price: f32) -> f32 {
+        match self {
+            BidIncrement::Absolute(amount) => price + amount,
+            BidIncrement::Percent(percent) => price + price * percent / 100.0,
+        }
+    }
+}
+
+impl fmt::Display for BidIncrement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BidIncrement::Absolute(amount) => write!(f, "{}", amount),
+            BidIncrement::Percent(percent) => write!(f, "{}%", percent),
+        }
+    }
+}
+
+impl FromStr for BidIncrement {
+    type Err = std::num::ParseFloatError;
+
+    // "5" is an absolute increment, "5%" a percentage of the current price
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().strip_suffix('%') {
+            Some(percent) => Ok(BidIncrement::Percent(percent.trim().parse()?)),
+            None => Ok(BidIncrement::Absolute(s.trim().parse()?)),
+        }
+    }
+}
+
+impl fmt::Display for AuctionKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AuctionKind::English => write!(f, "English"),
+            AuctionKind::SealedBid { .. } => write!(f, "Sealed-bid"),
+            AuctionKind::Dutch { .. } => write!(f, "Dutch"),
+        }
+    }
 }
 
 impl Auction {
+    // the signature is left empty, the seller signs `signed_content` and fills it in
     pub fn new(
         item_name: String,
         start_time: DateTime<Utc>,
         end_time: DateTime<Utc>,
         starting_bid: f32,
+        rules: AuctionRules,
+        kind: AuctionKind,
         user_id: String,
-        signature: String,
-        subscribers: Vec<String>,
     ) -> Self {
         Auction {
             item_name,
             start_time,
             end_time,
             starting_bid,
+            quantity: 1,
+            lot: Vec::new(),
+            metadata: AuctionMetadata::default(),
+            rules,
+            kind,
             bids: Vec::new(),
+            commitments: Vec::new(),
             active: true,
+            cancelled: false,
+            cancellation: None,
+            opening_notified: false,
             user_id,
-            signature,
-            subscribers,
+            signature: String::new(),
+            subscribers: Vec::new(),
+            subscriptions: Vec::new(),
+            endpoints: Vec::new(),
+        }
+    }
+
+    // content signed by the seller, covers every field the seller chooses, the schedule
+    // included so relisting the same item gets a new signature
+    pub fn signed_content(&self) -> String {
+        digest(
+            self.item_name.clone()
+                + &self.start_time.timestamp().to_string()
+                + &self.end_time.timestamp().to_string()
+                + &self.starting_bid.to_string()
+                + &self.quantity.to_string()
+                + &serde_json::to_string(&self.lot).unwrap()
+                + &serde_json::to_string(&self.metadata).unwrap()
+                + &serde_json::to_string(&self.rules).unwrap()
+                + &serde_json::to_string(&self.kind).unwrap()
+                + &self.user_id,
+        )
+    }
+
+    // users broadcast notifications go out to, an explicit unsubscribe outweighs following
+    // the auction by bidding on it
+    pub fn followers(&self) -> Vec<String> {
+        self.subscribers
+            .iter()
+            .filter(|subscriber| {
+                !self.subscriptions.iter().any(|subscription| {
+                    &subscription.subscriber == *subscriber && !subscription.subscribe
+                })
+            })
+            .cloned()
+            .collect()
+    }
+
+    // whether the user asked to follow the auction, bidders following it are not watching
+    pub fn watched_by(&self, user_id: &str) -> bool {
+        self.subscriptions
+            .iter()
+            .any(|subscription| subscription.subscriber == user_id && subscription.subscribe)
+    }
+
+    // keep the subscription unless a newer one of the same user is known, returns whether
+    // it was kept. Every replica ends up with the same latest subscription per user.
+    pub fn record_subscription(&mut self, subscription: &Subscription) -> bool {
+        match self
+            .subscriptions
+            .iter_mut()
+            .find(|known| known.subscriber == subscription.subscriber)
+        {
+            Some(known)
+                if (known.timestamp, &known.signature)
+                    >= (subscription.timestamp, &subscription.signature) =>
+            {
+                return false
+            }
+            Some(known) => *known = subscription.clone(),
+            None => {
+                self.subscriptions.push(subscription.clone());
+                self.subscriptions
+                    .sort_by(|a, b| a.subscriber.cmp(&b.subscriber));
+            }
+        }
+        if subscription.subscribe && !self.subscribers.contains(&subscription.subscriber) {
+            self.subscribers.push(subscription.subscriber.clone());
+        }
+        true
+    }
+
+    // keep the endpoint unless a newer one of the same user is known, returns whether it
+    // was kept
+    pub fn record_endpoint(&mut self, endpoint: &Endpoint) -> bool {
+        match self
+            .endpoints
+            .iter_mut()
+            .find(|known| known.user_id == endpoint.user_id)
+        {
+            Some(known)
+                if (known.timestamp, &known.signature)
+                    >= (endpoint.timestamp, &endpoint.signature) =>
+            {
+                false
+            }
+            Some(known) => {
+                *known = endpoint.clone();
+                true
+            }
+            None => {
+                self.endpoints.push(endpoint.clone());
+                self.endpoints.sort_by(|a, b| a.user_id.cmp(&b.user_id));
+                true
+            }
+        }
+    }
+
+    // English auctions with a soft close are extended by late bids and a bid meeting the
+    // buy now price ends them on the spot, a sold out Dutch auction ends when its last unit
+    // was taken. The close is derived from the bid timestamps alone, so every node holding
+    // the same bids agrees on it and the signed `end_time` never changes.
+    pub fn closing_time(&self) -> DateTime<Utc> {
+        match self.kind {
+            AuctionKind::English => {}
+            AuctionKind::Dutch { .. } => {
+                return self
+                    .bids
+                    .iter()
+                    .map(|bid| bid.timestamp)
+                    .nth(self.quantity.max(1) as usize - 1)
+                    .map_or(self.end_time, |sold_out| sold_out.min(self.end_time));
+            }
+            AuctionKind::SealedBid { .. } => return self.end_time,
+        }
+        let mut closing = self.end_time;
+        let mut buyers: Vec<&str> = Vec::new();
+        for bid in self.bids.iter() {
+            if self.meets_buy_now(bid) && !buyers.contains(&bid.bidder.as_str()) {
+                buyers.push(&bid.bidder);
+                if buyers.len() >= self.quantity as usize {
+                    return bid.timestamp.min(closing);
+                }
+            }
+            if let Some(soft_close) = self.rules.soft_close.as_ref() {
+                let cap = self.end_time + Duration::seconds(soft_close.max_extension_seconds);
+                if bid.timestamp < closing
+                    && closing - bid.timestamp <= Duration::seconds(soft_close.window_seconds)
+                {
+                    closing = (closing + Duration::seconds(soft_close.extension_seconds)).min(cap);
+                }
+            }
+        }
+        closing
+    }
+
+    pub fn meets_buy_now(&self, bid: &Bid) -> bool {
+        self.rules
+            .buy_now_price
+            .is_some_and(|buy_now_price| bid.amount >= buy_now_price)
+    }
+
+    pub fn state(&self) -> &str {
+        if self.cancelled {
+            "cancelled"
+        } else if self.active && Utc::now() < self.start_time {
+            "upcoming"
+        } else if self.active {
+            "active"
+        } else {
+            "closed"
+        }
+    }
+
+    // sealed-bid auctions only settle once the reveal phase is over
+    pub fn settlement_time(&self) -> DateTime<Utc> {
+        match self.kind {
+            AuctionKind::English | AuctionKind::Dutch { .. } => self.closing_time(),
+            AuctionKind::SealedBid { reveal_seconds, .. } => {
+                self.end_time + Duration::seconds(reveal_seconds)
+            }
+        }
+    }
+
+    // Dutch price at a given time, computed on whole seconds so every validator agrees
+    pub fn dutch_price_at(&self, at: DateTime<Utc>) -> Option<f32> {
+        match self.kind {
+            AuctionKind::Dutch {
+                start_price,
+                decrement,
+                decrement_seconds,
+                floor_price,
+            } => {
+                let elapsed = (at.timestamp() - self.start_time.timestamp()).max(0);
+                let steps = elapsed / decrement_seconds.max(1);
+                Some((start_price - decrement * steps as f32).max(floor_price))
+            }
+            _ => None,
+        }
+    }
+
+    // best bid of each bidder, highest first with the earliest bid winning ties since sealed-bid
+    // reveals do not arrive in ascending order. The first `quantity` of them hold a unit.
+    pub fn ranked_bids(&self) -> Vec<&Bid> {
+        let mut best: Vec<(usize, &Bid)> = Vec::new();
+        for (position, bid) in self.bids.iter().enumerate() {
+            match best.iter_mut().find(|(_, kept)| kept.bidder == bid.bidder) {
+                Some(kept) if bid.amount > kept.1.amount => *kept = (position, bid),
+                Some(_) => {}
+                None => best.push((position, bid)),
+            }
+        }
+        best.sort_by(|(a_position, a), (b_position, b)| {
+            b.amount
+                .total_cmp(&a.amount)
+                .then(a_position.cmp(b_position))
+        });
+        best.into_iter().map(|(_, bid)| bid).collect()
+    }
+
+    pub fn winning_bids(&self) -> Vec<&Bid> {
+        let mut ranked = self.ranked_bids();
+        ranked.truncate(self.quantity as usize);
+        ranked
+    }
+
+    // lowest bid still holding a unit, once every unit is taken
+    pub fn lowest_winning_bid(&self) -> Option<&Bid> {
+        let winning_bids = self.winning_bids();
+        if winning_bids.len() < self.quantity as usize {
+            return None;
+        }
+        winning_bids.last().copied()
+    }
+
+    // name shown in listings, with the unit count or the bundled items
+    pub fn title(&self) -> String {
+        if !self.lot.is_empty() {
+            format!("{} ({})", self.item_name, self.lot.join(", "))
+        } else if self.quantity > 1 {
+            format!("{} x{}", self.item_name, self.quantity)
+        } else {
+            self.item_name.clone()
+        }
+    }
+
+    // lowest price the seller accepts to part with the item
+    pub fn reserve_floor(&self) -> f32 {
+        self.rules.reserve_price.max(self.starting_bid)
+    }
+
+    pub fn current_price(&self) -> f32 {
+        match self.lowest_winning_bid() {
+            Some(bid) => bid.amount,
+            None => self.dutch_price_at(Utc::now()).unwrap_or(self.starting_bid),
+        }
+    }
+
+    // lowest amount the next bid has to reach to be accepted, it has to outbid the
+    // lowest winning bid once every unit is taken
+    pub fn minimum_next_bid(&self) -> f32 {
+        match self.lowest_winning_bid() {
+            Some(bid) => self.rules.min_increment.over(bid.amount),
+            None => self.starting_bid,
         }
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct AuctionHouse {
     pub auctions: Vec<Auction>,
 }
@@ -76,6 +701,14 @@ impl AuctionHouse {
         self.auctions.push(auction);
     }
 }
+// drop the snapshots of a previous discovery
+pub fn clear_auction_data() -> io::Result<()> {
+    for file in get_files_in_directory("auctions")? {
+        fs::remove_file(format!("auctions/{}", file))?;
+    }
+    Ok(())
+}
+
 pub fn save_auction_data(
     auctions: &AuctionHouse,
     ip_addr: &str,
@@ -89,31 +722,45 @@ pub fn save_auction_data(
     Ok(())
 }
 
-pub async fn list_auctions() -> AuctionHouse {
-    let result = get_files_in_directory("auctions");
+// sealed bids waiting for their reveal phase, kept per user since the salt never leaves the client
+pub fn save_sealed_bids(user_name: &str, sealed_bids: &[BidReveal]) {
+    let serialized = serde_json::to_string_pretty(sealed_bids).unwrap();
+    fs::create_dir_all("sealed_bids").expect("error creating sealed bids directory");
+    fs::write(format!("sealed_bids/{}.json", user_name), serialized)
+        .expect("error writing sealed bids");
+}
+
+pub fn load_sealed_bids(user_name: &str) -> Vec<BidReveal> {
+    match fs::read_to_string(format!("sealed_bids/{}.json", user_name)) {
+        Ok(data) => serde_json::from_str(&data).expect("Failed to deserialize JSON"),
+        Err(_) => Vec::new(),
+    }
+}
+
+pub fn print_auctions(auctions: &[Auction]) {
     println!(
-        "|{:<130} | {:<15} | {:<23} | {:<10} | {:<10}|",
-        "ID", "Auction Name", "End Time", "bidding price", "Auction State"
+        "|{:<130} | {:<15} | {:<10} | {:<23} | {:<10} | {:<10}|",
+        "ID", "Auction Name", "Type", "End Time", "bidding price", "Auction State"
     );
+    for auction in auctions.iter() {
+        println!(
+            "|{:<130} | {:<15} | {:<10} | {:<10} | {:<13} | {:<10}|",
+            auction.signature,
+            auction.title(),
+            auction.kind,
+            auction.closing_time(),
+            auction.current_price(),
+            auction.state()
+        );
+    }
+}
+
+pub async fn list_auctions() -> AuctionHouse {
+    let result = get_files_in_directory("auctions");
     match result {
         Ok(n) => {
             let auction_house = build_auctions_from_files(&n).await;
-            let mut bidding_price;
-            for auction in auction_house.auctions.iter() {
-                if auction.bids.is_empty() {
-                    bidding_price = auction.starting_bid;
-                } else {
-                    bidding_price = auction.bids[auction.bids.len() - 1].amount;
-                }
-                println!(
-                    "|{:<130} | {:<15} | {:<10} | {:<13} | {:<10}|",
-                    auction.signature,
-                    auction.item_name,
-                    auction.end_time,
-                    bidding_price,
-                    auction.active
-                );
-            }
+            print_auctions(&auction_house.auctions);
             auction_house
         }
         Err(_e) => {
@@ -130,6 +777,14 @@ pub async fn build_auctions_from_files(files: &Vec<std::string::String>) -> Auct
         let resudual_auction_house: AuctionHouse =
             serde_json::from_str(&data).expect("Failed to deserialize JSON");
         for auction in resudual_auction_house.auctions.iter() {
+            // the same auction can be listed by several peers
+            if major_auction
+                .auctions
+                .iter()
+                .any(|known| known.signature == auction.signature)
+            {
+                continue;
+            }
             major_auction.add_auction(auction.to_owned());
         }
     }
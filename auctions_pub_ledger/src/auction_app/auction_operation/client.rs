@@ -1,132 +1,275 @@
-use crate::auction_app::auction::{save_auction_data, Auction, AuctionHouse, Bid, Transaction};
+use crate::auction_app::auction::{
+    clear_auction_data, save_auction_data, Auction, AuctionHouse, Endpoint, Transaction,
+};
+use crate::auction_app::auction_operation::dht::discover_auctions;
 use crate::auction_app::user::{save_user_in_file, User};
-use crate::auction_tx::{
-    auction_tx_client::AuctionTxClient, CreateUsersRequest, GetAuctionsRequest, GetUsersRequest,
-    SubmitTransactionRequest, UpdateUsersRequest,
+use crate::auction_client::{
+    AuctionClient, AuctionClientBuilder, ClientError, Session, TlsConfig, DEFAULT_DOMAIN_NAME,
 };
+use crate::auction_server::auction_events::{AuctionEvent, EventFilter};
+use crate::auction_server::auction_handler::{from_response, Outcome, Rejection};
+use crate::auction_server::notification_outbox::SequencedNotification;
+use crate::auction_server::search::{AuctionQuery, SearchPage, MAX_PAGE_SIZE};
+use crate::auction_tx::{auction_tx_client::AuctionTxClient, SubmitTransactionRequest};
+use crate::cryptography::wallet::user_id;
+use chrono::{Duration, Utc};
+use k256::ecdsa::SigningKey;
 use serde::{Deserialize, Serialize};
-use tonic::transport::{Certificate, Channel, ClientTlsConfig};
+use std::collections::BTreeMap;
+use std::sync::{Mutex, OnceLock};
+use tokio_stream::Stream;
+use tonic::transport::Channel;
+use tonic::{Code, Status};
+
+// Most nodes a transaction goes through looking for the auction it targets
+pub const MAX_HOPS: u8 = 8;
+// CA of the network, read from the working directory
+const CA_CERTIFICATE: &str = "tls/rootCA.crt";
+// Seconds before it expires a session is no longer used
+const SESSION_MARGIN: i64 = 60;
+
+// Sessions of this process by node and user id, every node keeps its own sessions
+static SESSIONS: OnceLock<Mutex<BTreeMap<(String, String), Session>>> = OnceLock::new();
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TransactionInfo {
     pub subscriber_addrs: String,
     pub transaction: Transaction,
+    // hops left and nodes already visited while the transaction is forwarded
+    #[serde(default = "max_hops")]
+    pub ttl: u8,
+    #[serde(default)]
+    pub visited: Vec<String>,
 }
-// GRPC auction client
-pub async fn run_client(
-    dest_addr: &str,
-) -> Result<AuctionTxClient<Channel>, Box<dyn std::error::Error>> {
-    let ca = std::fs::read_to_string("tls/rootCA.crt")?;
-    let tls = ClientTlsConfig::new()
-        .ca_certificate(Certificate::from_pem(ca))
-        .domain_name("auctiondht.fc.up.pt");
-    let channel = Channel::builder(format!("https://{}:3000", dest_addr).parse().unwrap())
-        .tls_config(tls)
-        .unwrap()
-        .connect()
-        .await
-        .unwrap();
-
-    let client = AuctionTxClient::new(channel);
-
-    Ok(client)
-}
-pub async fn run_client_async(
-    dest_addr: String,
-) -> Result<AuctionTxClient<Channel>, Box<dyn std::error::Error + Send + Sync>> {
-    let ca = std::fs::read_to_string("tls/rootCA.crt")?;
-    let tls = ClientTlsConfig::new()
-        .ca_certificate(Certificate::from_pem(ca))
-        .domain_name("auctiondht.fc.up.pt");
-    let channel = Channel::builder(format!("https://{}:3000", dest_addr).parse().unwrap())
-        .tls_config(tls)
-        .unwrap()
-        .connect()
-        .await
-        .unwrap();
 
-    let client = AuctionTxClient::new(channel);
+fn max_hops() -> u8 {
+    MAX_HOPS
+}
 
-    Ok(client)
+// the node at `dest_addr`, trusted through the network CA
+fn node(dest_addr: &str) -> Result<AuctionClientBuilder, ClientError> {
+    Ok(AuctionClient::builder()
+        .endpoint(dest_addr)
+        .tls(TlsConfig::from_pem_file(
+            CA_CERTIFICATE,
+            DEFAULT_DOMAIN_NAME,
+        )?))
 }
+
+// GRPC auction client, nodes talk to each other through it
+pub async fn run_client(dest_addr: &str) -> Result<AuctionTxClient<Channel>, ClientError> {
+    node(dest_addr)?.build()?.connect().await
+}
+
 pub async fn send_transaction(
     data: Transaction,
     dest_addr: &str,
     subscriber_addr: String,
-) -> Result<String, Box<dyn std::error::Error>> {
-    let mut client = run_client(dest_addr).await?;
-
-    let transation_info: TransactionInfo = TransactionInfo {
-        subscriber_addrs: subscriber_addr,
-        transaction: data,
-    };
+) -> Result<Outcome, Box<dyn std::error::Error>> {
+    // a rejection comes back as the error, its reason is kept
+    let client = node(dest_addr)?
+        .notification_addr(&subscriber_addr)
+        .build()?;
+    Ok(client.submit_transaction(data).await?)
+}
 
-    let data_str = serde_json::to_string(&transation_info).unwrap();
-    let request = tonic::Request::new(SubmitTransactionRequest {
-        transaction: data_str,
-    });
+// Hand a transaction to a node. The status is an error only when the node could not be
+// reached or could not read the transaction, its verdict is the inner result.
+pub async fn relay_transaction(
+    transaction_info: &TransactionInfo,
+    dest_addr: &str,
+) -> Result<Result<Outcome, Rejection>, Status> {
+    let mut client = run_client(dest_addr)
+        .await
+        .map_err(|e| Status::unavailable(e.to_string()))?;
 
+    let request = tonic::Request::new(SubmitTransactionRequest::from(transaction_info.clone()));
     let response = client.submit_transaction(request).await?;
 
-    Ok(response.into_inner().message)
+    Ok(from_response(&response.into_inner()))
 }
 
+// Auctions are discovered through the DHT index, peers are only asked one by one when the
+// DHT can not be reached or holds no index yet. Either way a single deduplicated snapshot
+// replaces the old ones.
 pub async fn get_auction_house(peers: &Vec<String>) -> Result<(), Box<dyn std::error::Error>> {
-    for peer in peers {
-        let mut client = run_client(peer).await?;
-
-        let request = tonic::Request::new(GetAuctionsRequest {});
-        let response = client.get_auctions(request).await?;
+    let mut auctionshouse: AuctionHouse = AuctionHouse::new();
 
-        let mut auctionshouse: AuctionHouse = AuctionHouse::new();
+    match discover_auctions(&peers[0], None).await {
+        Ok(Some(auctions)) => auctionshouse.auctions = auctions,
+        discovery => {
+            match discovery {
+                Err(e) => println!("DHT discovery failed ({}), asking every peer", e),
+                _ => println!("No auction index in the DHT, asking every peer"),
+            }
+            for peer in peers {
+                for auction in node(peer)?.build()?.get_auctions().await? {
+                    if !auctionshouse
+                        .auctions
+                        .iter()
+                        .any(|known| known.signature == auction.signature)
+                    {
+                        auctionshouse.auctions.push(auction);
+                    }
+                }
+            }
+        }
+    }
 
-        let auctions_vector_str: Vec<String> =
-            serde_json::from_str(&response.into_inner().auctions)?;
+    clear_auction_data()?;
+    save_auction_data(&auctionshouse, "dht")?;
+    Ok(())
+}
 
-        for auction_str in auctions_vector_str {
-            let auction: Auction = serde_json::from_str(&auction_str).unwrap();
+pub async fn search_auctions(
+    peer: &str,
+    query: &AuctionQuery,
+) -> Result<SearchPage, Box<dyn std::error::Error>> {
+    Ok(node(peer)?.build()?.search_auctions(query).await?)
+}
 
-            auctionshouse.auctions.push(auction);
+// every auction of the peer matching the filters of `query`, page after page
+pub async fn search_every_page(
+    peer: &str,
+    query: &AuctionQuery,
+) -> Result<Vec<Auction>, Box<dyn std::error::Error>> {
+    let client = node(peer)?.build()?;
+    let mut auctions = Vec::new();
+    let mut query = AuctionQuery {
+        page: 0,
+        page_size: MAX_PAGE_SIZE,
+        ..query.clone()
+    };
+    loop {
+        let page = client.search_auctions(&query).await?;
+        let last = page.auctions.is_empty() || query.page + 1 >= page.page_count();
+        auctions.extend(page.auctions);
+        if last {
+            return Ok(auctions);
         }
-        save_auction_data(&auctionshouse, &peer.clone())?;
+        query.page += 1;
     }
-    //
-    Ok(())
 }
 
-pub async fn create_user(peer: &str, user_str: &str) -> Result<String, Box<dyn std::error::Error>> {
-    let mut client = run_client(peer).await?;
+// Follow auctions over this client's own connection, so it does not have to be reachable
+// by the nodes. The stream ends when the node drops the subscription.
+pub async fn subscribe_auction_events(
+    peer: &str,
+    filter: &EventFilter,
+) -> Result<impl Stream<Item = Result<AuctionEvent, ClientError>>, Box<dyn std::error::Error>> {
+    Ok(node(peer)?
+        .build()?
+        .subscribe_auction_events(filter)
+        .await?)
+}
 
-    let user = user_str.to_owned();
+// notifications a node queued for the user of `endpoint` after `since`, with the node
+// address they are numbered under. The node delivers the next ones to the endpoint.
+pub async fn catch_up_notifications(
+    peer: &str,
+    endpoint: &Endpoint,
+    since: u64,
+) -> Result<(String, Vec<SequencedNotification>), Box<dyn std::error::Error>> {
+    Ok(node(peer)?
+        .build()?
+        .catch_up_notifications(endpoint, since)
+        .await?)
+}
 
-    let request = tonic::Request::new(CreateUsersRequest { user });
-    let response = client.create_users(request).await?;
+// A session of the user with the node. The one of an earlier login is reused until it is
+// about to expire, a new one signs a fresh challenge of the node.
+pub async fn login(peer: &str, private_key: &SigningKey) -> Result<Session, ClientError> {
+    let sessions = SESSIONS.get_or_init(Default::default);
+    let key = (peer.to_string(), user_id(private_key));
+    let live = Utc::now() + Duration::seconds(SESSION_MARGIN);
+    if let Some(session) = sessions.lock().unwrap().get(&key) {
+        if session.expires_at > live {
+            return Ok(session.clone());
+        }
+    }
+    let session = node(peer)?
+        .signing_key(private_key.clone())
+        .build()?
+        .login()
+        .await?;
+    sessions.lock().unwrap().insert(key, session.clone());
+    Ok(session)
+}
 
-    Ok(response.into_inner().response)
+// forget the session of the user with the node, the node no longer knows it
+fn forget_session(peer: &str, private_key: &SigningKey) {
+    if let Some(sessions) = SESSIONS.get() {
+        let key = (peer.to_string(), user_id(private_key));
+        sessions.lock().unwrap().remove(&key);
+    }
 }
 
-pub async fn update_user(peer: &str, bid: &Bid) -> Result<String, Box<dyn std::error::Error>> {
-    let mut client = run_client(peer).await?;
+// Send `request` under the session of the user's login. A node that restarted lost its
+// sessions, the user logs in again once.
+async fn with_session<T, F, R>(
+    peer: &str,
+    private_key: &SigningKey,
+    request: F,
+) -> Result<T, ClientError>
+where
+    F: Fn(Session) -> R,
+    R: std::future::Future<Output = Result<T, ClientError>>,
+{
+    let session = login(peer, private_key).await?;
+    match request(session).await {
+        Err(ClientError::Status(status)) if status.code() == Code::Unauthenticated => {
+            forget_session(peer, private_key);
+            request(login(peer, private_key).await?).await
+        }
+        result => result,
+    }
+}
 
-    let bid_str = serde_json::to_string(bid).unwrap().to_owned();
+// Register the user with the node, under the session of the user's login
+pub async fn create_user(
+    peer: &str,
+    private_key: &SigningKey,
+    user: &User,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let client = &node(peer)?.signing_key(private_key.clone()).build()?;
+    Ok(with_session(peer, private_key, |session| async move {
+        client.create_user(&session, user).await
+    })
+    .await?)
+}
 
-    let request = tonic::Request::new(UpdateUsersRequest { bid_str });
-    let response = client.update_users(request).await?;
+// Add credits to the user's record, the node answers with the updated record
+pub async fn deposit(
+    peer: &str,
+    private_key: &SigningKey,
+    amount: f32,
+) -> Result<User, Box<dyn std::error::Error>> {
+    let client = &node(peer)?.signing_key(private_key.clone()).build()?;
+    Ok(with_session(peer, private_key, |session| async move {
+        client.deposit(&session, amount).await
+    })
+    .await?)
+}
 
-    Ok(response.into_inner().response)
+// Record a transaction the user signed in their activity
+pub async fn record_activity(
+    peer: &str,
+    private_key: &SigningKey,
+    transaction: &Transaction,
+) -> Result<User, Box<dyn std::error::Error>> {
+    let client = &node(peer)?.build()?;
+    Ok(with_session(peer, private_key, |session| async move {
+        client.record_activity(&session, transaction).await
+    })
+    .await?)
 }
 
 pub async fn get_user(peer: &str, id: &str) -> Result<User, Box<dyn std::error::Error>> {
-    let mut client = run_client(peer).await?;
-
-    let id = id.to_owned();
-
-    let request = tonic::Request::new(GetUsersRequest { id });
-    let response = client.get_users(request).await?.into_inner().user;
-    let user: User = serde_json::from_str(&response).unwrap();
+    let user = node(peer)?.build()?.get_user(id).await?;
     let file_path = format!("users/{}   .json", user.user_name);
 
-    save_user_in_file(&response, file_path).await;
+    let user_json = serde_json::to_string_pretty(&user)?;
+    save_user_in_file(&user_json, file_path).await;
 
     Ok(user)
 }
@@ -0,0 +1,144 @@
+use crate::auction_app::auction::Auction;
+use crate::auction_server::auction_handler::validate_tx_integrity;
+use crate::kademlia_node_search::node_functions::client::Client;
+use crate::kademlia_node_search::node_functions::crypto::Crypto;
+use futures::future::join_all;
+use ring::digest::{digest, SHA256};
+use ring::signature::{Ed25519KeyPair, KeyPair};
+
+// The app is not a kademlia node, it signs DHT requests with a throwaway keypair
+fn app_identity() -> Result<(Ed25519KeyPair, Vec<u8>), Box<dyn std::error::Error>> {
+    let keypair = Crypto::create_keypair()?;
+    let id = digest(&SHA256, keypair.public_key().as_ref())
+        .as_ref()
+        .to_vec();
+    Ok((keypair, id))
+}
+
+// DHT key of an auction record, hashed so it lives in the same space as node IDs
+pub fn auction_key(auction_signature: &str) -> Vec<u8> {
+    hex::decode(sha256::digest(format!("auction:{}", auction_signature))).unwrap()
+}
+
+// DHT key of a discovery index, a JSON list of auction IDs. `None` indexes every auction.
+pub fn index_key(category: Option<&str>) -> Vec<u8> {
+    let index = match category {
+        Some(category) => format!("category:{}", category.trim().to_lowercase()),
+        None => "auctions".to_string(),
+    };
+    hex::decode(sha256::digest(index)).unwrap()
+}
+
+pub async fn store_value(
+    peer: &str,
+    key: Vec<u8>,
+    value: Vec<u8>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (keypair, id) = app_identity()?;
+    let client = Client::new();
+
+    let request = client.create_store_node_request(&keypair, id, key, value);
+    let response = client
+        .send_store_request(request, format!("{}:50051", peer))
+        .await?;
+    if !response.success {
+        return Err(format!("peer {} refused to store the value", peer).into());
+    }
+
+    Ok(())
+}
+
+// `None` when no node along the lookup holds the key
+pub async fn find_value(
+    peer: &str,
+    key: Vec<u8>,
+) -> Result<Option<Vec<u8>>, Box<dyn std::error::Error>> {
+    let (keypair, id) = app_identity()?;
+    let client = Client::new();
+
+    let request = client.create_find_value_request(&keypair, id, key);
+    let response = client
+        .send_find_value_request(request, format!("{}:50051", peer))
+        .await?;
+
+    Ok(Some(response.value).filter(|value| !value.is_empty()))
+}
+
+// Store content in the DHT under its sha256 hash, the returned hex hash references it
+pub async fn store_content(
+    peer: &str,
+    content: Vec<u8>,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let content_hash = sha256::digest(content.as_slice());
+    store_value(peer, hex::decode(&content_hash)?, content).await?;
+
+    Ok(content_hash)
+}
+
+// Look up content by its hex hash, content not matching the hash is rejected
+pub async fn find_content(
+    peer: &str,
+    content_hash: &str,
+) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let content = find_value(peer, hex::decode(content_hash)?)
+        .await?
+        .ok_or_else(|| format!("content {} not found in the DHT", content_hash))?;
+    if sha256::digest(content.as_slice()) != content_hash {
+        return Err(format!("content {} does not match its hash", content_hash).into());
+    }
+
+    Ok(content)
+}
+
+// Auction record published by the nodes holding it, records without a valid seller
+// signature are rejected
+pub async fn find_auction(
+    peer: &str,
+    auction_signature: &str,
+) -> Result<Auction, Box<dyn std::error::Error>> {
+    let record = find_value(peer, auction_key(auction_signature))
+        .await?
+        .ok_or_else(|| format!("auction {} not found in the DHT", auction_signature))?;
+    let auction: Auction = serde_json::from_slice(&record)?;
+    if auction.signature != auction_signature
+        || !validate_tx_integrity(
+            &auction.signed_content(),
+            &auction.user_id,
+            auction.signature.clone(),
+        )
+        .await?
+    {
+        return Err(format!("auction record {} was tampered with", auction_signature).into());
+    }
+
+    Ok(auction)
+}
+
+// Every auction listed in a discovery index, `None` when no node holds the index. Records
+// are looked up all at once, unreachable or invalid ones are skipped.
+pub async fn discover_auctions(
+    peer: &str,
+    category: Option<&str>,
+) -> Result<Option<Vec<Auction>>, Box<dyn std::error::Error>> {
+    let auction_signatures: Vec<String> = match find_value(peer, index_key(category)).await? {
+        Some(index) => serde_json::from_slice(&index)?,
+        None => return Ok(None),
+    };
+
+    let records = join_all(
+        auction_signatures
+            .iter()
+            .map(|auction_signature| find_auction(peer, auction_signature)),
+    )
+    .await;
+
+    let mut auctions = Vec::new();
+    for (auction_signature, record) in auction_signatures.iter().zip(records) {
+        match record {
+            Ok(auction) => auctions.push(auction),
+            Err(e) => println!("Skipping auction {}: {}", auction_signature, e),
+        }
+    }
+
+    Ok(Some(auctions))
+}
@@ -1,20 +1,53 @@
 use crate::auction_app::auction::AuctionHouse;
-use crate::auction_app::auction::Bid;
+use crate::auction_app::auction::{Auction, Bid, BidCommitment, Endpoint, Transaction};
 use crate::auction_app::auction_operation::client::TransactionInfo;
-use crate::auction_app::user::{load_users_from_file, save_user_in_file, User};
-use crate::auction_server::auction_handler::transaction_handler;
+use crate::auction_app::auction_operation::dht::auction_key;
+use crate::auction_app::user::{load_users_from_file, User, UserOperation};
+use crate::auction_server::auction_directory::publish_auction;
+use crate::auction_server::auction_events::{
+    publish_changes, EventFilter, EventSender, EVENT_BUFFER,
+};
+use crate::auction_server::auction_handler::{
+    check_endpoint, to_response, transaction_handler, Applied, Route,
+};
+use crate::auction_server::auction_notifications::bid_notifications;
+use crate::auction_server::notification_outbox::{queue_notifications, SharedOutbox};
+use crate::auction_server::replication::{
+    apply_record, is_replica, listing, merge_auction, replicate, verified_record,
+};
+use crate::auction_server::search::{search, AuctionQuery};
+use crate::auction_server::settlement::Forfeit;
+use crate::auction_server::user_ledger::{
+    apply_operation, charge_award, charge_forfeit, hold_deposit, release_deposit, AwardCharge,
+    SharedLedgerQueue, LEDGER_FILE,
+};
+use crate::auction_server::user_sessions::{SharedSessions, UserSessions};
 use crate::auction_tx::auction_tx_server::AuctionTx;
 use crate::auction_tx::auction_tx_server::AuctionTxServer;
 use crate::auction_tx::{
-    CreateUsersRequest, CreateUsersResponse, GetAuctionsRequest, GetAuctionsResponse,
-    GetUsersRequest, GetUsersResponse, SubmitTransactionRequest, SubmitTransactionResponse,
-    UpdateUsersRequest, UpdateUsersResponse,
+    ApplyUserOperationRequest, ApplyUserOperationResponse, AuctionEvent,
+    CatchUpNotificationsRequest, CatchUpNotificationsResponse, ChargeDepositRequest,
+    ChargeDepositResponse, CreateUsersRequest, CreateUsersResponse, GetAuctionsRequest,
+    GetAuctionsResponse, GetUsersRequest, GetUsersResponse, HoldDepositRequest,
+    HoldDepositResponse, LoginRequest, LoginResponse, ReleaseDepositRequest,
+    ReleaseDepositResponse, ReplicateAuctionRequest, ReplicateAuctionResponse,
+    RequestChallengeRequest, RequestChallengeResponse, SearchAuctionsRequest,
+    SearchAuctionsResponse, SubmitTransactionRequest, SubmitTransactionResponse,
+    SubscribeAuctionEventsRequest, UpdateUsersRequest, UpdateUsersResponse,
 };
+use crate::auction_types as proto;
+use crate::conversions::{required, DecodeError};
 use crate::kademlia_node_search::node::Node;
-use crate::kademlia_node_search::node_functions::routing_table::Bucket;
-use std::fs::{self};
+use bytes::Bytes;
+use chrono::Utc;
+use k256::ecdsa::VerifyingKey;
+use std::net::IpAddr;
+use std::pin::Pin;
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{mpsc, Mutex};
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_stream::Stream;
 use tonic::{
     transport::{Identity, Server, ServerTlsConfig},
     Request, Response, Status,
@@ -26,6 +59,110 @@ type AuctionResult<T> = Result<Response<T>, Status>;
 pub struct AuctionsTxServer {
     shared_auction_house_state: Arc<Mutex<AuctionHouse>>,
     shared_kademlia_node: Arc<Mutex<Node>>,
+    events: EventSender,
+    outbox: SharedOutbox,
+    sessions: SharedSessions,
+    ledger: SharedLedgerQueue,
+    // user records are read, changed and written back one at a time
+    user_records: Arc<Mutex<()>>,
+}
+
+fn user_file(user_id: &str) -> String {
+    format!("users/{}.json", user_id)
+}
+
+// user IDs are hex encoded keys, anything else never names a record file
+async fn load_user(user_id: &str) -> Result<User, Status> {
+    if user_id.is_empty() || hex::decode(user_id).is_err() {
+        return Err(Status::invalid_argument(format!(
+            "user {} is not a valid user ID",
+            user_id
+        )));
+    }
+    load_users_from_file(&user_file(user_id))
+        .await
+        .map_err(|e| Status::not_found(format!("user {}: {}", user_id, e)))
+}
+
+async fn save_user(user: &User) -> Result<(), Status> {
+    let user_json = serde_json::to_string_pretty(user)
+        .map_err(|e| Status::internal(format!("user {}: {}", user.uid, e)))?;
+    tokio::fs::write(user_file(&user.uid), user_json)
+        .await
+        .map_err(|e| Status::internal(format!("user {} could not be saved: {}", user.uid, e)))
+}
+
+impl AuctionsTxServer {
+    // whether the request comes from a replica of the auction
+    async fn check_replica(
+        &self,
+        sender: Option<IpAddr>,
+        auction_signature: &str,
+    ) -> Result<(), Status> {
+        let from_replica = match sender {
+            Some(ip) => is_replica(&self.shared_kademlia_node, auction_signature, ip).await,
+            None => false,
+        };
+        if !from_replica {
+            return Err(Status::permission_denied(
+                "only replicas of the auction send its records and charges",
+            ));
+        }
+        Ok(())
+    }
+
+    // The auction a charge or a deposit is for. The node keeping the user records is not
+    // always a replica of it, so the replica sends its record along. Only replicas charge,
+    // the record only counts as far as its signatures go and completes the copy of this
+    // node, if any. The merged copy is verified again, whatever way its bids came in, so a
+    // bid nobody signed is never charged.
+    async fn charged_auction(
+        &self,
+        sender: Option<IpAddr>,
+        record: Option<proto::Auction>,
+    ) -> Result<Auction, Status> {
+        let record = Auction::try_from(required(record, "auction")?)?;
+        self.check_replica(sender, &record.signature).await?;
+        let record = verified_record(record)
+            .await
+            .ok_or_else(|| Status::invalid_argument("auction signature is not valid"))?;
+
+        let auction_house = self.shared_auction_house_state.lock().await;
+        let mut auction = match auction_house
+            .auctions
+            .iter()
+            .find(|auction| auction.signature == record.signature)
+        {
+            Some(auction) => auction.clone(),
+            None => listing(&record),
+        };
+        merge_auction(&mut auction, &record);
+        drop(auction_house);
+        verified_record(auction)
+            .await
+            .ok_or_else(|| Status::invalid_argument("auction signature is not valid"))
+    }
+
+    // The endpoint the signer proved to be at when catching up goes along with the auction
+    // they transacted on, so the replica notifying them knows where they are. Addresses
+    // sent along with transactions are not signed and never used.
+    async fn with_endpoint(&self, auction: &Auction, signer: &str) -> Auction {
+        let Some(endpoint) = self.outbox.lock().await.endpoints.get(signer).cloned() else {
+            return auction.clone();
+        };
+        let mut auction_house = self.shared_auction_house_state.lock().await;
+        match auction_house
+            .auctions
+            .iter_mut()
+            .find(|known| known.signature == auction.signature)
+        {
+            Some(known) => {
+                known.record_endpoint(&endpoint);
+                known.clone()
+            }
+            None => auction.clone(),
+        }
+    }
 }
 
 #[tonic::async_trait]
@@ -34,33 +171,64 @@ impl AuctionTx for AuctionsTxServer {
         &self,
         request: Request<SubmitTransactionRequest>,
     ) -> AuctionResult<SubmitTransactionResponse> {
-        let message = request.into_inner().clone().transaction;
+        let transaction_info = TransactionInfo::try_from(request.into_inner())?;
         let shared_node = self.shared_kademlia_node.clone();
 
-        let routing_table = <Vec<Bucket> as Clone>::clone(
-            &shared_node.lock().await.routing_table.lock().await.buckets,
-        )
-        .into_iter()
-        .map(|x| {
-            x.nodes
+        let auction_signature = transaction_info.transaction.auction_signature().to_string();
+        // peers are reached on the auction port, only their address is kept
+        let route = {
+            let node_lock = shared_node.lock().await;
+            let closest = node_lock
+                .routing_table
+                .lock()
+                .await
+                .find_closest(&Bytes::from(auction_key(&auction_signature)))
                 .into_iter()
-                .map(|node_info| node_info.addr.to_string())
-                .collect::<Vec<String>>()
-        })
-        .flatten()
-        .collect::<Vec<String>>();
+                .map(|node_info| node_info.addr.ip().to_string())
+                .collect();
+            Route {
+                ttl: transaction_info.ttl,
+                visited: transaction_info.visited,
+                own_addr: node_lock.addr.ip().to_string(),
+                closest,
+            }
+        };
 
-        let transaction_info: TransactionInfo =
-            serde_json::from_str(&message).expect("Error getting transaction info");
+        // subscribers hear about open bids, commitments and reveals stay quiet
+        let bid_signature = match &transaction_info.transaction {
+            Transaction::Bid(bid) => Some(bid.signature.clone()),
+            _ => None,
+        };
+        let signer = transaction_info.transaction.signer().to_string();
 
-        transaction_handler(
+        let result = transaction_handler(
             transaction_info.transaction,
             &mut self.shared_auction_house_state.clone(),
             transaction_info.subscriber_addrs,
-            routing_table,
+            route,
         )
         .await;
-        Ok(Response::new(SubmitTransactionResponse { message }))
+
+        // republish and replicate the auction if this node applied the transaction, forwarded
+        // ones are handled by the node that applied them
+        if let Ok(Applied::Here { before, after }) = &result {
+            publish_changes(&self.events, before.as_deref(), after);
+            let record = self.with_endpoint(after, &signer).await;
+            tokio::spawn(publish_auction(shared_node.clone(), record.clone()));
+            tokio::spawn(replicate(shared_node, record));
+
+            if let (Some(before), Some(bid)) = (
+                before.as_deref(),
+                bid_signature
+                    .and_then(|signature| after.bids.iter().find(|bid| bid.signature == signature)),
+            ) {
+                queue_notifications(&self.outbox, after, bid_notifications(before, after, bid))
+                    .await;
+            }
+        }
+        Ok(Response::new(to_response(
+            &result.as_ref().map(Applied::outcome).map_err(Clone::clone),
+        )))
     }
     async fn get_auctions(
         &self,
@@ -68,26 +236,41 @@ impl AuctionTx for AuctionsTxServer {
     ) -> AuctionResult<GetAuctionsResponse> {
         let auction_house_state = &self.shared_auction_house_state.lock().await;
 
-        let mut auction_house_str: Vec<String> = Vec::new();
-        for auction in auction_house_state.auctions.clone() {
-            auction_house_str.push(serde_json::to_string(&auction).unwrap())
-        }
-        let auctions = serde_json::to_string(&auction_house_str).unwrap();
+        let auction_list = auction_house_state
+            .auctions
+            .iter()
+            .cloned()
+            .map(Into::into)
+            .collect();
 
-        Ok(Response::new(GetAuctionsResponse { auctions }))
+        Ok(Response::new(GetAuctionsResponse { auction_list }))
     }
     async fn create_users(
         &self,
         request: Request<CreateUsersRequest>,
     ) -> AuctionResult<CreateUsersResponse> {
-        let user_str_request = request.into_inner().clone().user;
+        let request = request.into_inner();
+        let account = User::from(required(request.account, "account")?);
 
-        let user: User = serde_json::from_str(&user_str_request).unwrap();
-        let file_path = format!("users/{}.json", user.uid);
+        // only the user can register their record, proven by a session they logged in for
+        self.sessions
+            .lock()
+            .await
+            .authorize(&request.session, &account.uid, Utc::now())
+            .map_err(|e| Status::unauthenticated(e.to_string()))?;
 
-        save_user_in_file(&user_str_request, file_path).await;
+        // credits and activity only come from operations applied by this node
+        let _records = self.user_records.lock().await;
+        if std::path::Path::new(&user_file(&account.uid)).exists() {
+            return Err(Status::already_exists(format!(
+                "user {} is already registered",
+                account.uid
+            )));
+        }
+        let user = User::new(account.user_name, account.uid);
+        save_user(&user).await?;
         let response = format!(
-            "User {} was successfully created/updated into bootstrap node\n",
+            "User {} was successfully created into bootstrap node\n",
             &user.uid
         );
 
@@ -97,44 +280,299 @@ impl AuctionTx for AuctionsTxServer {
         &self,
         request: Request<UpdateUsersRequest>,
     ) -> AuctionResult<UpdateUsersResponse> {
-        let bid_str_request = request.into_inner().clone().bid_str;
+        let sender = request.remote_addr().map(|addr| addr.ip());
+        let request = request.into_inner();
+        let bid = Bid::try_from(request.bid.ok_or(DecodeError::MissingField("bid"))?)?;
+        let auction = self.charged_auction(sender, request.auction).await?;
 
-        let bid: Bid = serde_json::from_str(&bid_str_request).unwrap();
-        let file_path = format!("users/{}.json", bid.bidder);
+        let _records = self.user_records.lock().await;
+        let mut user = load_user(&bid.bidder).await?;
+        let charged = charge_award(&mut user, &bid, &[auction], Utc::now())?;
+        save_user(&user).await?;
 
-        let mut user: User = load_users_from_file(&file_path)
-            .await
-            .expect("error reading user from file");
+        let response = match charged {
+            AwardCharge::Paid(price) => format!("User {} was charged {}\n", &user.uid, price),
+            AwardCharge::Forfeited(price) => format!(
+                "Credits of user {} do not cover {}, the award was forfeited\n",
+                &user.uid, price
+            ),
+        };
+
+        Ok(Response::new(UpdateUsersResponse { response }))
+    }
+    async fn charge_deposit(
+        &self,
+        request: Request<ChargeDepositRequest>,
+    ) -> AuctionResult<ChargeDepositResponse> {
+        let sender = request.remote_addr().map(|addr| addr.ip());
+        let request = request.into_inner();
+        let forfeit = Forfeit::from(
+            request
+                .deposit
+                .ok_or(DecodeError::MissingField("deposit"))?,
+        );
+        let auction = self.charged_auction(sender, request.auction).await?;
 
-        user.credits = user.credits - bid.amount;
-        user.auctions_winner.push(bid.auction_signature);
-        let user_json = serde_json::to_string_pretty(&user).expect("Failed to serialize users");
+        let _records = self.user_records.lock().await;
+        let mut user = load_user(&forfeit.bidder).await?;
+        charge_forfeit(&mut user, &forfeit, &[auction], Utc::now())?;
+        save_user(&user).await?;
 
-        save_user_in_file(&user_json, file_path).await;
+        let response = format!("Deposit of user {} was forfeited\n", &user.uid);
 
-        let response = format!(
-            "User {} was successfully created/updated into bootstrap node\n",
-            &user.uid
-        );
+        Ok(Response::new(ChargeDepositResponse { response }))
+    }
+    async fn hold_deposit(
+        &self,
+        request: Request<HoldDepositRequest>,
+    ) -> AuctionResult<HoldDepositResponse> {
+        let sender = request.remote_addr().map(|addr| addr.ip());
+        let request = request.into_inner();
+        let commitment = BidCommitment::from(required(request.commitment, "commitment")?);
+        let auction = self.charged_auction(sender, request.auction).await?;
 
-        Ok(Response::new(UpdateUsersResponse { response }))
+        let _records = self.user_records.lock().await;
+        let mut user = load_user(&commitment.bidder).await?;
+        let deposit = hold_deposit(&mut user, &commitment, &auction).await?;
+        save_user(&user).await?;
+
+        let response = format!("Deposit of {} held for user {}\n", deposit, &user.uid);
+
+        Ok(Response::new(HoldDepositResponse { response }))
+    }
+    async fn release_deposit(
+        &self,
+        request: Request<ReleaseDepositRequest>,
+    ) -> AuctionResult<ReleaseDepositResponse> {
+        let sender = request.remote_addr().map(|addr| addr.ip());
+        let request = request.into_inner();
+        let auction = self.charged_auction(sender, request.auction).await?;
+
+        let _records = self.user_records.lock().await;
+        let mut user = load_user(&request.bidder).await?;
+        let deposit = release_deposit(
+            &mut user,
+            std::slice::from_ref(&auction),
+            &auction.signature,
+            Utc::now(),
+        )?;
+        save_user(&user).await?;
+
+        let response = format!("Deposit of {} returned to user {}\n", deposit, &user.uid);
+
+        Ok(Response::new(ReleaseDepositResponse { response }))
     }
     async fn get_users(
         &self,
         request: Request<GetUsersRequest>,
     ) -> AuctionResult<GetUsersResponse> {
         let id = request.into_inner().clone().id;
+        let user = load_user(&id).await?;
+
+        Ok(Response::new(GetUsersResponse {
+            account: Some(user.into()),
+        }))
+    }
+    async fn search_auctions(
+        &self,
+        request: Request<SearchAuctionsRequest>,
+    ) -> AuctionResult<SearchAuctionsResponse> {
+        // an empty request asks for the first page of every auction
+        let query = match request.into_inner().filter {
+            Some(filter) => AuctionQuery::try_from(filter)?,
+            None => AuctionQuery::default(),
+        };
+
+        let auction_house_state = self.shared_auction_house_state.lock().await;
+        let results = search(&auction_house_state.auctions, &query).into();
+
+        Ok(Response::new(SearchAuctionsResponse {
+            results: Some(results),
+        }))
+    }
+    async fn replicate_auction(
+        &self,
+        request: Request<ReplicateAuctionRequest>,
+    ) -> AuctionResult<ReplicateAuctionResponse> {
+        let sender = request.remote_addr().map(|addr| addr.ip());
+        let record = request.into_inner().record;
+        let auction = Auction::try_from(record.ok_or(DecodeError::MissingField("record"))?)?;
+        self.check_replica(sender, &auction.signature).await?;
+        let record = verified_record(auction)
+            .await
+            .ok_or_else(|| Status::invalid_argument("auction signature is not valid"))?;
+
+        let signature = record.signature.clone();
+        let mut auction_house = self.shared_auction_house_state.lock().await;
+        let before = auction_house
+            .auctions
+            .iter()
+            .find(|auction| auction.signature == signature)
+            .cloned();
+        let updated = apply_record(&mut auction_house, record);
+        if updated {
+            println!("Replica of auction {} updated\n", signature);
+            if let Some(after) = auction_house
+                .auctions
+                .iter()
+                .find(|auction| auction.signature == signature)
+            {
+                publish_changes(&self.events, before.as_ref(), after);
+            }
+        }
+
+        Ok(Response::new(ReplicateAuctionResponse { updated }))
+    }
+
+    type SubscribeAuctionEventsStream =
+        Pin<Box<dyn Stream<Item = Result<AuctionEvent, Status>> + Send>>;
+
+    // each subscriber gets its own relay task, it stops when the client goes away
+    async fn subscribe_auction_events(
+        &self,
+        request: Request<SubscribeAuctionEventsRequest>,
+    ) -> AuctionResult<Self::SubscribeAuctionEventsStream> {
+        let filter = EventFilter::from(request.into_inner());
+        let mut events = self.events.subscribe();
+        let (sender, receiver) = mpsc::channel(EVENT_BUFFER);
+
+        tokio::spawn(async move {
+            loop {
+                let message = match events.recv().await {
+                    Ok(event) if filter.matches(&event) => Ok(event.into()),
+                    Ok(_) => continue,
+                    Err(RecvError::Lagged(missed)) => Err(Status::data_loss(format!(
+                        "subscriber fell behind and missed {} events",
+                        missed
+                    ))),
+                    Err(RecvError::Closed) => break,
+                };
+                let lagged = message.is_err();
+                if sender.send(message).await.is_err() || lagged {
+                    break;
+                }
+            }
+        });
+
+        Ok(Response::new(Box::pin(ReceiverStream::new(receiver))))
+    }
+    async fn catch_up_notifications(
+        &self,
+        request: Request<CatchUpNotificationsRequest>,
+    ) -> AuctionResult<CatchUpNotificationsResponse> {
+        let request = request.into_inner();
+        let origin = self.shared_kademlia_node.lock().await.addr.ip().to_string();
+
+        // only the subscriber can read their notifications, the endpoint they signed is
+        // where the next ones go
+        let endpoint = Endpoint::try_from(required(request.endpoint, "endpoint")?)?;
+        if endpoint.user_id != request.subscriber {
+            return Err(Status::permission_denied("endpoint signed by another user"));
+        }
+        check_endpoint(&endpoint, Utc::now())
+            .await
+            .map_err(|rejection| Status::permission_denied(rejection.to_string()))?;
+
+        let mut outbox = self.outbox.lock().await;
+        outbox.remember(&endpoint);
+        let notifications = outbox
+            .catch_up(&request.subscriber, request.since)
+            .into_iter()
+            .map(Into::into)
+            .collect();
+        outbox.persist().await;
+
+        Ok(Response::new(CatchUpNotificationsResponse {
+            origin,
+            notifications,
+        }))
+    }
+    async fn request_challenge(
+        &self,
+        request: Request<RequestChallengeRequest>,
+    ) -> AuctionResult<RequestChallengeResponse> {
+        let user_id = request.into_inner().user_id;
+        // challenges are only kept for ids that are public keys
+        hex::decode(&user_id)
+            .ok()
+            .and_then(|bytes| VerifyingKey::from_sec1_bytes(&bytes).ok())
+            .ok_or_else(|| Status::invalid_argument("user id is not a public key"))?;
+
+        let (nonce, expires_at) = self
+            .sessions
+            .lock()
+            .await
+            .issue_challenge(&user_id, Utc::now())
+            .map_err(|e| Status::resource_exhausted(e.to_string()))?;
 
-        let file_path = format!("users/{}.json", id);
+        Ok(Response::new(RequestChallengeResponse {
+            nonce,
+            expires_at: expires_at.timestamp(),
+        }))
+    }
+    async fn login(&self, request: Request<LoginRequest>) -> AuctionResult<LoginResponse> {
+        let request = request.into_inner();
+        let (session, expires_at) = self
+            .sessions
+            .lock()
+            .await
+            .login(
+                &request.user_id,
+                &request.nonce,
+                &request.signature,
+                Utc::now(),
+            )
+            .map_err(|e| Status::unauthenticated(e.to_string()))?;
+
+        Ok(Response::new(LoginResponse {
+            session,
+            expires_at: expires_at.timestamp(),
+        }))
+    }
+    async fn apply_user_operation(
+        &self,
+        request: Request<ApplyUserOperationRequest>,
+    ) -> AuctionResult<ApplyUserOperationResponse> {
+        let request = request.into_inner();
+        let operation = UserOperation::try_from(required(request.operation, "operation")?)?;
+
+        // only the user changes their record, proven by a session they logged in for
+        self.sessions
+            .lock()
+            .await
+            .authorize(&request.session, operation.user_id(), Utc::now())
+            .map_err(|e| Status::unauthenticated(e.to_string()))?;
 
-        let user = fs::read_to_string(file_path).unwrap();
+        let _records = self.user_records.lock().await;
+        let mut user = load_user(operation.user_id()).await?;
+        {
+            let auction_house = self.shared_auction_house_state.lock().await;
+            apply_operation(&mut user, &operation, &auction_house.auctions, Utc::now()).await?;
+        }
+        // credits never change without the deposit saved for the next block first
+        if let UserOperation::Deposit(_) = operation {
+            self.ledger
+                .lock()
+                .await
+                .record(LEDGER_FILE, serde_json::to_string(&operation).unwrap())
+                .await
+                .map_err(|e| {
+                    Status::unavailable(format!("deposit could not be recorded: {}", e))
+                })?;
+        }
+        save_user(&user).await?;
 
-        Ok(Response::new(GetUsersResponse { user }))
+        Ok(Response::new(ApplyUserOperationResponse {
+            account: Some(user.into()),
+        }))
     }
 }
 pub async fn auction_server(
     shared_auction_house: Arc<Mutex<AuctionHouse>>,
     kademlia_node: Arc<Mutex<Node>>,
+    events: EventSender,
+    outbox: SharedOutbox,
+    ledger: SharedLedgerQueue,
 ) {
     let cert = std::fs::read_to_string("tls/server.crt");
     let key = std::fs::read_to_string("tls/server.key");
@@ -148,6 +586,11 @@ pub async fn auction_server(
         .add_service(AuctionTxServer::new(AuctionsTxServer {
             shared_auction_house_state: shared_auction_house,
             shared_kademlia_node: kademlia_node,
+            events,
+            outbox,
+            sessions: Arc::new(Mutex::new(UserSessions::default())),
+            ledger,
+            user_records: Arc::new(Mutex::new(())),
         }))
         .serve(addr)
         .await
@@ -0,0 +1,682 @@
+use crate::auction_app::auction::{
+    load_sealed_bids, save_sealed_bids, Auction, AuctionCancel, AuctionKind, AuctionRules, Bid,
+    BidCommitment, BidIncrement, BidReveal, DutchAccept, Endpoint, Pricing, SoftClose,
+    Subscription, Transaction,
+};
+use crate::auction_app::auction_operation::client::{
+    catch_up_notifications, create_user, deposit, get_user, login as node_login, record_activity,
+    search_auctions, send_transaction,
+};
+use crate::auction_app::auction_operation::dht::find_auction;
+use crate::auction_app::notifications::notify_server::{load_sequences, record_sequence};
+use crate::auction_app::user::{
+    create_account, imports_key_file, load_users_from_file, read_new_passphrase, read_passphrase,
+    unlock_account, User,
+};
+use crate::auction_server::auction_handler::Outcome;
+use crate::auction_server::search::{AuctionQuery, SortKey, StateFilter};
+use crate::cryptography::wallet::{Wallet, WALLET_FILE};
+use chrono::{DateTime, Duration, Utc};
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use k256::ecdsa::{signature::Signer, Signature, SigningKey};
+use local_ip_address::local_ip;
+use rand_core::{OsRng, RngCore};
+use serde_json::{json, Value};
+use std::error::Error;
+
+type CliResult = Result<Value, Box<dyn Error>>;
+
+// Non-interactive commands of the auction client, for scripts and load tests. Every command
+// prints one JSON document, the interactive menus run when no command is given.
+pub fn command() -> Command<'static> {
+    Command::new("auction_app")
+        .about("BidBuddie's auction client, interactive when no command is given")
+        .arg(
+            Arg::new("peers")
+                .help("Comma separated addresses of the nodes to talk to")
+                .value_parser(clap::value_parser!(String))
+                .required(true)
+                .index(1),
+        )
+        .arg(
+            Arg::new("bootstrap")
+                .long("bootstrap")
+                .help("Address of the node keeping the user accounts")
+                .value_parser(clap::value_parser!(String))
+                .default_value("10.10.0.2"),
+        )
+        .arg(
+            text("wallet", "Wallet holding the encrypted signing keys", false)
+                .default_value(WALLET_FILE)
+                .global(true),
+        )
+        .subcommand(
+            Command::new("register")
+                .about("Create a key pair in the wallet and register a new user")
+                .arg(text("name", "User name", true))
+                .arg(passphrase()),
+        )
+        .subcommand(
+            Command::new("login")
+                .about("Unlock a user and fetch the notifications missed while offline")
+                .arg(text("name", "User name", true))
+                .arg(passphrase()),
+        )
+        .subcommand(
+            Command::new("create-auction")
+                .about("Put an item up for auction")
+                .arg(user())
+                .arg(passphrase())
+                .arg(text("item", "Item name", true))
+                .arg(
+                    text("kind", "Auction type", false)
+                        .value_parser(["english", "sealed", "dutch"])
+                        .default_value("english"),
+                )
+                .arg(number::<f32>("starting-bid", "Starting bid").default_value("1"))
+                .arg(number::<i64>("minutes", "Duration in minutes").required(true))
+                .arg(number::<i64>("start-in", "Minutes until bidding opens").default_value("0"))
+                .arg(number::<u32>("quantity", "Identical units on sale").default_value("1"))
+                .arg(text(
+                    "lot",
+                    "Items bundled in the lot, comma separated",
+                    false,
+                ))
+                .arg(text("category", "Category", false))
+                .arg(text("description", "Description", false))
+                .arg(number::<f32>("reserve-price", "Reserve price").default_value("0"))
+                .arg(
+                    text("min-increment", "Minimum increment, e.g. 5 or 5%", false)
+                        .default_value("1"),
+                )
+                .arg(
+                    text("pricing", "Price paid by the winners", false)
+                        .value_parser(["first", "second", "uniform"])
+                        .default_value("first"),
+                )
+                .arg(number::<f32>(
+                    "buy-now",
+                    "Buy now price of an English auction",
+                ))
+                .arg(number::<i64>(
+                    "soft-close-window",
+                    "Soft close window in seconds",
+                ))
+                .arg(
+                    number::<i64>("extension", "Seconds a late bid extends the auction by")
+                        .default_value("60"),
+                )
+                .arg(
+                    number::<i64>("max-extension", "Maximum total extension in seconds")
+                        .default_value("600"),
+                )
+                .arg(number::<f32>("deposit", "Deposit of a sealed-bid auction").default_value("0"))
+                .arg(
+                    number::<i64>("reveal-minutes", "Reveal period of a sealed-bid auction")
+                        .default_value("10"),
+                )
+                .arg(number::<f32>(
+                    "start-price",
+                    "Start price of a Dutch auction",
+                ))
+                .arg(number::<f32>(
+                    "decrement",
+                    "Price drop per step of a Dutch auction",
+                ))
+                .arg(
+                    number::<i64>("decrement-minutes", "Minutes between price drops")
+                        .default_value("1"),
+                )
+                .arg(number::<f32>(
+                    "floor-price",
+                    "Floor price of a Dutch auction",
+                )),
+        )
+        .subcommand(
+            Command::new("bid")
+                .about("Bid on an auction, commit a sealed bid or accept a Dutch price")
+                .arg(user())
+                .arg(passphrase())
+                .arg(text("auction", "Auction ID", true))
+                .arg(
+                    number::<f32>("amount", "Bid amount, ignored for Dutch auctions")
+                        .default_value("0"),
+                ),
+        )
+        .subcommand(
+            Command::new("reveal")
+                .about("Reveal the sealed bids kept locally, the rejected ones are kept")
+                .arg(user())
+                .arg(text(
+                    "auction",
+                    "Only reveal the bid on this auction",
+                    false,
+                )),
+        )
+        .subcommand(
+            Command::new("cancel")
+                .about("Cancel an auction of yours")
+                .arg(user())
+                .arg(passphrase())
+                .arg(text("auction", "Auction ID", true)),
+        )
+        .subcommand(
+            Command::new("list")
+                .about("Search the auctions held by the first peer")
+                .arg(
+                    text("state", "Auction state", false)
+                        .value_parser(["any", "active", "closed"])
+                        .default_value("any"),
+                )
+                .arg(text("seller", "Seller uid", false))
+                .arg(text("category", "Category", false))
+                .arg(
+                    text("sort", "Sort order", false)
+                        .value_parser(["ending", "lowest", "highest", "newest"])
+                        .default_value("ending"),
+                )
+                .arg(number::<usize>("page", "Page, starting at 0").default_value("0"))
+                .arg(number::<usize>("page-size", "Auctions per page").default_value("20")),
+        )
+        .subcommand(
+            Command::new("history")
+                .about("Auction activity of a user")
+                .arg(user()),
+        )
+        .subcommand(
+            Command::new("add-credits")
+                .about("Add credits to a user")
+                .arg(user())
+                .arg(passphrase())
+                .arg(number::<f32>("amount", "Credits to add").required(true)),
+        )
+        .subcommand(
+            Command::new("watch")
+                .about("Follow an auction without bidding, or stop following it")
+                .arg(user())
+                .arg(passphrase())
+                .arg(text("auction", "Auction ID", true))
+                .arg(
+                    Arg::new("stop")
+                        .long("stop")
+                        .help("Unsubscribe instead")
+                        .action(ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("wallet")
+                .about("Manage the accounts of the wallet")
+                .subcommand_required(true)
+                .subcommand(Command::new("list").about("Accounts in the wallet"))
+                .subcommand(
+                    Command::new("export")
+                        .about("Print an account, its key stays encrypted")
+                        .arg(user()),
+                )
+                .subcommand(
+                    Command::new("import")
+                        .about("Add an exported account to the wallet")
+                        .arg(text("file", "File holding the exported account", true)),
+                ),
+        )
+}
+
+fn text(name: &'static str, help: &'static str, required: bool) -> Arg<'static> {
+    Arg::new(name)
+        .long(name)
+        .help(help)
+        .takes_value(true)
+        .value_parser(clap::value_parser!(String))
+        .required(required)
+}
+
+fn number<T>(name: &'static str, help: &'static str) -> Arg<'static>
+where
+    T: std::str::FromStr + Clone + Send + Sync + 'static,
+    <T as std::str::FromStr>::Err: Into<Box<dyn Error + Send + Sync + 'static>>,
+{
+    Arg::new(name)
+        .long(name)
+        .help(help)
+        .takes_value(true)
+        // floats also parse "inf" and "NaN", no amount is spelled out
+        .value_parser(|value: &str| -> Result<T, Box<dyn Error + Send + Sync>> {
+            if value
+                .chars()
+                .any(|c| c.is_ascii_alphabetic() && !matches!(c, 'e' | 'E'))
+            {
+                return Err(format!("{} is not a number", value).into());
+            }
+            value.parse::<T>().map_err(Into::into)
+        })
+}
+
+fn user() -> Arg<'static> {
+    text("user", "Name of the local user acting", true)
+}
+
+fn passphrase() -> Arg<'static> {
+    text(
+        "passphrase",
+        "Wallet passphrase, read from AUCTION_PASSPHRASE or asked for when not given",
+        false,
+    )
+}
+
+fn value<T: Clone + Send + Sync + 'static>(matches: &ArgMatches, name: &str) -> T {
+    matches.get_one::<T>(name).cloned().unwrap()
+}
+
+fn optional<T: Clone + Send + Sync + 'static>(matches: &ArgMatches, name: &str) -> Option<T> {
+    matches.get_one::<T>(name).cloned()
+}
+
+// a new passphrase typed at the prompt is asked twice
+fn read_passphrase_arg(matches: &ArgMatches, new: bool) -> Result<String, Box<dyn Error>> {
+    match optional(matches, "passphrase").or_else(|| std::env::var("AUCTION_PASSPHRASE").ok()) {
+        Some(passphrase) => Ok(passphrase),
+        None if new => {
+            Ok(read_new_passphrase("New wallet passphrase:")
+                .ok_or("the passphrases do not match")?)
+        }
+        None => Ok(read_passphrase("Wallet passphrase:")),
+    }
+}
+
+// the local user record
+async fn local_user(name: &str) -> Result<User, Box<dyn Error>> {
+    Ok(load_users_from_file(&format!("users/{}.json", name))
+        .await
+        .map_err(|e| format!("can not load user {}: {}", name, e))?)
+}
+
+// the local user record and its signing key, unlocked from the wallet
+async fn account(matches: &ArgMatches) -> Result<(User, SigningKey), Box<dyn Error>> {
+    let name: String = value(matches, "user");
+    load_account(matches, &name).await
+}
+
+async fn load_account(
+    matches: &ArgMatches,
+    name: &str,
+) -> Result<(User, SigningKey), Box<dyn Error>> {
+    let user = local_user(name).await?;
+    let wallet: String = value(matches, "wallet");
+    let passphrase = read_passphrase_arg(matches, imports_key_file(&wallet, &user)?)?;
+    let private_key = unlock_account(&wallet, &user, &passphrase)?;
+    Ok((user, private_key))
+}
+
+fn sign(private_key: &SigningKey, content: &str) -> String {
+    let signature: Signature = private_key.sign(content.as_bytes());
+    hex::encode(signature.to_bytes())
+}
+
+fn outcome(outcome: &Outcome) -> Value {
+    match outcome {
+        Outcome::Accepted => json!({ "status": "accepted" }),
+        Outcome::Forwarded { node } => json!({ "status": "forwarded", "node": node }),
+    }
+}
+
+// run a command, `peers` is never empty
+pub async fn run(matches: &ArgMatches, peers: &[String]) -> CliResult {
+    let bootstrap: String = value(matches, "bootstrap");
+    match matches.subcommand() {
+        Some(("register", matches)) => register(matches, &bootstrap).await,
+        Some(("login", matches)) => login(matches, peers, &bootstrap).await,
+        Some(("create-auction", matches)) => create_auction(matches, peers, &bootstrap).await,
+        Some(("bid", matches)) => bid(matches, peers, &bootstrap).await,
+        Some(("reveal", matches)) => reveal(matches, peers).await,
+        Some(("cancel", matches)) => cancel(matches, peers, &bootstrap).await,
+        Some(("list", matches)) => list(matches, peers).await,
+        Some(("history", matches)) => history(matches, &bootstrap).await,
+        Some(("add-credits", matches)) => add_credits(matches, &bootstrap).await,
+        Some(("watch", matches)) => watch(matches, peers).await,
+        Some(("wallet", matches)) => wallet(matches),
+        _ => Err("unknown command".into()),
+    }
+}
+
+async fn register(matches: &ArgMatches, bootstrap: &str) -> CliResult {
+    let name: String = value(matches, "name");
+    let wallet: String = value(matches, "wallet");
+    let passphrase = read_passphrase_arg(matches, true)?;
+    let (user, private_key) = create_account(&wallet, &name, &passphrase).await?;
+    create_user(bootstrap, &private_key, &user).await?;
+    Ok(json!({ "uid": user.uid, "user_name": user.user_name }))
+}
+
+async fn login(matches: &ArgMatches, peers: &[String], bootstrap: &str) -> CliResult {
+    let name: String = value(matches, "name");
+    let (user, private_key) = load_account(matches, &name).await?;
+    // the node keeping the records trusts the key from a signed challenge
+    let session = node_login(bootstrap, &private_key).await?;
+
+    let mut endpoint = Endpoint {
+        user_id: user.uid.clone(),
+        addr: local_ip()?.to_string(),
+        timestamp: Utc::now(),
+        signature: String::new(),
+    };
+    endpoint.signature = sign(&private_key, &endpoint.signed_content());
+
+    let mut notifications = Vec::new();
+    let mut unreachable = Vec::new();
+    for peer in peers {
+        let since = load_sequences().get(peer).copied().unwrap_or(0);
+        match catch_up_notifications(peer, &endpoint, since).await {
+            Ok((origin, missed)) => notifications.extend(
+                missed
+                    .into_iter()
+                    .filter(|sequenced| record_sequence(&origin, sequenced.sequence))
+                    .map(|sequenced| sequenced.notification),
+            ),
+            Err(e) => unreachable.push(json!({ "peer": peer, "error": e.to_string() })),
+        }
+    }
+    Ok(json!({
+        "user": user,
+        "session_expires_at": session.expires_at,
+        "notifications": notifications,
+        "unreachable": unreachable,
+    }))
+}
+
+async fn create_auction(matches: &ArgMatches, peers: &[String], bootstrap: &str) -> CliResult {
+    let (local_user, private_key) = account(matches).await?;
+    let user = get_user(bootstrap, &local_user.uid).await?;
+
+    let (starting_bid, rules, kind) = match value::<String>(matches, "kind").as_str() {
+        "dutch" => {
+            let floor_price: f32 =
+                optional(matches, "floor-price").ok_or("--floor-price is required")?;
+            let kind = AuctionKind::Dutch {
+                start_price: optional(matches, "start-price").ok_or("--start-price is required")?,
+                decrement: optional(matches, "decrement").ok_or("--decrement is required")?,
+                decrement_seconds: value::<i64>(matches, "decrement-minutes") * 60,
+                floor_price,
+            };
+            (floor_price, AuctionRules::default(), kind)
+        }
+        kind => {
+            let min_increment: BidIncrement = value::<String>(matches, "min-increment")
+                .parse()
+                .map_err(|_| "--min-increment is not a valid increment")?;
+            let english = kind == "english";
+            let rules = AuctionRules {
+                reserve_price: value(matches, "reserve-price"),
+                min_increment,
+                pricing: match value::<String>(matches, "pricing").as_str() {
+                    "second" => Pricing::SecondPrice,
+                    "uniform" => Pricing::Uniform,
+                    _ => Pricing::FirstPrice,
+                },
+                soft_close: optional(matches, "soft-close-window")
+                    .filter(|_| english)
+                    .map(|window_seconds| SoftClose {
+                        window_seconds,
+                        extension_seconds: value(matches, "extension"),
+                        max_extension_seconds: value(matches, "max-extension"),
+                    }),
+                buy_now_price: optional(matches, "buy-now").filter(|_| english),
+            };
+            let kind = if english {
+                AuctionKind::English
+            } else {
+                AuctionKind::SealedBid {
+                    deposit: value(matches, "deposit"),
+                    reveal_seconds: value::<i64>(matches, "reveal-minutes") * 60,
+                }
+            };
+            (value(matches, "starting-bid"), rules, kind)
+        }
+    };
+
+    let start_time = Utc::now() + Duration::minutes(value(matches, "start-in"));
+    let mut auction = Auction::new(
+        value(matches, "item"),
+        start_time,
+        start_time + Duration::minutes(value(matches, "minutes")),
+        starting_bid,
+        rules,
+        kind,
+        user.uid.clone(),
+    );
+    auction.quantity = value::<u32>(matches, "quantity").max(1);
+    auction.lot = optional::<String>(matches, "lot")
+        .map(|lot| {
+            lot.split(',')
+                .map(|item| item.trim().to_string())
+                .filter(|item| !item.is_empty())
+                .collect()
+        })
+        .unwrap_or_default();
+    auction.metadata.category = optional(matches, "category").unwrap_or_default();
+    auction.metadata.description = optional(matches, "description").unwrap_or_default();
+    auction.signature = sign(&private_key, &auction.signed_content());
+
+    let transaction = Transaction::Auction(Box::new(auction.clone()));
+    let result = send_transaction(transaction.clone(), &peers[0], local_ip()?.to_string()).await?;
+    record_activity(bootstrap, &private_key, &transaction).await?;
+    Ok(json!({ "outcome": outcome(&result), "auction": auction }))
+}
+
+async fn bid(matches: &ArgMatches, peers: &[String], bootstrap: &str) -> CliResult {
+    let (local_user, private_key) = account(matches).await?;
+    let user = get_user(bootstrap, &local_user.uid).await?;
+    let auction = find_auction(&peers[0], &value::<String>(matches, "auction")).await?;
+
+    let (transaction, amount, sealed) = match auction.kind {
+        AuctionKind::Dutch { .. } => {
+            let timestamp = Utc::now();
+            let price = auction
+                .dutch_price_at(timestamp)
+                .ok_or("the auction is not selling at the moment")?;
+            let mut accept = DutchAccept {
+                bidder: user.uid.clone(),
+                auction_signature: auction.signature.clone(),
+                timestamp,
+                signature: String::new(),
+            };
+            accept.signature = sign(&private_key, &accept.signed_content());
+            (Transaction::Accept(accept), price, None)
+        }
+        AuctionKind::English | AuctionKind::SealedBid { .. } => {
+            let mut bid = Bid {
+                bidder: user.uid.clone(),
+                amount: value(matches, "amount"),
+                signature: String::new(),
+                auction_signature: auction.signature.clone(),
+                timestamp: DateTime::<Utc>::default(),
+                salt: String::new(),
+            };
+            bid.signature = sign(&private_key, &bid.signed_content());
+            if auction.kind == AuctionKind::English {
+                (Transaction::Bid(bid.clone()), bid.amount, None)
+            } else {
+                // the bid stays local until the reveal phase, only the commitment is sent
+                let mut salt = [0u8; 16];
+                OsRng.fill_bytes(&mut salt);
+                let reveal = BidReveal {
+                    bid: bid.clone(),
+                    salt: hex::encode(salt),
+                };
+                let mut commitment = BidCommitment {
+                    bidder: bid.bidder.clone(),
+                    auction_signature: bid.auction_signature.clone(),
+                    commitment: reveal.commitment(),
+                    signature: String::new(),
+                };
+                commitment.signature = sign(&private_key, &commitment.signed_content());
+                (Transaction::Commit(commitment), bid.amount, Some(reveal))
+            }
+        }
+    };
+    if user.credits < amount {
+        return Err("insufficient credits".into());
+    }
+
+    let result = send_transaction(transaction.clone(), &peers[0], local_ip()?.to_string()).await?;
+    // a sealed bid is only kept for its reveal once its commitment was taken
+    if let Some(reveal) = sealed {
+        let mut sealed_bids = load_sealed_bids(&user.user_name);
+        sealed_bids.push(reveal);
+        save_sealed_bids(&user.user_name, &sealed_bids);
+    }
+    record_activity(bootstrap, &private_key, &transaction).await?;
+    Ok(json!({
+        "outcome": outcome(&result),
+        "auction": auction.signature,
+        "amount": amount,
+    }))
+}
+
+async fn reveal(matches: &ArgMatches, peers: &[String]) -> CliResult {
+    let user = local_user(&value::<String>(matches, "user")).await?;
+    let only: Option<String> = optional(matches, "auction");
+
+    let mut revealed = Vec::new();
+    let mut rejected = Vec::new();
+    let mut pending = Vec::new();
+    for reveal in load_sealed_bids(&user.user_name) {
+        if only
+            .as_ref()
+            .is_some_and(|auction| auction != &reveal.bid.auction_signature)
+        {
+            pending.push(reveal);
+            continue;
+        }
+        match send_transaction(
+            Transaction::Reveal(reveal.clone()),
+            &peers[0],
+            local_ip()?.to_string(),
+        )
+        .await
+        {
+            Ok(result) => revealed.push(json!({
+                "auction": reveal.bid.auction_signature,
+                "outcome": outcome(&result),
+            })),
+            Err(e) => {
+                rejected.push(json!({
+                    "auction": reveal.bid.auction_signature,
+                    "error": e.to_string(),
+                }));
+                // kept while the auction may still enter its reveal phase
+                pending.push(reveal);
+            }
+        }
+    }
+    save_sealed_bids(&user.user_name, &pending);
+    Ok(json!({ "revealed": revealed, "rejected": rejected }))
+}
+
+async fn cancel(matches: &ArgMatches, peers: &[String], bootstrap: &str) -> CliResult {
+    let (user, private_key) = account(matches).await?;
+    let mut cancel = AuctionCancel {
+        seller: user.uid.clone(),
+        auction_signature: value(matches, "auction"),
+        signature: String::new(),
+    };
+    cancel.signature = sign(&private_key, &cancel.signed_content());
+
+    let transaction = Transaction::Cancel(cancel.clone());
+    let result = send_transaction(transaction.clone(), &peers[0], local_ip()?.to_string()).await?;
+    record_activity(bootstrap, &private_key, &transaction).await?;
+    Ok(json!({
+        "outcome": outcome(&result),
+        "auction": cancel.auction_signature,
+    }))
+}
+
+async fn list(matches: &ArgMatches, peers: &[String]) -> CliResult {
+    let query = AuctionQuery {
+        state: match value::<String>(matches, "state").as_str() {
+            "active" => StateFilter::Active,
+            "closed" => StateFilter::Closed,
+            _ => StateFilter::Any,
+        },
+        seller: optional(matches, "seller"),
+        category: optional(matches, "category"),
+        sort: match value::<String>(matches, "sort").as_str() {
+            "lowest" => SortKey::PriceLowest,
+            "highest" => SortKey::PriceHighest,
+            "newest" => SortKey::Newest,
+            _ => SortKey::EndingSoonest,
+        },
+        page: value(matches, "page"),
+        page_size: value(matches, "page-size"),
+        ..AuctionQuery::default()
+    };
+    Ok(serde_json::to_value(
+        search_auctions(&peers[0], &query).await?,
+    )?)
+}
+
+async fn history(matches: &ArgMatches, bootstrap: &str) -> CliResult {
+    let local_user = local_user(&value::<String>(matches, "user")).await?;
+    let user = get_user(bootstrap, &local_user.uid).await?;
+    Ok(json!({
+        "activity": user.activity,
+        "auctions_winner": user.auctions_winner,
+    }))
+}
+
+async fn add_credits(matches: &ArgMatches, bootstrap: &str) -> CliResult {
+    let (_, private_key) = account(matches).await?;
+    let user = deposit(bootstrap, &private_key, value(matches, "amount")).await?;
+    Ok(json!({ "uid": user.uid, "credits": user.credits }))
+}
+
+async fn watch(matches: &ArgMatches, peers: &[String]) -> CliResult {
+    let (user, private_key) = account(matches).await?;
+    let mut subscription = Subscription {
+        subscriber: user.uid.clone(),
+        auction_signature: value(matches, "auction"),
+        subscribe: !value::<bool>(matches, "stop"),
+        timestamp: Utc::now(),
+        signature: String::new(),
+    };
+    subscription.signature = sign(&private_key, &subscription.signed_content());
+
+    let result = send_transaction(
+        Transaction::Subscription(subscription.clone()),
+        &peers[0],
+        local_ip()?.to_string(),
+    )
+    .await?;
+    Ok(json!({
+        "outcome": outcome(&result),
+        "auction": subscription.auction_signature,
+        "watching": subscription.subscribe,
+    }))
+}
+
+fn wallet(matches: &ArgMatches) -> CliResult {
+    let path: String = value(matches, "wallet");
+    let mut wallet = Wallet::open(&path)?;
+    match matches.subcommand() {
+        Some(("list", _)) => Ok(json!({
+            "accounts": wallet
+                .accounts
+                .iter()
+                .map(|account| json!({ "name": account.name, "uid": account.uid }))
+                .collect::<Vec<_>>(),
+        })),
+        Some(("export", matches)) => {
+            Ok(serde_json::from_str(&wallet.export_account(&value::<
+                String,
+            >(
+                matches, "user",
+            ))?)?)
+        }
+        Some(("import", matches)) => {
+            let exported = std::fs::read_to_string(value::<String>(matches, "file"))?;
+            let account = wallet.import_account(&exported)?.clone();
+            wallet.save(&path)?;
+            Ok(json!({ "name": account.name, "uid": account.uid }))
+        }
+        _ => Err("unknown wallet command".into()),
+    }
+}
@@ -1,26 +1,54 @@
 use auctions_pub_ledger::auction_app::auction::list_auctions;
+use auctions_pub_ledger::auction_app::auction::print_auctions;
 use auctions_pub_ledger::auction_app::auction::Auction;
+use auctions_pub_ledger::auction_app::auction::AuctionCancel;
+use auctions_pub_ledger::auction_app::auction::AuctionHouse;
+use auctions_pub_ledger::auction_app::auction::AuctionKind;
+use auctions_pub_ledger::auction_app::auction::AuctionMetadata;
+use auctions_pub_ledger::auction_app::auction::AuctionRules;
 use auctions_pub_ledger::auction_app::auction::Bid;
+use auctions_pub_ledger::auction_app::auction::BidIncrement;
+use auctions_pub_ledger::auction_app::auction::DutchAccept;
 use auctions_pub_ledger::auction_app::auction::Transaction;
-use auctions_pub_ledger::auction_app::auction_operation::client::create_user;
+use auctions_pub_ledger::auction_app::auction::{load_sealed_bids, save_sealed_bids};
+use auctions_pub_ledger::auction_app::auction::{BidCommitment, BidReveal, Pricing, SoftClose};
+use auctions_pub_ledger::auction_app::auction::{Endpoint, Subscription};
+use auctions_pub_ledger::auction_app::auction_operation::client::catch_up_notifications;
 use auctions_pub_ledger::auction_app::auction_operation::client::get_auction_house;
 use auctions_pub_ledger::auction_app::auction_operation::client::get_user;
+use auctions_pub_ledger::auction_app::auction_operation::client::login as client_login;
+use auctions_pub_ledger::auction_app::auction_operation::client::record_activity;
+use auctions_pub_ledger::auction_app::auction_operation::client::search_auctions;
+use auctions_pub_ledger::auction_app::auction_operation::client::search_every_page;
 use auctions_pub_ledger::auction_app::auction_operation::client::send_transaction;
-use auctions_pub_ledger::auction_app::notifications::notify_server::notification_server;
-use auctions_pub_ledger::auction_app::user::UserActivity;
+use auctions_pub_ledger::auction_app::auction_operation::client::subscribe_auction_events;
+use auctions_pub_ledger::auction_app::auction_operation::dht::{
+    discover_auctions, find_content, store_content,
+};
+use auctions_pub_ledger::auction_app::cli;
+use auctions_pub_ledger::auction_app::notifications::notify_server::{
+    load_sequences, notification_server, record_sequence, show_notification,
+};
 use auctions_pub_ledger::auction_app::user::{
-    add_credits, load_users_from_file, register_user, User,
+    add_credits, imports_key_file, load_users_from_file, read_new_passphrase, read_passphrase,
+    register_user, unlock_account, User,
 };
-use auctions_pub_ledger::cryptography::ecdsa_keys::load_ecdsa_keys;
-use chrono::{Duration, Utc};
+use auctions_pub_ledger::auction_server::auction_events::EventFilter;
+use auctions_pub_ledger::auction_server::search::{
+    merge_results, AuctionQuery, SortKey, StateFilter,
+};
+use auctions_pub_ledger::cryptography::wallet::WALLET_FILE;
+use chrono::{DateTime, Duration, Utc};
 use colored::*;
 use k256::ecdsa::SigningKey;
 use k256::ecdsa::{signature::Signer, Signature};
 use local_ip_address::local_ip;
-use sha256::digest;
-use std::env;
+use rand_core::{OsRng, RngCore};
+use std::fs;
 use std::io::{self, Write};
+use std::str::FromStr;
 use tokio::task;
+use tokio_stream::StreamExt;
 
 #[cfg(not(target_os = "windows"))]
 fn clear_screen() {
@@ -30,9 +58,28 @@ const BOOTSTRAP_NODE_ADDRES: &str = "10.10.0.2";
 
 #[tokio::main]
 async fn main() {
+    let matches = cli::command().get_matches();
+    let peers: Vec<String> = matches
+        .get_one::<String>("peers")
+        .unwrap()
+        .split(',')
+        .map(String::from)
+        .collect();
+
+    // commands print a single JSON document and exit, errors included
+    if matches.subcommand().is_some() {
+        match cli::run(&matches, &peers).await {
+            Ok(output) => println!("{}", serde_json::to_string_pretty(&output).unwrap()),
+            Err(e) => {
+                println!("{}", serde_json::json!({ "error": e.to_string() }));
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
     task::spawn(notification_server());
     clear_screen();
-    let args: Vec<String> = env::args().collect();
     println!("Welcome to the BidBuddie's Auction System!");
 
     println!("Please select an option:\n1. Login\n2. Register");
@@ -41,14 +88,15 @@ async fn main() {
         .read_line(&mut option)
         .expect("Failed to read line");
 
-    let mut user = match option.trim() {
+    let (mut user, private_key) = match option.trim() {
         "1" => login().await,
-        "2" => register_user(BOOTSTRAP_NODE_ADDRES).await,
+        "2" => register_user(BOOTSTRAP_NODE_ADDRES, WALLET_FILE).await,
         _ => {
             println!("Invalid option, please try again.");
             return;
         }
     };
+    catch_up(&user, &private_key, &peers).await;
     pause();
 
     loop {
@@ -66,8 +114,8 @@ async fn main() {
             .expect("Failed to read line");
 
         match option.trim() {
-            "1" => auctions_menu(&mut user, args[1].split(",").map(String::from).collect()).await,
-            "2" => profile_menu(&mut user).await,
+            "1" => auctions_menu(&mut user, &private_key, peers.clone()).await,
+            "2" => profile_menu(&mut user, &private_key, peers.clone()).await,
             "3" => {
                 println!("Exiting...");
                 break;
@@ -79,36 +127,99 @@ async fn main() {
     }
 }
 
-async fn login() -> User {
-    println!("Please enter your Username:");
-    let mut username = String::new();
-    io::stdin()
-        .read_line(&mut username)
-        .expect("Failed to read line");
-    let username = username.trim();
+// notifications queued while this client was offline, asked from every node it knows. The
+// nodes deliver the next ones to the address this client runs on now.
+async fn catch_up(user: &User, private_key: &SigningKey, peers: &[String]) {
+    let mut endpoint = Endpoint {
+        user_id: user.uid.clone(),
+        addr: local_ip().unwrap().to_string(),
+        timestamp: Utc::now(),
+        signature: String::new(),
+    };
+    let signature: Signature = private_key.sign(endpoint.signed_content().as_bytes());
+    endpoint.signature = hex::encode(signature.to_bytes());
 
-    let user: User = load_users_from_file(&format!("users/{}.json", &username))
-        .await
-        .expect("Login error please try again...");
+    for peer in peers {
+        let since = load_sequences().get(peer).copied().unwrap_or(0);
+        match catch_up_notifications(peer, &endpoint, since).await {
+            Ok((origin, missed)) => {
+                for sequenced in missed {
+                    if record_sequence(&origin, sequenced.sequence) {
+                        show_notification(&sequenced.notification);
+                    }
+                }
+            }
+            Err(e) => println!(
+                "{}",
+                format!("Could not catch up with {}: {}", peer, e).yellow()
+            ),
+        }
+    }
+}
 
-    println!(
-        "{}",
-        format!("Login successful\nWelcome back {}", user.user_name).green()
-    );
+// the user's record and the signing key unlocked from the wallet
+async fn login() -> (User, SigningKey) {
+    loop {
+        println!("Please enter your Username:");
+        let mut username = String::new();
+        io::stdin()
+            .read_line(&mut username)
+            .expect("Failed to read line");
+        let username = username.trim();
 
-    user
+        let user: User = match load_users_from_file(&format!("users/{}.json", &username)).await {
+            Ok(user) => user,
+            Err(e) => {
+                println!("{}", format!("Login error please try again... {}", e).red());
+                continue;
+            }
+        };
+        // a key file from before the wallet is moved into it under a new passphrase
+        let passphrase = if imports_key_file(WALLET_FILE, &user).unwrap_or(false) {
+            match read_new_passphrase("Choose a passphrase for your wallet:") {
+                Some(passphrase) => passphrase,
+                None => {
+                    println!("{}", "The passphrases do not match".red());
+                    continue;
+                }
+            }
+        } else {
+            read_passphrase("Please enter your wallet passphrase:")
+        };
+        match unlock_account(WALLET_FILE, &user, &passphrase) {
+            Ok(private_key) => {
+                // the node keeping the records trusts the key from a signed challenge, the
+                // session is reused for the updates of the user
+                if let Err(e) = client_login(BOOTSTRAP_NODE_ADDRES, &private_key).await {
+                    println!("{}", format!("Login error please try again... {}", e).red());
+                    continue;
+                }
+                println!(
+                    "{}",
+                    format!("Login successful\nWelcome back {}", user.user_name).green()
+                );
+                return (user, private_key);
+            }
+            Err(e) => println!("{}", format!("Login error please try again... {}", e).red()),
+        }
+    }
 }
 
-async fn auctions_menu(user: &mut User, peers_list: Vec<String>) {
-    let (private_key, _) = load_ecdsa_keys(user.uid.clone());
+async fn auctions_menu(user: &mut User, private_key: &SigningKey, peers_list: Vec<String>) {
     loop {
         clear_screen();
         println!("=== Auctions Menu ===");
         println!("1. Join Auction");
         println!("2. Create Auction");
         println!("3. Current Auctions");
-        println!("4. History");
-        println!("5. Back");
+        println!("4. Search Auctions");
+        println!("5. History");
+        println!("6. Reveal Sealed Bids");
+        println!("7. Cancel Auction");
+        println!("8. Follow Auctions");
+        println!("9. Watch Auction");
+        println!("10. Unsubscribe From Auction");
+        println!("11. Back");
         print!("Select an option: ");
         io::stdout().flush().unwrap();
 
@@ -121,8 +232,14 @@ async fn auctions_menu(user: &mut User, peers_list: Vec<String>) {
             "1" => join_auction(user, &peers_list, private_key.clone()).await,
             "2" => create_auction(user, &peers_list, private_key.clone()).await,
             "3" => current_auctions(&peers_list).await,
-            "4" => history(user).await,
-            "5" => break,
+            "4" => search(&peers_list).await,
+            "5" => history(user).await,
+            "6" => reveal_sealed_bids(user, &peers_list).await,
+            "7" => cancel_auction(user, &peers_list, private_key.clone()).await,
+            "8" => follow_auctions(&peers_list).await,
+            "9" => update_subscription(user, &peers_list, private_key.clone(), true).await,
+            "10" => update_subscription(user, &peers_list, private_key.clone(), false).await,
+            "11" => break,
             _ => {
                 println!("Invalid option, please try again.");
             }
@@ -130,14 +247,15 @@ async fn auctions_menu(user: &mut User, peers_list: Vec<String>) {
     }
 }
 
-async fn profile_menu(user: &mut User) {
+async fn profile_menu(user: &mut User, private_key: &SigningKey, peers_list: Vec<String>) {
     loop {
         clear_screen();
         println!("=== Profile Menu ===");
         println!("1. View Profile");
         println!("2. Add Credits");
         println!("3. History");
-        println!("4. Back");
+        println!("4. Watchlist");
+        println!("5. Back");
         print!("Select an option: ");
         io::stdout().flush().unwrap();
 
@@ -148,9 +266,10 @@ async fn profile_menu(user: &mut User) {
 
         match option.trim() {
             "1" => view_profile(user).await,
-            "2" => add_credits(BOOTSTRAP_NODE_ADDRES, user).await,
+            "2" => add_credits(BOOTSTRAP_NODE_ADDRES, private_key, user).await,
             "3" => history(user).await,
-            "4" => break,
+            "4" => watchlist(user, &peers_list).await,
+            "5" => break,
             _ => {
                 println!("Invalid option, please try again.");
             }
@@ -179,7 +298,7 @@ async fn join_auction(user: &mut User, dest_ip: &Vec<String>, private_key: Signi
         .await
         .expect("error geting acution from peers");
 
-    let _ = list_auctions().await;
+    let auction_house = list_auctions().await;
     let auction_signature;
     loop {
         println!("Enter the Auction ID you want to join (or 'exit' to cancel):");
@@ -204,8 +323,38 @@ async fn join_auction(user: &mut User, dest_ip: &Vec<String>, private_key: Signi
         }
     }
 
+    let auction = auction_house
+        .auctions
+        .iter()
+        .find(|auction| auction.signature == auction_signature);
+    let auction_kind = auction
+        .map(|auction| auction.kind.clone())
+        .unwrap_or_default();
+
+    // Dutch auctions are won by accepting the current price instead of bidding
+    if let Some(auction) =
+        auction.filter(|auction| matches!(auction.kind, AuctionKind::Dutch { .. }))
+    {
+        accept_dutch_price(&mut user, dest_ip, private_key, auction).await;
+        return;
+    }
+
     // Proceed with the rest of the function using the valid `auction_id`
     println!("Your balance: ${}", user.credits);
+    if let Some(auction) = auction.filter(|auction| auction.quantity > 1) {
+        println!(
+            "{} units on sale, the top {} bids win. Minimum bid: ${}",
+            auction.quantity,
+            auction.quantity,
+            auction.minimum_next_bid()
+        );
+    }
+    if let Some(buy_now_price) = auction.and_then(|auction| auction.rules.buy_now_price) {
+        println!(
+            "Bid ${} or more to buy it now and end the auction.",
+            buy_now_price
+        );
+    }
     println!("Enter your bid amount:");
     let mut amount_str = String::new();
     io::stdin().read_line(&mut amount_str).unwrap();
@@ -217,41 +366,65 @@ async fn join_auction(user: &mut User, dest_ip: &Vec<String>, private_key: Signi
         }
     };
 
-    let signed_content =
-        digest(auction_signature.clone() + &user.uid.clone() + &amount.to_string());
-    let signature: Signature = private_key.sign(signed_content.clone().as_bytes());
     if user.credits >= amount {
-        let bid = Bid {
+        let mut bid = Bid {
             bidder: user.uid.clone(),
             amount,
-            signature: hex::encode(signature.clone().to_bytes()),
-            auction_signature: auction_signature,
+            signature: String::new(),
+            auction_signature,
+            timestamp: DateTime::<Utc>::default(),
+            salt: String::new(),
         };
+        let signature: Signature = private_key.sign(bid.signed_content().as_bytes());
+        bid.signature = hex::encode(signature.to_bytes());
         let local_ip_address = local_ip().unwrap();
 
+        // sealed bids only publish a commitment, the bid is kept locally until the reveal phase
+        let (transaction, reveal) = match auction_kind {
+            AuctionKind::English | AuctionKind::Dutch { .. } => {
+                (Transaction::Bid(bid.clone()), None)
+            }
+            AuctionKind::SealedBid { .. } => {
+                let mut salt = [0u8; 16];
+                OsRng.fill_bytes(&mut salt);
+                let reveal = BidReveal {
+                    bid: bid.clone(),
+                    salt: hex::encode(salt),
+                };
+                let mut commitment = BidCommitment {
+                    bidder: bid.bidder.clone(),
+                    auction_signature: bid.auction_signature.clone(),
+                    commitment: reveal.commitment(),
+                    signature: String::new(),
+                };
+                let signature: Signature = private_key.sign(commitment.signed_content().as_bytes());
+                commitment.signature = hex::encode(signature.to_bytes());
+                (Transaction::Commit(commitment), Some(reveal))
+            }
+        };
+
         match send_transaction(
-            Transaction::Bid(bid.clone()),
+            transaction.clone(),
             &dest_ip[0],
             local_ip_address.to_string(),
         )
         .await
         {
-            Ok(_result) => {
+            Ok(outcome) => {
                 println!(
-                    "Transaction successfully created for auction:  {:?} ",
-                    bid.clone().signature
+                    "{}",
+                    format!("Bid on auction {} {}", bid.auction_signature, outcome).green()
                 );
-                let activity: UserActivity = UserActivity {
-                    activity_type: "Bid".to_string(),
-                    auction_signature: bid.auction_signature,
-                    amount: bid.amount,
-                };
-                user.activity.push(activity);
-                let user_str = serde_json::to_string(&user).unwrap();
-                let _ = create_user(BOOTSTRAP_NODE_ADDRES, &user_str).await;
+                if let Some(reveal) = reveal {
+                    let mut sealed_bids = load_sealed_bids(&user.user_name);
+                    sealed_bids.push(reveal);
+                    save_sealed_bids(&user.user_name, &sealed_bids);
+                    println!("Remember to reveal your sealed bid once bidding closes.");
+                }
+                record(&mut user, &private_key, &transaction).await;
             }
             Err(e) => {
-                println!("error {}", e);
+                println!("{}", format!("Bid rejected: {}", e).red());
             }
         }
     } else {
@@ -260,37 +433,376 @@ async fn join_auction(user: &mut User, dest_ip: &Vec<String>, private_key: Signi
     pause();
 }
 
+// the bootstrap node records accepted transactions in the user's activity, the local copy
+// follows its record
+async fn record(user: &mut User, private_key: &SigningKey, transaction: &Transaction) {
+    if let Ok(account) = record_activity(BOOTSTRAP_NODE_ADDRES, private_key, transaction).await {
+        *user = account;
+    }
+}
+
+async fn accept_dutch_price(
+    user: &mut User,
+    dest_ip: &[String],
+    private_key: SigningKey,
+    auction: &Auction,
+) {
+    let timestamp = Utc::now();
+    let price = auction.dutch_price_at(timestamp).unwrap();
+    println!("Your balance: ${}", user.credits);
+    println!("Current price: ${}. Accept it? (y/n)", price);
+    let mut confirm = String::new();
+    io::stdin().read_line(&mut confirm).unwrap();
+    if !confirm.trim().eq_ignore_ascii_case("y") {
+        return;
+    }
+    if user.credits < price {
+        println!("Insufficient credits to accept the price.");
+        pause();
+        return;
+    }
+
+    let mut accept = DutchAccept {
+        bidder: user.uid.clone(),
+        auction_signature: auction.signature.clone(),
+        timestamp,
+        signature: String::new(),
+    };
+    let signature: Signature = private_key.sign(accept.signed_content().as_bytes());
+    accept.signature = hex::encode(signature.to_bytes());
+
+    let transaction = Transaction::Accept(accept.clone());
+    let local_ip_address = local_ip().unwrap();
+    match send_transaction(
+        transaction.clone(),
+        &dest_ip[0],
+        local_ip_address.to_string(),
+    )
+    .await
+    {
+        Ok(outcome) => {
+            println!(
+                "{}",
+                format!(
+                    "Price of ${} for auction {} {}",
+                    price, accept.auction_signature, outcome
+                )
+                .green()
+            );
+            record(user, &private_key, &transaction).await;
+        }
+        Err(e) => {
+            println!("{}", format!("Accept rejected: {}", e).red());
+        }
+    }
+    pause();
+}
+
+async fn reveal_sealed_bids(user: &User, dest_ip: &[String]) {
+    clear_screen();
+    let sealed_bids = load_sealed_bids(&user.user_name);
+    if sealed_bids.is_empty() {
+        println!("You have no sealed bids to reveal.");
+        pause();
+        return;
+    }
+
+    let local_ip_address = local_ip().unwrap();
+    let mut pending = Vec::new();
+    for reveal in sealed_bids {
+        match send_transaction(
+            Transaction::Reveal(reveal.clone()),
+            &dest_ip[0],
+            local_ip_address.to_string(),
+        )
+        .await
+        {
+            Ok(outcome) => println!(
+                "{}",
+                format!(
+                    "Sealed bid reveal for auction {} {}",
+                    reveal.bid.auction_signature, outcome
+                )
+                .green()
+            ),
+            Err(e) => {
+                println!(
+                    "{}",
+                    format!(
+                        "Reveal rejected for auction {}: {}",
+                        reveal.bid.auction_signature, e
+                    )
+                    .red()
+                );
+                // keep it around while the auction may still enter its reveal phase
+                pending.push(reveal);
+            }
+        }
+    }
+    save_sealed_bids(&user.user_name, &pending);
+    pause();
+}
+
+async fn cancel_auction(user: &User, dest_ip: &Vec<String>, private_key: SigningKey) {
+    clear_screen();
+
+    get_auction_house(dest_ip)
+        .await
+        .expect("error geting acution from peers");
+
+    list_auctions().await;
+    let auction_signature: String = read_value(
+        "Enter the ID of your auction to cancel:",
+        "Failed to read line",
+    );
+
+    let mut cancel = AuctionCancel {
+        seller: user.uid.clone(),
+        auction_signature,
+        signature: String::new(),
+    };
+    let signature: Signature = private_key.sign(cancel.signed_content().as_bytes());
+    cancel.signature = hex::encode(signature.to_bytes());
+
+    let local_ip_address = local_ip().unwrap();
+    match send_transaction(
+        Transaction::Cancel(cancel.clone()),
+        &dest_ip[0],
+        local_ip_address.to_string(),
+    )
+    .await
+    {
+        Ok(outcome) => println!(
+            "{}",
+            format!(
+                "Cancellation of auction {} {}",
+                cancel.auction_signature, outcome
+            )
+            .green()
+        ),
+        Err(e) => println!("{}", format!("Cancellation rejected: {}", e).red()),
+    }
+    pause();
+}
+
+// watch an auction to get its notifications without bidding, or stop its notifications.
+// The ones about your own bids and auctions keep coming after unsubscribing.
+async fn update_subscription(
+    user: &User,
+    dest_ip: &Vec<String>,
+    private_key: SigningKey,
+    subscribe: bool,
+) {
+    clear_screen();
+
+    get_auction_house(dest_ip)
+        .await
+        .expect("error geting acution from peers");
+
+    list_auctions().await;
+    let auction_signature: String = read_value(
+        if subscribe {
+            "Enter the ID of the auction to watch:"
+        } else {
+            "Enter the ID of the auction to unsubscribe from:"
+        },
+        "Failed to read line",
+    );
+
+    let mut subscription = Subscription {
+        subscriber: user.uid.clone(),
+        auction_signature,
+        subscribe,
+        timestamp: Utc::now(),
+        signature: String::new(),
+    };
+    let signature: Signature = private_key.sign(subscription.signed_content().as_bytes());
+    subscription.signature = hex::encode(signature.to_bytes());
+
+    let action = if subscribe {
+        "Watching"
+    } else {
+        "Unsubscription from"
+    };
+    let local_ip_address = local_ip().unwrap();
+    match send_transaction(
+        Transaction::Subscription(subscription.clone()),
+        &dest_ip[0],
+        local_ip_address.to_string(),
+    )
+    .await
+    {
+        Ok(outcome) => println!(
+            "{}",
+            format!(
+                "{} auction {} {}",
+                action, subscription.auction_signature, outcome
+            )
+            .green()
+        ),
+        Err(e) => println!("{}", format!("{} rejected: {}", action, e).red()),
+    }
+    pause();
+}
+
+// Every node only holds the auctions it is a replica of, so all of them are asked for every
+// page of the auctions the user watches
+async fn watchlist(user: &User, dest_ip: &[String]) {
+    clear_screen();
+    let query = AuctionQuery {
+        watcher: Some(user.uid.clone()),
+        ..AuctionQuery::default()
+    };
+    let mut results = Vec::new();
+    for peer in dest_ip {
+        match search_every_page(peer, &query).await {
+            Ok(auctions) => results.push(auctions),
+            Err(e) => println!(
+                "{}",
+                format!("Could not load the watchlist from {}: {}", peer, e).yellow()
+            ),
+        }
+    }
+    let watched = merge_results(&query, results);
+    if watched.is_empty() {
+        println!("You are not watching any auction.");
+    } else {
+        println!("Watched auctions:");
+        print_auctions(&watched);
+    }
+    pause();
+}
+
 async fn create_auction(user: &mut User, dest_ip: &Vec<String>, private_key: SigningKey) {
     let mut user = get_user(BOOTSTRAP_NODE_ADDRES, &user.uid).await.unwrap();
 
     clear_screen();
     println!("Creating a new auction.");
-    println!("Enter the item name:");
-    let mut item_name = String::new();
-    io::stdin()
-        .read_line(&mut item_name)
-        .expect("Failed to read line");
+    let item_name: String = read_value("Enter the item name:", "Failed to read line");
+    let lot: String = read_value(
+        "Enter the items bundled in this lot, comma separated (empty for a single item):",
+        "Failed to read line",
+    );
+    let lot = split_list(&lot);
+    let quantity: u32 = read_value(
+        "Enter the number of identical units on sale (1 for a single one):",
+        "Please enter a valid number of units",
+    );
+    let metadata = read_metadata(dest_ip).await;
 
-    println!("Enter the starting bid:");
-    let mut starting_bid_str = String::new();
-    io::stdin()
-        .read_line(&mut starting_bid_str)
-        .expect("Failed to read line");
-    let starting_bid: f32 = starting_bid_str
-        .trim()
-        .parse()
-        .expect("Please enter a valid number");
-
-    let start_time = Utc::now();
-    println!("Enter the auction duration in days:");
-    let mut duration_str = String::new();
-    io::stdin()
-        .read_line(&mut duration_str)
-        .expect("Failed to read line");
-    let duration: i64 = duration_str
-        .trim()
-        .parse()
-        .expect("Please enter a valid number of minutes");
+    let kind_option: String = read_value(
+        "Select the auction type:\n1. English\n2. Sealed-bid\n3. Dutch",
+        "Failed to read line",
+    );
+    let (starting_bid, rules, kind) = match kind_option.as_str() {
+        "3" => {
+            let start_price: f32 =
+                read_value("Enter the start price:", "Please enter a valid number");
+            let decrement: f32 = read_value(
+                "Enter the price drop per step:",
+                "Please enter a valid number",
+            );
+            let decrement_minutes: i64 = read_value(
+                "Enter the minutes between price drops:",
+                "Please enter a valid number of minutes",
+            );
+            let floor_price: f32 =
+                read_value("Enter the floor price:", "Please enter a valid number");
+            let kind = AuctionKind::Dutch {
+                start_price,
+                decrement,
+                decrement_seconds: decrement_minutes * 60,
+                floor_price,
+            };
+            (floor_price, AuctionRules::default(), kind)
+        }
+        option => {
+            let starting_bid: f32 =
+                read_value("Enter the starting bid:", "Please enter a valid number");
+            let reserve_price: f32 = read_value(
+                "Enter the reserve price (0 for none):",
+                "Please enter a valid number",
+            );
+            let min_increment: BidIncrement = read_value(
+                "Enter the minimum bid increment (e.g. 5 or 5%):",
+                "Please enter a valid increment",
+            );
+            let pricing_option: String = read_value(
+                "Select the price paid by the winners:\n1. Own bid (first price, discriminatory)\n2. Best losing bid (Vickrey)\n3. Lowest winning bid for every unit (uniform)",
+                "Failed to read line",
+            );
+            let pricing = match pricing_option.as_str() {
+                "2" => Pricing::SecondPrice,
+                "3" => Pricing::Uniform,
+                _ => Pricing::FirstPrice,
+            };
+            let mut soft_close = None;
+            let mut buy_now_price = None;
+            let kind = match option {
+                "2" => {
+                    let deposit: f32 = read_value(
+                        "Enter the deposit forfeited by bidders that do not reveal:",
+                        "Please enter a valid number",
+                    );
+                    let reveal_minutes: i64 = read_value(
+                        "Enter the reveal period in minutes:",
+                        "Please enter a valid number of minutes",
+                    );
+                    AuctionKind::SealedBid {
+                        deposit,
+                        reveal_seconds: reveal_minutes * 60,
+                    }
+                }
+                _ => {
+                    let window_seconds: i64 = read_value(
+                        "Enter the soft close window in seconds (0 to disable):",
+                        "Please enter a valid number of seconds",
+                    );
+                    if window_seconds > 0 {
+                        let extension_seconds: i64 = read_value(
+                            "Enter the seconds a late bid extends the auction by:",
+                            "Please enter a valid number of seconds",
+                        );
+                        let max_extension_seconds: i64 = read_value(
+                            "Enter the maximum total extension in seconds:",
+                            "Please enter a valid number of seconds",
+                        );
+                        soft_close = Some(SoftClose {
+                            window_seconds,
+                            extension_seconds,
+                            max_extension_seconds,
+                        });
+                    }
+                    let price: f32 = read_value(
+                        "Enter the buy now price (0 for none):",
+                        "Please enter a valid number",
+                    );
+                    if price > 0.0 {
+                        buy_now_price = Some(price);
+                    }
+                    AuctionKind::English
+                }
+            };
+            let rules = AuctionRules {
+                reserve_price,
+                min_increment,
+                pricing,
+                soft_close,
+                buy_now_price,
+            };
+            (starting_bid, rules, kind)
+        }
+    };
+
+    let delay: i64 = read_value(
+        "Enter the minutes until bidding opens (0 to open now):",
+        "Please enter a valid number of minutes",
+    );
+    let start_time = Utc::now() + Duration::minutes(delay);
+    let duration: i64 = read_value(
+        "Enter the auction duration in minutes:",
+        "Please enter a valid number of minutes",
+    );
     let end_time = start_time + Duration::minutes(duration);
 
     get_auction_house(dest_ip)
@@ -299,43 +811,40 @@ async fn create_auction(user: &mut User, dest_ip: &Vec<String>, private_key: Sig
 
     list_auctions().await;
 
-    let signed_content =
-        digest(item_name.trim().to_string() + &starting_bid.to_string() + &user.uid.clone());
-
-    let signature: Signature = private_key.sign(signed_content.as_bytes());
-
     // Use user.uid to pass the creator's uid to the new auction
-    let auction = Auction::new(
-        item_name.trim().to_string(),
+    let mut auction = Auction::new(
+        item_name,
         start_time,
         end_time,
         starting_bid,
+        rules,
+        kind,
         user.uid.clone(), // Pass the user's uid as the creator
-        hex::encode(signature.to_bytes()),
-        vec![],
     );
+    auction.quantity = quantity.max(1);
+    auction.lot = lot;
+    auction.metadata = metadata;
+    let signature: Signature = private_key.sign(auction.signed_content().as_bytes());
+    auction.signature = hex::encode(signature.to_bytes());
 
+    let transaction = Transaction::Auction(Box::new(auction.clone()));
     let local_ip_address = local_ip().unwrap();
     match send_transaction(
-        Transaction::Auction(auction.clone()),
+        transaction.clone(),
         &dest_ip[0],
         local_ip_address.to_string(),
     )
     .await
     {
-        Ok(_result) => {
-            println!("Auction created successfully:  {:?}", auction.signature);
-            let activity: UserActivity = UserActivity {
-                activity_type: "AuctionCreation".to_string(),
-                auction_signature: auction.signature,
-                amount: auction.starting_bid,
-            };
-            user.activity.push(activity);
-            let user_str = serde_json::to_string(&user).unwrap();
-            let _ = create_user(BOOTSTRAP_NODE_ADDRES, &user_str).await;
+        Ok(outcome) => {
+            println!(
+                "{}",
+                format!("Auction {} {}", auction.signature, outcome).green()
+            );
+            record(&mut user, &private_key, &transaction).await;
         }
         Err(e) => {
-            println!("error {}", e);
+            println!("{}", format!("Auction rejected: {}", e).red());
         }
     }
 
@@ -345,16 +854,232 @@ async fn create_auction(user: &mut User, dest_ip: &Vec<String>, private_key: Sig
 async fn current_auctions(dest_ip: &Vec<String>) {
     clear_screen();
 
-    println!("Active Auctions:");
+    let category: String = read_value(
+        "Browse a category (empty for every auction):",
+        "Failed to read line",
+    );
 
-    get_auction_house(dest_ip)
-        .await
-        .expect("error geting auction from peers");
+    println!("Active Auctions:");
 
-    list_auctions().await;
+    let auction_house = if category.trim().is_empty() {
+        get_auction_house(dest_ip)
+            .await
+            .expect("error geting auction from peers");
+        list_auctions().await
+    } else {
+        let mut auction_house = AuctionHouse::new();
+        match discover_auctions(&dest_ip[0], Some(&category)).await {
+            Ok(auctions) => auction_house.auctions = auctions.unwrap_or_default(),
+            Err(e) => println!("Failed to browse category: {}", e),
+        }
+        print_auctions(&auction_house.auctions);
+        auction_house
+    };
+    let auction_signature: String = read_value(
+        "Enter an auction ID to see its details (empty to go back):",
+        "Failed to read line",
+    );
+    if auction_signature.is_empty() {
+        return;
+    }
+    match auction_house
+        .auctions
+        .iter()
+        .find(|auction| auction.signature == auction_signature)
+    {
+        Some(auction) => show_auction_details(auction, dest_ip).await,
+        None => println!("Auction not found."),
+    }
     pause();
 }
 
+async fn search(dest_ip: &[String]) {
+    clear_screen();
+    println!("Search Auctions (leave a filter empty to skip it)");
+    let state_option: String = read_value(
+        "Auction state:\n1. Any\n2. Active\n3. Closed",
+        "Failed to read line",
+    );
+    let sort_option: String = read_value(
+        "Sort by:\n1. Ending soonest\n2. Lowest price\n3. Highest price\n4. Newest",
+        "Failed to read line",
+    );
+    let mut query = AuctionQuery {
+        state: match state_option.as_str() {
+            "2" => StateFilter::Active,
+            "3" => StateFilter::Closed,
+            _ => StateFilter::Any,
+        },
+        seller: read_optional("Seller uid:"),
+        category: read_optional("Category:"),
+        min_price: read_optional("Minimum price:"),
+        max_price: read_optional("Maximum price:"),
+        ending_before: read_optional("Ending within minutes:")
+            .map(|minutes: i64| Utc::now() + Duration::minutes(minutes)),
+        sort: match sort_option.as_str() {
+            "2" => SortKey::PriceLowest,
+            "3" => SortKey::PriceHighest,
+            "4" => SortKey::Newest,
+            _ => SortKey::EndingSoonest,
+        },
+        ..AuctionQuery::default()
+    };
+
+    loop {
+        clear_screen();
+        let page = match search_auctions(&dest_ip[0], &query).await {
+            Ok(page) => page,
+            Err(e) => {
+                println!("{}", format!("Search failed: {}", e).red());
+                pause();
+                return;
+            }
+        };
+        print_auctions(&page.auctions);
+        println!(
+            "\nPage {} of {} ({} auctions found)",
+            page.page + 1,
+            page.page_count().max(1),
+            page.total
+        );
+
+        let option: String = read_value(
+            "n. Next page | p. Previous page | Enter to go back",
+            "Failed to read line",
+        );
+        match option.as_str() {
+            "n" if page.page + 1 < page.page_count() => query.page += 1,
+            "p" if page.page > 0 => query.page -= 1,
+            "n" | "p" => {}
+            _ => return,
+        }
+    }
+}
+
+// stream auction events from a node until the user presses Enter
+async fn follow_auctions(dest_ip: &[String]) {
+    clear_screen();
+    println!("Follow Auctions (leave a filter empty to skip it)");
+    let signatures: String = read_value("Auction IDs, comma separated:", "Failed to read line");
+    let filter = EventFilter {
+        auction_signatures: signatures
+            .split(',')
+            .map(|signature| signature.trim().to_string())
+            .filter(|signature| !signature.is_empty())
+            .collect(),
+        seller: read_optional("Seller uid:"),
+        category: read_optional("Category:"),
+    };
+
+    let events = match subscribe_auction_events(&dest_ip[0], &filter).await {
+        Ok(events) => events,
+        Err(e) => {
+            println!("{}", format!("Subscription failed: {}", e).red());
+            pause();
+            return;
+        }
+    };
+    tokio::pin!(events);
+    println!("Following auctions, press Enter to stop.\n");
+
+    let mut stop = task::spawn_blocking(|| {
+        let mut input = String::new();
+        io::stdin().read_line(&mut input)
+    });
+    loop {
+        tokio::select! {
+            _ = &mut stop => return,
+            event = events.next() => match event {
+                Some(Ok(event)) => println!("{}", event.to_string().green()),
+                Some(Err(e)) => {
+                    println!("{}", format!("Subscription ended: {}", e).red());
+                    break;
+                }
+                None => {
+                    println!("{}", "Subscription ended by the node".yellow());
+                    break;
+                }
+            },
+        }
+    }
+    // the blocking read still owns stdin, let it take the Enter before the menu does
+    println!("\nPress Enter to continue...");
+    let _ = stop.await;
+}
+
+async fn show_auction_details(auction: &Auction, dest_ip: &[String]) {
+    clear_screen();
+    let metadata = &auction.metadata;
+    println!("{} ({})", auction.title(), auction.kind);
+    println!("Description: {}", metadata.description);
+    println!("Category: {}", metadata.category);
+    println!("Tags: {}", metadata.tags.join(", "));
+    for (name, value) in metadata.attributes.iter() {
+        println!("{}: {}", name, value);
+    }
+    // images are fetched from the DHT and checked against the signed content hash
+    for image_hash in metadata.images.iter() {
+        match find_content(&dest_ip[0], image_hash).await {
+            Ok(image) => {
+                let path = format!("auction_images/{}", image_hash);
+                fs::create_dir_all("auction_images").expect("Failed to create directory");
+                fs::write(&path, image).expect("Failed to write image");
+                println!("Image saved to {}", path);
+            }
+            Err(e) => println!(
+                "{}",
+                format!("Image {} unavailable: {}", image_hash, e).red()
+            ),
+        }
+    }
+}
+
+// descriptive details of a new auction, images are uploaded to the DHT by content hash
+async fn read_metadata(dest_ip: &[String]) -> AuctionMetadata {
+    let description: String = read_value("Enter a description:", "Failed to read line");
+    let category: String = read_value("Enter the category:", "Failed to read line");
+    let tags: String = read_value("Enter tags, comma separated:", "Failed to read line");
+    let attributes: String = read_value(
+        "Enter attributes as name=value, comma separated:",
+        "Failed to read line",
+    );
+    let images: String = read_value(
+        "Enter image file paths, comma separated:",
+        "Failed to read line",
+    );
+
+    let mut image_hashes = Vec::new();
+    for path in split_list(&images) {
+        match fs::read(&path) {
+            Ok(image) => match store_content(&dest_ip[0], image).await {
+                Ok(image_hash) => image_hashes.push(image_hash),
+                Err(e) => println!("{}", format!("Failed to upload {}: {}", path, e).red()),
+            },
+            Err(e) => println!("{}", format!("Failed to read {}: {}", path, e).red()),
+        }
+    }
+
+    AuctionMetadata {
+        description,
+        category,
+        tags: split_list(&tags),
+        attributes: split_list(&attributes)
+            .iter()
+            .filter_map(|attribute| attribute.split_once('='))
+            .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+            .collect(),
+        images: image_hashes,
+    }
+}
+
+fn split_list(input: &str) -> Vec<String> {
+    input
+        .split(',')
+        .map(|item| item.trim().to_string())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
 async fn history(user: &User) {
     let user: User = get_user(BOOTSTRAP_NODE_ADDRES, &user.uid).await.unwrap();
 
@@ -377,6 +1102,28 @@ async fn history(user: &User) {
     pause();
 }
 
+fn read_value<T: FromStr>(prompt: &str, error: &str) -> T {
+    println!("{}", prompt);
+    let mut input = String::new();
+    io::stdin()
+        .read_line(&mut input)
+        .expect("Failed to read line");
+    match input.trim().parse() {
+        Ok(value) => value,
+        Err(_) => panic!("{}", error),
+    }
+}
+
+// empty input leaves the value out
+fn read_optional<T: FromStr>(prompt: &str) -> Option<T> {
+    println!("{}", prompt);
+    let mut input = String::new();
+    io::stdin()
+        .read_line(&mut input)
+        .expect("Failed to read line");
+    input.trim().parse().ok()
+}
+
 fn pause() {
     let mut pause = String::new();
     println!("\nPress Enter to continue...");
@@ -1,4 +1,5 @@
 pub mod auction;
 pub mod auction_operation;
+pub mod cli;
 pub mod notifications;
 pub mod user;
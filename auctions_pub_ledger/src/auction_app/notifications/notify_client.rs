@@ -1,20 +1,26 @@
-use crate::auction_app::auction::Bid;
+use crate::auction_app::auction::Notification;
 use crate::notification_tx::notification_tx_client::NotificationTxClient;
 use crate::notification_tx::SendNotificationRequest;
+use std::time::Duration;
 use tonic::transport::Certificate;
 use tonic::transport::Channel;
 use tonic::transport::ClientTlsConfig;
+
+// Seconds to wait for a subscriber to accept the connection
+const CONNECT_TIMEOUT: u64 = 3;
+
 pub async fn send_notification(
     dest_addr: String,
-    data: Bid,
+    origin: String,
+    sequence: u64,
+    data: Notification,
 ) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
-    tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
-
-    let data_str = serde_json::to_string(&data).unwrap();
     let mut client = run_client_async(dest_addr).await?;
 
     let request = tonic::Request::new(SendNotificationRequest {
-        notification: data_str,
+        event: Some(data.into()),
+        origin,
+        sequence,
     });
     let response = client.send_notification(request).await?;
     let result_response = response.into_inner().resp;
@@ -31,9 +37,10 @@ pub async fn run_client_async(
     let channel = Channel::builder(format!("https://{}:3002", dest_addr).parse().unwrap())
         .tls_config(tls)
         .unwrap()
+        // subscribers are often offline, give up quickly and let the outbox retry
+        .connect_timeout(Duration::from_secs(CONNECT_TIMEOUT))
         .connect()
-        .await
-        .unwrap();
+        .await?;
 
     let client = NotificationTxClient::new(channel);
 
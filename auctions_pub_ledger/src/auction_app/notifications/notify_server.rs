@@ -1,13 +1,19 @@
-use crate::auction_app::auction::Bid;
 use crate::auction_app::auction::Notification;
+use crate::conversions::DecodeError;
 use crate::notification_tx::notification_tx_server::NotificationTx;
 use crate::notification_tx::notification_tx_server::NotificationTxServer;
 use crate::notification_tx::{SendNotificationRequest, SendNotificationResponse};
+use colored::*;
+use std::collections::BTreeMap;
 use std::fs;
 use tonic::{
     transport::{Identity, Server, ServerTlsConfig},
     Request, Response, Status,
 };
+
+// Last sequence number received from every node
+const SEQUENCES_FILE: &str = "notification_sequences.json";
+
 type NotificationTxResult<T> = Result<Response<T>, Status>;
 
 #[derive(Default)]
@@ -19,25 +25,135 @@ impl NotificationTx for NotificationServer {
         &self,
         request: Request<SendNotificationRequest>,
     ) -> NotificationTxResult<SendNotificationResponse> {
-        let message = request.into_inner().clone().notification;
-        let bid: Bid = serde_json::from_str(&message).unwrap();
-        println!(
-            "\nNew notification!\nBid submited by: {}\nAuction: {}\nbidding value: {}\n",
-            bid.bidder, bid.signature, bid.amount
-        );
-        let notification = Notification {
-            bidder: bid.bidder,
-            amount: bid.amount,
-            auction_signature: bid.auction_signature,
-        };
-        let serialized = serde_json::to_string_pretty(&notification).unwrap();
-
-        fs::write("auction_data_notification.json", serialized).unwrap();
+        let request = request.into_inner();
+        let notification =
+            Notification::try_from(request.event.ok_or(DecodeError::MissingField("event"))?)?;
+
+        // a retry of a notification that already arrived is acknowledged again and dropped
+        if record_sequence(&request.origin, request.sequence) {
+            show_notification(&notification);
+        }
         let resp = "Notification submieted with success".to_string();
 
         Ok(Response::new(SendNotificationResponse { resp }))
     }
 }
+
+// kept to skip duplicates and to know where to catch up from
+pub fn load_sequences() -> BTreeMap<String, u64> {
+    match fs::read_to_string(SEQUENCES_FILE) {
+        Ok(data) => serde_json::from_str(&data).expect("Failed to deserialize JSON"),
+        Err(_) => BTreeMap::new(),
+    }
+}
+
+// returns false when the notification is not newer than what was already received
+pub fn record_sequence(origin: &str, sequence: u64) -> bool {
+    let mut sequences = load_sequences();
+    let last = sequences.entry(origin.to_string()).or_insert(0);
+    if sequence <= *last {
+        return false;
+    }
+    *last = sequence;
+    let serialized = serde_json::to_string_pretty(&sequences).unwrap();
+    fs::write(SEQUENCES_FILE, serialized).expect("error writing notification sequences");
+    true
+}
+
+pub fn show_notification(notification: &Notification) {
+    match notification {
+        Notification::Bid(bid) => println!(
+            "\nNew notification!\nBid submited by: {}\nAuction: {}\nbidding value: {}\n",
+            bid.bidder, bid.auction_signature, bid.amount
+        ),
+        Notification::BiddingOpen {
+            auction_signature,
+            item_name,
+        } => println!(
+            "{}",
+            format!(
+                "\nNew notification!\nBidding is open for: {}\nAuction: {}\n",
+                item_name, auction_signature
+            )
+            .cyan()
+        ),
+        Notification::Outbid {
+            auction_signature,
+            item_name,
+            bid,
+        } => println!(
+            "{}",
+            format!(
+                "\nYou have been outbid on {}!\nNew bid of {} by {}\nAuction: {}\n",
+                item_name, bid.amount, bid.bidder, auction_signature
+            )
+            .yellow()
+        ),
+        Notification::Extended {
+            auction_signature,
+            item_name,
+            closing_time,
+        } => println!(
+            "{}",
+            format!(
+                "\nAuction for {} extended until {}\nAuction: {}\n",
+                item_name, closing_time, auction_signature
+            )
+            .cyan()
+        ),
+        Notification::Won {
+            auction_signature,
+            item_name,
+            price,
+        } => println!(
+            "{}",
+            format!(
+                "\nYou won {}!\nPrice to pay: {}\nAuction: {}\n",
+                item_name, price, auction_signature
+            )
+            .green()
+            .bold()
+        ),
+        Notification::Lost {
+            auction_signature,
+            item_name,
+        } => println!(
+            "{}",
+            format!(
+                "\nAuction for {} closed, your bid did not win\nAuction: {}\n",
+                item_name, auction_signature
+            )
+            .red()
+        ),
+        Notification::EndedWithoutBids {
+            auction_signature,
+            item_name,
+        } => println!(
+            "{}",
+            format!(
+                "\nAuction for {} ended without a winning bid\nAuction: {}\n",
+                item_name, auction_signature
+            )
+            .yellow()
+        ),
+        Notification::SellerPayout {
+            auction_signature,
+            item_name,
+            amount,
+            units_sold,
+        } => println!(
+            "{}",
+            format!(
+                "\nYour auction for {} sold {} unit(s)\nPayout: {}\nAuction: {}\n",
+                item_name, units_sold, amount, auction_signature
+            )
+            .green()
+        ),
+    }
+    let serialized = serde_json::to_string_pretty(notification).unwrap();
+
+    fs::write("auction_data_notification.json", serialized).unwrap();
+}
 pub async fn notification_server() {
     let cert = std::fs::read_to_string("tls/server.crt");
     let key = std::fs::read_to_string("tls/server.key");
@@ -1,15 +1,23 @@
-use crate::auction_app::auction_operation::client::create_user;
-use crate::cryptography::ecdsa_keys::generate_ecdsa_keypair;
+use crate::auction_app::auction::Transaction;
+use crate::auction_app::auction_operation::client::{create_user, deposit};
+use crate::cryptography::wallet::{user_id, Wallet, WalletError};
+use chrono::{DateTime, Utc};
 use colored::*;
+use k256::ecdsa::SigningKey;
 use serde::{Deserialize, Serialize};
+use sha256::digest;
 use std::fs::{self};
-use std::io::{self};
+use std::io::{self, Write};
+use std::path::Path;
 // The AuctionActivity enum is used to store the activities of the user in the auctions.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UserActivity {
     pub activity_type: String,
     pub auction_signature: String,
     pub amount: f32,
+    // signature of the operation the entry was recorded for, empty for settlement entries
+    #[serde(default)]
+    pub signature: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -32,52 +40,189 @@ impl User {
         }
     }
 
-    pub fn add_credits(&mut self, amount: f32) {
-        self.credits += amount;
+    // whether the operation with this signature is already in the activity as `activity_type`
+    pub fn recorded(&self, activity_type: &str, signature: &str) -> bool {
+        self.activity.iter().any(|activity| {
+            activity.activity_type == activity_type && activity.signature == signature
+        })
     }
 }
 
-pub async fn register_user(peer: &str) -> User {
-    println!("Please enter your Username:");
-    let mut username = String::new();
-    io::stdin()
-        .read_line(&mut username)
-        .expect("Failed to read line");
+// Credits a user adds to their own account, signed so nobody else can top it up
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Deposit {
+    pub user_id: String,
+    pub amount: f32,
+    #[serde(with = "chrono::serde::ts_seconds")]
+    pub timestamp: DateTime<Utc>,
+    pub signature: String,
+}
 
-    let (_, public_key) = generate_ecdsa_keypair();
-    let user = User {
-        uid: hex::encode(public_key.to_sec1_bytes()),
-        user_name: username.trim().to_string(),
-        credits: 0.0,
-        auctions_winner: Vec::new(),
-        activity: Vec::new(),
-    };
+impl Deposit {
+    // content signed by the user: sha256(uid + amount + "deposit" + unix timestamp)
+    pub fn signed_content(&self) -> String {
+        digest(
+            self.user_id.clone()
+                + &self.amount.to_string()
+                + "deposit"
+                + &self.timestamp.timestamp().to_string(),
+        )
+    }
+}
+
+// Change to a user record, only the node keeping the record applies it. Activity entries are
+// the signed auction transactions themselves, so their amounts come from what the user signed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum UserOperation {
+    Deposit(Deposit),
+    Activity(Box<Transaction>),
+}
+
+impl UserOperation {
+    // uid of the user whose record changes, who must have signed the operation
+    pub fn user_id(&self) -> &str {
+        match self {
+            UserOperation::Deposit(deposit) => &deposit.user_id,
+            UserOperation::Activity(transaction) => transaction.signer(),
+        }
+    }
+
+    pub fn signature(&self) -> &str {
+        match self {
+            UserOperation::Deposit(deposit) => &deposit.signature,
+            UserOperation::Activity(transaction) => transaction.signature(),
+        }
+    }
+
+    pub fn signed_content(&self) -> String {
+        match self {
+            UserOperation::Deposit(deposit) => deposit.signed_content(),
+            UserOperation::Activity(transaction) => transaction.signed_content(),
+        }
+    }
+}
+
+pub async fn register_user(peer: &str, wallet_path: &str) -> (User, SigningKey) {
+    loop {
+        println!("Please enter your Username:");
+        let mut username = String::new();
+        io::stdin()
+            .read_line(&mut username)
+            .expect("Failed to read line");
+        let Some(passphrase) = read_new_passphrase("Choose a passphrase for your wallet:") else {
+            println!("{}", "The passphrases do not match".red());
+            continue;
+        };
+
+        let (user, private_key) =
+            match create_account(wallet_path, username.trim(), &passphrase).await {
+                Ok(account) => account,
+                Err(e) => {
+                    println!("{}", format!("Could not create the account: {}", e).red());
+                    continue;
+                }
+            };
+        match create_user(peer, &private_key, &user).await {
+            Ok(response) => println!("{}", response.green()),
+            Err(e) => println!(
+                "{}",
+                format!("Error registering user {}\n{}", &user.uid, e).red()
+            ),
+        }
+        return (user, private_key);
+    }
+}
+
+// New key pair in the wallet and local user record. Registering the user with the nodes is
+// left to the caller, the account is kept even when they can not be reached.
+pub async fn create_account(
+    wallet_path: &str,
+    user_name: &str,
+    passphrase: &str,
+) -> Result<(User, SigningKey), WalletError> {
+    let mut wallet = Wallet::open(wallet_path)?;
+    let private_key = wallet.create_account(user_name, passphrase)?;
+    wallet.save(wallet_path)?;
+
+    let user = User::new(user_name.to_string(), user_id(&private_key));
 
     // Path to the JSON file where users are stored
     let file_path = format!("users/{}.json", user.user_name);
     let user_json = serde_json::to_string_pretty(&user).expect("Failed to serialize users");
 
     save_user_in_file(&user_json, file_path).await;
+    Ok((user, private_key))
+}
 
-    match create_user(peer, &user_json).await {
-        Ok(response) => println!("{}", response.green()),
-        Err(e) => println!(
-            "{}",
-            format!("Error registering user {}\n{}", &user.uid, e).red()
-        ),
+// whether unlocking the user moves a key file written in the clear into the wallet, the
+// passphrase is then a new one and should be asked twice
+pub fn imports_key_file(wallet_path: &str, user: &User) -> Result<bool, WalletError> {
+    let wallet = Wallet::open(wallet_path)?;
+    Ok(wallet.account(&user.user_name).is_err() && Path::new(&user.uid).exists())
+}
+
+// Signing key of a local user from the wallet. A key file written in the clear before the
+// wallet existed is moved into it, under the passphrase, the first time it is used. The key
+// file is only removed once the saved wallet unlocks to the same key.
+pub fn unlock_account(
+    wallet_path: &str,
+    user: &User,
+    passphrase: &str,
+) -> Result<SigningKey, WalletError> {
+    let mut wallet = Wallet::open(wallet_path)?;
+    let private_key = if imports_key_file(wallet_path, user)? {
+        let private_key = wallet.import_key_file(&user.user_name, &user.uid, passphrase)?;
+        if user_id(&private_key) != user.uid {
+            return Err(WalletError::InvalidKey);
+        }
+        wallet.save(wallet_path)?;
+        let saved = Wallet::open(wallet_path)?.unlock(&user.user_name, passphrase)?;
+        if saved.to_bytes() != private_key.to_bytes() {
+            return Err(WalletError::InvalidKey);
+        }
+        fs::remove_file(&user.uid)?;
+        private_key
+    } else {
+        wallet.unlock(&user.user_name, passphrase)?
+    };
+
+    // the wallet account may hold the key of another user with the same name
+    if user_id(&private_key) != user.uid {
+        return Err(WalletError::InvalidKey);
     }
-    user
+    Ok(private_key)
+}
+
+// the prompt goes to stderr so commands keep their output clean. The passphrase is read
+// from the terminal without echo, or from stdin when there is no terminal.
+pub fn read_passphrase(prompt: &str) -> String {
+    eprintln!("{}", prompt);
+    io::stderr().flush().unwrap();
+    if let Ok(passphrase) = rpassword::read_password() {
+        return passphrase;
+    }
+    let mut passphrase = String::new();
+    io::stdin()
+        .read_line(&mut passphrase)
+        .expect("Failed to read line");
+    passphrase.trim_end_matches(['\r', '\n']).to_string()
+}
+
+// a passphrase the user typed twice the same, none when they differ
+pub fn read_new_passphrase(prompt: &str) -> Option<String> {
+    let passphrase = read_passphrase(prompt);
+    (read_passphrase("Repeat the passphrase:") == passphrase).then_some(passphrase)
 }
 pub async fn save_user_in_file(user_json: &String, file_path: String) {
     fs::write(file_path, user_json).expect("Failed to write to users.json");
 }
 pub async fn load_users_from_file(file_path: &str) -> Result<User, Box<dyn std::error::Error>> {
-    let data = fs::read_to_string(file_path).unwrap();
+    let data = fs::read_to_string(file_path)?;
 
     Ok(serde_json::from_str(&data)?)
 }
 
-pub async fn add_credits(peer: &str, user: &mut User) {
+pub async fn add_credits(peer: &str, private_key: &SigningKey, user: &mut User) {
     std::process::Command::new("clear").status().unwrap();
 
     println!("Adding credits to your account.");
@@ -86,14 +231,16 @@ pub async fn add_credits(peer: &str, user: &mut User) {
 
     io::stdin().read_line(&mut amount_str).unwrap();
     let amount: f32 = amount_str.trim().parse().unwrap();
-    user.add_credits(amount);
 
-    let user_json = serde_json::to_string_pretty(&user).expect("Failed to serialize users");
-    match create_user(peer, &user_json).await {
-        Ok(response) => println!("{}", response.green()),
+    // the node keeping the record adds the credits, the local copy follows its answer
+    match deposit(peer, private_key, amount).await {
+        Ok(account) => {
+            *user = account;
+            println!("{}", format!("Your balance: ${}", user.credits).green())
+        }
         Err(e) => println!(
             "{}",
-            format!("Error updation user {}\n{}", &user.user_name, e).red()
+            format!("Error adding credits to user {}\n{}", &user.user_name, e).red()
         ),
     }
 
@@ -0,0 +1,142 @@
+use crate::auction_client::{AuctionClient, ClientError};
+use k256::ecdsa::SigningKey;
+use std::path::Path;
+use std::time::Duration;
+use tonic::transport::{Certificate, ClientTlsConfig, Endpoint};
+
+// Port the auction service listens on
+pub const DEFAULT_PORT: u16 = 3000;
+// Name the node certificates are issued for
+pub const DEFAULT_DOMAIN_NAME: &str = "auctiondht.fc.up.pt";
+
+// CA the node certificates are checked against and the name they must be issued for
+#[derive(Debug, Clone)]
+pub struct TlsConfig {
+    pub ca_certificate: Vec<u8>,
+    pub domain_name: String,
+}
+
+impl TlsConfig {
+    pub fn new(ca_certificate: impl Into<Vec<u8>>, domain_name: &str) -> Self {
+        TlsConfig {
+            ca_certificate: ca_certificate.into(),
+            domain_name: domain_name.to_string(),
+        }
+    }
+
+    pub fn from_pem_file(path: impl AsRef<Path>, domain_name: &str) -> Result<Self, ClientError> {
+        Ok(TlsConfig::new(std::fs::read(path)?, domain_name))
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct AuctionClientBuilder {
+    endpoints: Vec<String>,
+    port: Option<u16>,
+    tls: Option<TlsConfig>,
+    signing_key: Option<SigningKey>,
+    notification_addr: String,
+    connect_timeout: Option<Duration>,
+}
+
+impl AuctionClientBuilder {
+    // a node address, an IP or host name gets the service port. Nodes are tried in the order
+    // they were added until one answers.
+    pub fn endpoint(mut self, endpoint: &str) -> Self {
+        self.endpoints.push(endpoint.to_string());
+        self
+    }
+
+    pub fn endpoints<I, S>(mut self, endpoints: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.endpoints.extend(
+            endpoints
+                .into_iter()
+                .map(|endpoint| endpoint.as_ref().to_string()),
+        );
+        self
+    }
+
+    // port used for endpoints given without one, `DEFAULT_PORT` otherwise
+    pub fn port(mut self, port: u16) -> Self {
+        self.port = Some(port);
+        self
+    }
+
+    // without TLS the nodes are reached over plain HTTP/2
+    pub fn tls(mut self, tls: TlsConfig) -> Self {
+        self.tls = Some(tls);
+        self
+    }
+
+    // key the client signs transactions with, its public key is the user id
+    pub fn signing_key(mut self, signing_key: SigningKey) -> Self {
+        self.signing_key = Some(signing_key);
+        self
+    }
+
+    // address the nodes deliver the user's notifications to, none when left empty
+    pub fn notification_addr(mut self, addr: &str) -> Self {
+        self.notification_addr = addr.to_string();
+        self
+    }
+
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    // checks the endpoints, no connection is made until the first call
+    pub fn build(self) -> Result<AuctionClient, ClientError> {
+        if self.endpoints.is_empty() {
+            return Err(ClientError::NoEndpoints);
+        }
+        let scheme = if self.tls.is_some() { "https" } else { "http" };
+        let port = self.port.unwrap_or(DEFAULT_PORT);
+
+        let mut endpoints = Vec::new();
+        for endpoint in self.endpoints.iter() {
+            let uri = uri(endpoint, scheme, port);
+            let mut target = Endpoint::from_shared(uri)
+                .map_err(|_| ClientError::InvalidEndpoint(endpoint.clone()))?;
+            if let Some(tls) = &self.tls {
+                target = target.tls_config(
+                    ClientTlsConfig::new()
+                        .ca_certificate(Certificate::from_pem(&tls.ca_certificate))
+                        .domain_name(tls.domain_name.clone()),
+                )?;
+            }
+            if let Some(timeout) = self.connect_timeout {
+                target = target.connect_timeout(timeout);
+            }
+            endpoints.push(target);
+        }
+
+        Ok(AuctionClient::new(
+            endpoints,
+            self.signing_key,
+            self.notification_addr,
+        ))
+    }
+}
+
+// "10.10.0.3" becomes "https://10.10.0.3:3000", full URIs and addresses with a port keep
+// theirs
+fn uri(endpoint: &str, scheme: &str, port: u16) -> String {
+    if endpoint.contains("://") {
+        endpoint.to_string()
+    } else if endpoint.parse::<std::net::SocketAddr>().is_ok()
+        || endpoint
+            .split_once(':')
+            .is_some_and(|(_, port)| port.parse::<u16>().is_ok())
+    {
+        format!("{}://{}", scheme, endpoint)
+    } else if endpoint.parse::<std::net::Ipv6Addr>().is_ok() {
+        format!("{}://[{}]:{}", scheme, endpoint, port)
+    } else {
+        format!("{}://{}:{}", scheme, endpoint, port)
+    }
+}
@@ -0,0 +1,79 @@
+use crate::auction_server::auction_handler::Rejection;
+use crate::conversions::DecodeError;
+use std::fmt;
+use tonic::Status;
+
+// Everything an `AuctionClient` call can fail with
+#[derive(Debug)]
+pub enum ClientError {
+    NoEndpoints,
+    InvalidEndpoint(String),
+    // the operation signs on behalf of the user but the client has no signing key
+    MissingSigningKey,
+    Io(std::io::Error),
+    // no endpoint could be reached
+    Transport(tonic::transport::Error),
+    // the node answered with an error, boxed as statuses are large
+    Status(Box<Status>),
+    // the node refused the transaction
+    Rejected(Rejection),
+    Decode(DecodeError),
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ClientError::NoEndpoints => write!(f, "no endpoint to connect to"),
+            ClientError::InvalidEndpoint(endpoint) => {
+                write!(f, "`{}` is not a valid endpoint", endpoint)
+            }
+            ClientError::MissingSigningKey => write!(f, "the client has no signing key"),
+            ClientError::Io(error) => write!(f, "{}", error),
+            ClientError::Transport(error) => write!(f, "{}", error),
+            ClientError::Status(status) => write!(f, "{}", status.message()),
+            ClientError::Rejected(rejection) => write!(f, "{}", rejection),
+            ClientError::Decode(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for ClientError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ClientError::Io(error) => Some(error),
+            ClientError::Transport(error) => Some(error),
+            ClientError::Decode(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for ClientError {
+    fn from(error: std::io::Error) -> Self {
+        ClientError::Io(error)
+    }
+}
+
+impl From<tonic::transport::Error> for ClientError {
+    fn from(error: tonic::transport::Error) -> Self {
+        ClientError::Transport(error)
+    }
+}
+
+impl From<Status> for ClientError {
+    fn from(status: Status) -> Self {
+        ClientError::Status(Box::new(status))
+    }
+}
+
+impl From<Rejection> for ClientError {
+    fn from(rejection: Rejection) -> Self {
+        ClientError::Rejected(rejection)
+    }
+}
+
+impl From<DecodeError> for ClientError {
+    fn from(error: DecodeError) -> Self {
+        ClientError::Decode(error)
+    }
+}
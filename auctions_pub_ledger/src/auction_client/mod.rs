@@ -0,0 +1,448 @@
+// Client library of the auction network, for services that embed it. It only talks to the
+// nodes: nothing is read from or written to the working directory and nothing is printed,
+// every failure is returned as a `ClientError`.
+pub mod builder;
+pub mod error;
+
+pub use builder::{AuctionClientBuilder, TlsConfig, DEFAULT_DOMAIN_NAME, DEFAULT_PORT};
+pub use error::ClientError;
+// types of the requests and answers of the client, so embedders need nothing else
+pub use crate::auction_app::auction::{
+    Auction, AuctionCancel, Bid, BidCommitment, BidReveal, DutchAccept, Endpoint, Notification,
+    Subscription, Transaction,
+};
+pub use crate::auction_app::user::{Deposit, User, UserOperation};
+pub use crate::auction_server::auction_events::{AuctionEvent, EventFilter};
+pub use crate::auction_server::auction_handler::{Outcome, Rejection};
+pub use crate::auction_server::notification_outbox::SequencedNotification;
+pub use crate::auction_server::search::{AuctionQuery, SearchPage};
+
+use crate::auction_server::auction_handler::from_response;
+use crate::auction_server::user_sessions::login_content;
+use crate::auction_tx::{
+    auction_tx_client::AuctionTxClient, ApplyUserOperationRequest, CatchUpNotificationsRequest,
+    CreateUsersRequest, GetAuctionsRequest, GetUsersRequest, LoginRequest, RequestChallengeRequest,
+    SearchAuctionsRequest, SubmitTransactionRequest, SubscribeAuctionEventsRequest,
+};
+use crate::conversions::{timestamp, DecodeError};
+use chrono::{DateTime, Utc};
+use k256::ecdsa::{signature::Signer, Signature, SigningKey};
+use rand_core::{OsRng, RngCore};
+use std::sync::{Arc, Mutex};
+use tokio_stream::{Stream, StreamExt};
+use tonic::transport::Channel;
+use tonic::Request;
+
+// Proof that the signing user logged in, required by user updates. Sessions are held by
+// the node that issued them, calls made under one go to that node only.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Session {
+    pub user_id: String,
+    pub token: String,
+    pub expires_at: DateTime<Utc>,
+    // URI of the endpoint that issued the session
+    pub endpoint: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct AuctionClient {
+    endpoints: Vec<tonic::transport::Endpoint>,
+    // channel of every endpoint that answered, shared by the clones of the client
+    channels: Arc<Mutex<Vec<Option<Channel>>>>,
+    signing_key: Option<SigningKey>,
+    notification_addr: String,
+}
+
+impl AuctionClient {
+    pub fn builder() -> AuctionClientBuilder {
+        AuctionClientBuilder::default()
+    }
+
+    fn new(
+        endpoints: Vec<tonic::transport::Endpoint>,
+        signing_key: Option<SigningKey>,
+        notification_addr: String,
+    ) -> Self {
+        AuctionClient {
+            channels: Arc::new(Mutex::new(vec![None; endpoints.len()])),
+            endpoints,
+            signing_key,
+            notification_addr,
+        }
+    }
+
+    // gRPC client of the first endpoint that answers. Channels are kept and reconnect by
+    // themselves, so calls stay on the endpoint that answered first.
+    pub async fn connect(&self) -> Result<AuctionTxClient<Channel>, ClientError> {
+        Ok(self.connect_any().await?.1)
+    }
+
+    // the client of the endpoint with the URI `endpoint`, never another one
+    pub async fn connect_to(
+        &self,
+        endpoint: &str,
+    ) -> Result<AuctionTxClient<Channel>, ClientError> {
+        let index = self
+            .endpoints
+            .iter()
+            .position(|known| *known.uri() == *endpoint)
+            .ok_or_else(|| ClientError::InvalidEndpoint(endpoint.to_string()))?;
+        Ok(AuctionTxClient::new(self.channel(index).await?))
+    }
+
+    async fn connect_any(&self) -> Result<(usize, AuctionTxClient<Channel>), ClientError> {
+        let mut last_error = ClientError::NoEndpoints;
+        for index in 0..self.endpoints.len() {
+            match self.channel(index).await {
+                Ok(channel) => return Ok((index, AuctionTxClient::new(channel))),
+                Err(e) => last_error = e,
+            }
+        }
+        Err(last_error)
+    }
+
+    async fn channel(&self, index: usize) -> Result<Channel, ClientError> {
+        if let Some(channel) = self.channels.lock().unwrap()[index].clone() {
+            return Ok(channel);
+        }
+        let channel = self.endpoints[index].connect().await?;
+        self.channels.lock().unwrap()[index] = Some(channel.clone());
+        Ok(channel)
+    }
+
+    // id of the signing user, the hex encoded compressed public key
+    pub fn user_id(&self) -> Result<String, ClientError> {
+        let signing_key = self.key()?;
+        Ok(hex::encode(signing_key.verifying_key().to_sec1_bytes()))
+    }
+
+    // hex encoded signature of `content`, as the nodes verify it
+    pub fn sign(&self, content: &str) -> Result<String, ClientError> {
+        let signature: Signature = self.key()?.sign(content.as_bytes());
+        Ok(hex::encode(signature.to_bytes()))
+    }
+
+    fn key(&self) -> Result<&SigningKey, ClientError> {
+        self.signing_key
+            .as_ref()
+            .ok_or(ClientError::MissingSigningKey)
+    }
+
+    // Hand an already signed transaction to the nodes. A rejection is returned as
+    // `ClientError::Rejected` with its reason.
+    pub async fn submit_transaction(
+        &self,
+        transaction: Transaction,
+    ) -> Result<Outcome, ClientError> {
+        // the node sets the hop limit of transactions coming from clients
+        let request = Request::new(SubmitTransactionRequest {
+            subscriber_addr: self.notification_addr.clone(),
+            tx: Some(transaction.into()),
+            ttl: None,
+            visited: Vec::new(),
+        });
+        let response = self.connect().await?.submit_transaction(request).await?;
+
+        Ok(from_response(&response.into_inner())?)
+    }
+
+    // Put an auction up for sale as the signing user. The seller and signature are filled
+    // in, the signed auction is returned with the outcome: its signature is the auction id.
+    pub async fn create_auction(
+        &self,
+        mut auction: Auction,
+    ) -> Result<(Auction, Outcome), ClientError> {
+        auction.user_id = self.user_id()?;
+        auction.signature = self.sign(&auction.signed_content())?;
+
+        let outcome = self
+            .submit_transaction(Transaction::Auction(Box::new(auction.clone())))
+            .await?;
+        Ok((auction, outcome))
+    }
+
+    pub async fn bid(&self, auction_signature: &str, amount: f32) -> Result<Outcome, ClientError> {
+        let bid = self.signed_bid(auction_signature, amount)?;
+        self.submit_transaction(Transaction::Bid(bid)).await
+    }
+
+    // Commit to a sealed bid. The returned reveal is all the bid is known by until the
+    // reveal phase, the caller keeps it to send it then.
+    pub async fn commit_bid(
+        &self,
+        auction_signature: &str,
+        amount: f32,
+    ) -> Result<(BidReveal, Outcome), ClientError> {
+        let mut salt = [0u8; 16];
+        OsRng.fill_bytes(&mut salt);
+        let reveal = BidReveal {
+            bid: self.signed_bid(auction_signature, amount)?,
+            salt: hex::encode(salt),
+        };
+        let mut commitment = BidCommitment {
+            bidder: reveal.bid.bidder.clone(),
+            auction_signature: auction_signature.to_string(),
+            commitment: reveal.commitment(),
+            signature: String::new(),
+        };
+        commitment.signature = self.sign(&commitment.signed_content())?;
+
+        let outcome = self
+            .submit_transaction(Transaction::Commit(commitment))
+            .await?;
+        Ok((reveal, outcome))
+    }
+
+    pub async fn reveal_bid(&self, reveal: BidReveal) -> Result<Outcome, ClientError> {
+        self.submit_transaction(Transaction::Reveal(reveal)).await
+    }
+
+    // take a Dutch auction at its current price
+    pub async fn accept_dutch(&self, auction_signature: &str) -> Result<Outcome, ClientError> {
+        let mut accept = DutchAccept {
+            bidder: self.user_id()?,
+            auction_signature: auction_signature.to_string(),
+            timestamp: Utc::now(),
+            signature: String::new(),
+        };
+        accept.signature = self.sign(&accept.signed_content())?;
+        self.submit_transaction(Transaction::Accept(accept)).await
+    }
+
+    pub async fn cancel_auction(&self, auction_signature: &str) -> Result<Outcome, ClientError> {
+        let mut cancel = AuctionCancel {
+            seller: self.user_id()?,
+            auction_signature: auction_signature.to_string(),
+            signature: String::new(),
+        };
+        cancel.signature = self.sign(&cancel.signed_content())?;
+        self.submit_transaction(Transaction::Cancel(cancel)).await
+    }
+
+    // follow an auction, or stop following it when `subscribe` is false
+    pub async fn subscribe(
+        &self,
+        auction_signature: &str,
+        subscribe: bool,
+    ) -> Result<Outcome, ClientError> {
+        let mut subscription = Subscription {
+            subscriber: self.user_id()?,
+            auction_signature: auction_signature.to_string(),
+            subscribe,
+            timestamp: Utc::now(),
+            signature: String::new(),
+        };
+        subscription.signature = self.sign(&subscription.signed_content())?;
+        self.submit_transaction(Transaction::Subscription(subscription))
+            .await
+    }
+
+    // the node stamps the bid when it accepts it
+    fn signed_bid(&self, auction_signature: &str, amount: f32) -> Result<Bid, ClientError> {
+        let mut bid = Bid {
+            bidder: self.user_id()?,
+            amount,
+            signature: String::new(),
+            auction_signature: auction_signature.to_string(),
+            timestamp: DateTime::<Utc>::default(),
+            salt: String::new(),
+        };
+        bid.signature = self.sign(&bid.signed_content())?;
+        Ok(bid)
+    }
+
+    // every auction the node holds
+    pub async fn get_auctions(&self) -> Result<Vec<Auction>, ClientError> {
+        let response = self
+            .connect()
+            .await?
+            .get_auctions(Request::new(GetAuctionsRequest {}))
+            .await?;
+
+        Ok(response
+            .into_inner()
+            .auction_list
+            .into_iter()
+            .map(Auction::try_from)
+            .collect::<Result<_, _>>()?)
+    }
+
+    pub async fn search_auctions(&self, query: &AuctionQuery) -> Result<SearchPage, ClientError> {
+        let request = Request::new(SearchAuctionsRequest {
+            filter: Some(query.clone().into()),
+        });
+        let results = self
+            .connect()
+            .await?
+            .search_auctions(request)
+            .await?
+            .into_inner()
+            .results
+            .ok_or(DecodeError::MissingField("results"))?;
+
+        Ok(results.try_into()?)
+    }
+
+    // Follow auctions over the client's own connection. The stream ends when the node drops
+    // the subscription.
+    pub async fn subscribe_auction_events(
+        &self,
+        filter: &EventFilter,
+    ) -> Result<impl Stream<Item = Result<AuctionEvent, ClientError>>, ClientError> {
+        let request = Request::new(SubscribeAuctionEventsRequest::from(filter.clone()));
+        let events = self
+            .connect()
+            .await?
+            .subscribe_auction_events(request)
+            .await?
+            .into_inner();
+
+        Ok(events.map(|event| Ok(AuctionEvent::try_from(event?)?)))
+    }
+
+    // endpoint of the signing user at `addr`, signed now
+    pub fn endpoint(&self, addr: &str) -> Result<Endpoint, ClientError> {
+        let mut endpoint = Endpoint {
+            user_id: self.user_id()?,
+            addr: addr.to_string(),
+            timestamp: Utc::now(),
+            signature: String::new(),
+        };
+        endpoint.signature = self.sign(&endpoint.signed_content())?;
+        Ok(endpoint)
+    }
+
+    // notifications the node queued for the user of `endpoint` after `since`, with the node
+    // address they are numbered under. The node delivers the next ones to the endpoint.
+    pub async fn catch_up_notifications(
+        &self,
+        endpoint: &Endpoint,
+        since: u64,
+    ) -> Result<(String, Vec<SequencedNotification>), ClientError> {
+        let request = Request::new(CatchUpNotificationsRequest {
+            subscriber: endpoint.user_id.clone(),
+            since,
+            endpoint: Some(endpoint.clone().into()),
+        });
+        let response = self
+            .connect()
+            .await?
+            .catch_up_notifications(request)
+            .await?
+            .into_inner();
+
+        let notifications = response
+            .notifications
+            .into_iter()
+            .map(TryInto::try_into)
+            .collect::<Result<_, _>>()?;
+
+        Ok((response.origin, notifications))
+    }
+
+    // Sign a challenge of the node with the user's key. The session is pinned to the
+    // endpoint that issued it.
+    pub async fn login(&self) -> Result<Session, ClientError> {
+        let user_id = self.user_id()?;
+        let (index, mut client) = self.connect_any().await?;
+
+        let nonce = client
+            .request_challenge(Request::new(RequestChallengeRequest {
+                user_id: user_id.clone(),
+            }))
+            .await?
+            .into_inner()
+            .nonce;
+        let request = Request::new(LoginRequest {
+            user_id: user_id.clone(),
+            signature: self.sign(&login_content(&user_id, &nonce))?,
+            nonce,
+        });
+        let response = client.login(request).await?.into_inner();
+
+        Ok(Session {
+            user_id,
+            token: response.session,
+            expires_at: timestamp(response.expires_at, "expires_at")?,
+            endpoint: self.endpoints[index].uri().to_string(),
+        })
+    }
+
+    // register the user the session belongs to, the node starts their record without credits
+    pub async fn create_user(&self, session: &Session, user: &User) -> Result<String, ClientError> {
+        let request = Request::new(CreateUsersRequest {
+            account: Some(user.clone().into()),
+            session: session.token.clone(),
+        });
+        let response = self
+            .connect_to(&session.endpoint)
+            .await?
+            .create_users(request)
+            .await?;
+
+        Ok(response.into_inner().response)
+    }
+
+    pub async fn get_user(&self, id: &str) -> Result<User, ClientError> {
+        let request = Request::new(GetUsersRequest { id: id.to_string() });
+        let account = self
+            .connect()
+            .await?
+            .get_users(request)
+            .await?
+            .into_inner()
+            .account
+            .ok_or(DecodeError::MissingField("account"))?;
+
+        Ok(User::from(account))
+    }
+
+    // credits added to the signing user's record, answered with the updated record
+    pub async fn deposit(&self, session: &Session, amount: f32) -> Result<User, ClientError> {
+        let mut deposit = Deposit {
+            user_id: self.user_id()?,
+            amount,
+            timestamp: Utc::now(),
+            signature: String::new(),
+        };
+        deposit.signature = self.sign(&deposit.signed_content())?;
+        self.apply_user_operation(session, UserOperation::Deposit(deposit))
+            .await
+    }
+
+    // record a transaction in the activity of the user that signed it
+    pub async fn record_activity(
+        &self,
+        session: &Session,
+        transaction: &Transaction,
+    ) -> Result<User, ClientError> {
+        self.apply_user_operation(
+            session,
+            UserOperation::Activity(Box::new(transaction.clone())),
+        )
+        .await
+    }
+
+    // applied under the session of the user that signed the operation, on the node that
+    // issued it
+    pub async fn apply_user_operation(
+        &self,
+        session: &Session,
+        operation: UserOperation,
+    ) -> Result<User, ClientError> {
+        let request = Request::new(ApplyUserOperationRequest {
+            operation: Some(operation.into()),
+            session: session.token.clone(),
+        });
+        let account = self
+            .connect_to(&session.endpoint)
+            .await?
+            .apply_user_operation(request)
+            .await?
+            .into_inner()
+            .account
+            .ok_or(DecodeError::MissingField("account"))?;
+
+        Ok(User::from(account))
+    }
+}
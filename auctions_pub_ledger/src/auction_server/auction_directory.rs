@@ -0,0 +1,136 @@
+use crate::auction_app::auction::Auction;
+use crate::auction_app::auction_operation::dht::{auction_key, index_key};
+use crate::kademlia_node_search::node::Node;
+use colored::*;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+// Requests go through the node's own kademlia server, which stores the value locally and
+// forwards it to the closest nodes. The node lock is released before sending since the
+// request handlers take it too.
+async fn store_value(node: &Arc<Mutex<Node>>, key: Vec<u8>, value: Vec<u8>) -> bool {
+    let (client, request, addr) = {
+        let node_lock = node.lock().await;
+        let request = node_lock.client.create_store_node_request(
+            &node_lock.keypair,
+            node_lock.id.to_vec(),
+            key,
+            value,
+        );
+        (
+            node_lock.client.clone(),
+            request,
+            node_lock.addr.to_string(),
+        )
+    };
+
+    match client.send_store_request(request, addr).await {
+        Ok(response) => response.success,
+        Err(e) => {
+            eprintln!(
+                "{}",
+                format!("Failed to store value in the DHT: {}", e).red()
+            );
+            false
+        }
+    }
+}
+
+async fn find_value(node: &Arc<Mutex<Node>>, key: Vec<u8>) -> Option<Vec<u8>> {
+    let (client, request, addr) = {
+        let node_lock = node.lock().await;
+        let request = node_lock.client.create_find_value_request(
+            &node_lock.keypair,
+            node_lock.id.to_vec(),
+            key,
+        );
+        (
+            node_lock.client.clone(),
+            request,
+            node_lock.addr.to_string(),
+        )
+    };
+
+    client
+        .send_find_value_request(request, addr)
+        .await
+        .ok()
+        .map(|response| response.value)
+        .filter(|value| !value.is_empty())
+}
+
+// Index updates of this node go one at a time, concurrent publishes of different auctions
+// would otherwise read the same index and drop each other's entry.
+static INDEX_LOCK: Mutex<()> = Mutex::const_new(());
+
+// Writes of other nodes can still overwrite the index between our read and write, the index
+// is read back after every write and written again until it holds the change.
+const INDEX_ATTEMPTS: usize = 5;
+
+async fn find_index(node: &Arc<Mutex<Node>>, key: Vec<u8>) -> Vec<String> {
+    find_value(node, key)
+        .await
+        .and_then(|index| serde_json::from_slice(&index).ok())
+        .unwrap_or_default()
+}
+
+// lists the auction in the index, or drops it from the index when `listed` is false
+async fn update_index(
+    node: &Arc<Mutex<Node>>,
+    key: Vec<u8>,
+    auction_signature: &str,
+    listed: bool,
+) {
+    let _updating = INDEX_LOCK.lock().await;
+    for _ in 0..INDEX_ATTEMPTS {
+        let mut auction_signatures = find_index(node, key.clone()).await;
+        if auction_signatures
+            .iter()
+            .any(|known| known == auction_signature)
+            == listed
+        {
+            return;
+        }
+        if listed {
+            auction_signatures.push(auction_signature.to_string());
+        } else {
+            auction_signatures.retain(|known| known != auction_signature);
+        }
+        if !store_value(
+            node,
+            key.clone(),
+            serde_json::to_vec(&auction_signatures).unwrap(),
+        )
+        .await
+        {
+            return;
+        }
+    }
+    eprintln!(
+        "{}",
+        format!(
+            "Index update for auction {} did not settle",
+            auction_signature
+        )
+        .red()
+    );
+}
+
+// publish the current state of an auction under its ID and list it in the global index and
+// in its category index. Closed and cancelled auctions leave the indexes, their record stays
+// under their ID.
+pub async fn publish_auction(node: Arc<Mutex<Node>>, auction: Auction) {
+    let record = serde_json::to_vec(&auction).unwrap();
+    if store_value(&node, auction_key(&auction.signature), record).await {
+        println!("Auction {} published to the DHT\n", auction.signature);
+    }
+
+    let listed = matches!(auction.state(), "active" | "upcoming");
+    let mut indexes = vec![index_key(None)];
+    if !auction.metadata.category.trim().is_empty() {
+        indexes.push(index_key(Some(&auction.metadata.category)));
+    }
+    for key in indexes {
+        update_index(&node, key, &auction.signature, listed).await;
+    }
+}
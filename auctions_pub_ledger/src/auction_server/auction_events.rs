@@ -0,0 +1,145 @@
+use crate::auction_app::auction::{Auction, Bid};
+use crate::auction_server::settlement::{settle, Award};
+use chrono::{DateTime, Utc};
+use std::fmt;
+use tokio::sync::broadcast;
+
+// Events a node keeps for a slow subscriber before the subscriber is dropped
+pub const EVENT_BUFFER: usize = 256;
+
+pub type EventSender = broadcast::Sender<AuctionEvent>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum EventKind {
+    NewBid(Bid),
+    // a late bid pushed the close of a soft-close auction back
+    Extended { closing_time: DateTime<Utc> },
+    // the auction stopped taking transactions, either cancelled or past its settlement time
+    Closed { cancelled: bool },
+    Settled { awards: Vec<Award> },
+}
+
+// Change to an auction streamed to the clients following it. Seller and category travel
+// with the event so subscribers can filter on them without the record.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuctionEvent {
+    pub auction_signature: String,
+    pub seller: String,
+    pub category: String,
+    pub kind: EventKind,
+}
+
+impl fmt::Display for AuctionEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.kind {
+            EventKind::NewBid(bid) => write!(
+                f,
+                "New bid of {} by {} on auction {}",
+                bid.amount, bid.bidder, self.auction_signature
+            ),
+            EventKind::Extended { closing_time } => write!(
+                f,
+                "Auction {} extended until {}",
+                self.auction_signature, closing_time
+            ),
+            EventKind::Closed { cancelled: true } => {
+                write!(f, "Auction {} cancelled", self.auction_signature)
+            }
+            EventKind::Closed { cancelled: false } => {
+                write!(f, "Auction {} closed", self.auction_signature)
+            }
+            EventKind::Settled { awards } if awards.is_empty() => write!(
+                f,
+                "Auction {} settled without a winner",
+                self.auction_signature
+            ),
+            EventKind::Settled { awards } => {
+                write!(f, "Auction {} settled:", self.auction_signature)?;
+                for award in awards {
+                    write!(f, " {} pays {};", award.bid.bidder, award.price)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+// Auctions a subscriber follows, filters left empty match every auction
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct EventFilter {
+    pub auction_signatures: Vec<String>,
+    pub seller: Option<String>,
+    pub category: Option<String>,
+}
+
+impl EventFilter {
+    pub fn matches(&self, event: &AuctionEvent) -> bool {
+        (self.auction_signatures.is_empty()
+            || self.auction_signatures.contains(&event.auction_signature))
+            && self
+                .seller
+                .as_ref()
+                .is_none_or(|seller| &event.seller == seller)
+            && self
+                .category
+                .as_ref()
+                .is_none_or(|category| event.category.eq_ignore_ascii_case(category))
+    }
+}
+
+pub fn event_channel() -> EventSender {
+    broadcast::channel(EVENT_BUFFER).0
+}
+
+// Events between two versions of an auction record. Every node derives them the same way,
+// whether it applied a transaction, merged a replica or settled the auction itself.
+pub fn auction_changes(before: Option<&Auction>, after: &Auction) -> Vec<AuctionEvent> {
+    let event = |kind| AuctionEvent {
+        auction_signature: after.signature.clone(),
+        seller: after.user_id.clone(),
+        category: after.metadata.category.clone(),
+        kind,
+    };
+    let mut events: Vec<AuctionEvent> = after
+        .bids
+        .iter()
+        .filter(|bid| {
+            before.is_none_or(|before| {
+                !before
+                    .bids
+                    .iter()
+                    .any(|known| known.signature == bid.signature)
+            })
+        })
+        .map(|bid| event(EventKind::NewBid(bid.clone())))
+        .collect();
+
+    // a new record has nothing to be extended or closed from
+    let Some(before) = before else {
+        return events;
+    };
+    if after.closing_time() > before.closing_time() {
+        events.push(event(EventKind::Extended {
+            closing_time: after.closing_time(),
+        }));
+    }
+    // a cancelled auction is closed once, when the cancellation arrives
+    if !before.cancelled && (after.cancelled || (before.active && !after.active)) {
+        events.push(event(EventKind::Closed {
+            cancelled: after.cancelled,
+        }));
+    }
+    if before.active && !after.active && !after.cancelled {
+        events.push(event(EventKind::Settled {
+            awards: settle(after).awards,
+        }));
+    }
+    events
+}
+
+// send the changes to whoever is subscribed, nobody listening is not an error
+pub fn publish_changes(events: &EventSender, before: Option<&Auction>, after: &Auction) {
+    for event in auction_changes(before, after) {
+        let _ = events.send(event);
+    }
+}
@@ -1,19 +1,300 @@
-use crate::auction_app::auction::{AuctionHouse, Bid, Transaction};
-use crate::auction_app::auction_operation::client::send_transaction;
-use crate::auction_app::notifications::notify_client::send_notification;
-use chrono::Utc;
-use elliptic_curve::generic_array::GenericArray;
+use crate::auction_app::auction::{
+    Auction, AuctionCancel, AuctionHouse, AuctionKind, Bid, BidCommitment, BidIncrement, BidReveal,
+    DutchAccept, Endpoint, Subscription, Transaction,
+};
+use crate::auction_app::auction_operation::client::{
+    relay_transaction, run_client, TransactionInfo,
+};
+use crate::auction_app::user::User;
+use crate::auction_server::replication::listing;
+use crate::auction_server::user_ledger::{available_credits, USER_RECORDS_NODE};
+use crate::auction_tx::{
+    GetUsersRequest, HoldDepositRequest, RejectionReason, ReleaseDepositRequest,
+    SubmitTransactionResponse, TransactionStatus,
+};
+use chrono::{DateTime, SubsecRound, Utc};
 use k256::ecdsa::Signature;
 use k256::ecdsa::{signature::Verifier, VerifyingKey};
-use sha256::digest;
+use std::fmt;
 use std::sync::Arc;
 use tokio::sync::Mutex;
+use tonic::Code;
+// Maximum distance in seconds between a transaction timestamp and the validator clock
+pub const MAX_CLOCK_SKEW: i64 = 30;
+
+// Reasons a transaction is refused, reported back to the client that submitted it
+#[derive(Debug, Clone, PartialEq)]
+pub enum Rejection {
+    InvalidSignature,
+    AuctionClosed,
+    NotYetOpen { start_time: DateTime<Utc> },
+    SellerBid,
+    BelowStartingBid { starting_bid: f32 },
+    BelowMinimumIncrement { minimum: f32 },
+    UnsupportedTransaction,
+    AlreadyCommitted,
+    NotInRevealPhase,
+    CommitmentMismatch,
+    AlreadyRevealed,
+    AlreadyAccepted,
+    StaleTimestamp,
+    NotSeller,
+    AuctionHasBids,
+    AuctionNotFound,
+    HopLimitReached,
+    DepositNotHeld { deposit: f32 },
+    AlreadyListed,
+    InvalidAmount,
+    InsufficientCredits { credits: f32 },
+    Peer(String),
+}
+
+// How a transaction that was not rejected got applied
+#[derive(Debug, Clone, PartialEq)]
+pub enum Outcome {
+    Accepted,
+    Forwarded { node: String },
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Outcome::Accepted => write!(f, "accepted by the node you are connected to"),
+            Outcome::Forwarded { node } => write!(f, "forwarded to and accepted by node {}", node),
+        }
+    }
+}
+
+// What `transaction_handler` did with a transaction. One applied here comes with the
+// auction as it was before and after, both taken under the lock of the change, so the
+// events of concurrent transactions are never duplicated or missed.
+#[derive(Debug, Clone)]
+pub enum Applied {
+    Here {
+        before: Option<Box<Auction>>,
+        after: Box<Auction>,
+    },
+    Forwarded {
+        node: String,
+    },
+}
+
+impl Applied {
+    pub fn outcome(&self) -> Outcome {
+        match self {
+            Applied::Here { .. } => Outcome::Accepted,
+            Applied::Forwarded { node } => Outcome::Forwarded { node: node.clone() },
+        }
+    }
+}
+
+// What a node needs to forward a transaction for an auction it does not hold: the hops
+// left, the nodes the transaction already went through and its peers closest to the
+// auction, nearest first
+#[derive(Debug, Clone)]
+pub struct Route {
+    pub ttl: u8,
+    pub visited: Vec<String>,
+    pub own_addr: String,
+    pub closest: Vec<String>,
+}
+
+impl Route {
+    // peers this node tries, nearest first, the visited ones and itself are never tried
+    pub fn candidates(&self) -> Vec<String> {
+        self.closest
+            .iter()
+            .filter(|peer| !self.visited.contains(peer) && **peer != self.own_addr)
+            .cloned()
+            .collect()
+    }
+
+    // the next hops skip every node this one went through or is about to try itself
+    pub fn next_visited(&self, candidates: &[String]) -> Vec<String> {
+        let mut visited = self.visited.clone();
+        visited.push(self.own_addr.clone());
+        visited.extend(candidates.iter().cloned());
+        visited
+    }
+}
+
+impl Rejection {
+    // routing failures let the previous hop try another node
+    pub fn is_routing_failure(&self) -> bool {
+        matches!(
+            self,
+            Rejection::AuctionNotFound | Rejection::HopLimitReached
+        )
+    }
+
+    pub fn reason(&self) -> RejectionReason {
+        match self {
+            Rejection::InvalidSignature => RejectionReason::InvalidSignature,
+            Rejection::AuctionClosed => RejectionReason::AuctionClosed,
+            Rejection::NotYetOpen { .. } => RejectionReason::NotYetOpen,
+            Rejection::SellerBid => RejectionReason::SellerBid,
+            Rejection::BelowStartingBid { .. } => RejectionReason::BelowStartingBid,
+            Rejection::BelowMinimumIncrement { .. } => RejectionReason::BelowMinimumIncrement,
+            Rejection::UnsupportedTransaction => RejectionReason::UnsupportedTransaction,
+            Rejection::AlreadyCommitted => RejectionReason::AlreadyCommitted,
+            Rejection::NotInRevealPhase => RejectionReason::NotInRevealPhase,
+            Rejection::CommitmentMismatch => RejectionReason::CommitmentMismatch,
+            Rejection::AlreadyRevealed => RejectionReason::AlreadyRevealed,
+            Rejection::AlreadyAccepted => RejectionReason::AlreadyAccepted,
+            Rejection::StaleTimestamp => RejectionReason::StaleTimestamp,
+            Rejection::NotSeller => RejectionReason::NotSeller,
+            Rejection::AuctionHasBids => RejectionReason::AuctionHasBids,
+            Rejection::AuctionNotFound => RejectionReason::AuctionNotFound,
+            Rejection::HopLimitReached => RejectionReason::HopLimitReached,
+            Rejection::DepositNotHeld { .. } => RejectionReason::DepositNotHeld,
+            Rejection::AlreadyListed => RejectionReason::AlreadyListed,
+            Rejection::InvalidAmount => RejectionReason::InvalidAmount,
+            Rejection::InsufficientCredits { .. } => RejectionReason::InsufficientCredits,
+            Rejection::Peer(_) => RejectionReason::Unspecified,
+        }
+    }
+}
+
+impl std::error::Error for Rejection {}
+
+// gRPC form of the result of `transaction_handler`
+pub fn to_response(result: &Result<Outcome, Rejection>) -> SubmitTransactionResponse {
+    let mut response = SubmitTransactionResponse::default();
+    match result {
+        Ok(Outcome::Accepted) => response.set_status(TransactionStatus::Accepted),
+        Ok(Outcome::Forwarded { node }) => {
+            response.set_status(TransactionStatus::Forwarded);
+            response.node = node.clone();
+        }
+        Err(rejection) => {
+            response.set_status(TransactionStatus::Rejected);
+            response.set_reason(rejection.reason());
+            response.detail = rejection.to_string();
+            match rejection {
+                Rejection::NotYetOpen { start_time } => {
+                    response.start_time = start_time.timestamp()
+                }
+                Rejection::BelowStartingBid {
+                    starting_bid: amount,
+                }
+                | Rejection::BelowMinimumIncrement { minimum: amount }
+                | Rejection::DepositNotHeld { deposit: amount }
+                | Rejection::InsufficientCredits { credits: amount } => response.amount = *amount,
+                _ => {}
+            }
+        }
+    }
+    response
+}
+
+// reasons unknown to this version come back as `Rejection::Peer` with the peer's description,
+// a response without a status is never taken for an accepted transaction
+pub fn from_response(response: &SubmitTransactionResponse) -> Result<Outcome, Rejection> {
+    match response.status() {
+        TransactionStatus::Unspecified => Err(Rejection::Peer(
+            "node answered without a transaction status".to_string(),
+        )),
+        TransactionStatus::Accepted => Ok(Outcome::Accepted),
+        TransactionStatus::Forwarded => Ok(Outcome::Forwarded {
+            node: response.node.clone(),
+        }),
+        TransactionStatus::Rejected => Err(match response.reason() {
+            RejectionReason::InvalidSignature => Rejection::InvalidSignature,
+            RejectionReason::AuctionClosed => Rejection::AuctionClosed,
+            RejectionReason::NotYetOpen => Rejection::NotYetOpen {
+                start_time: DateTime::from_timestamp(response.start_time, 0).unwrap_or_default(),
+            },
+            RejectionReason::SellerBid => Rejection::SellerBid,
+            RejectionReason::BelowStartingBid => Rejection::BelowStartingBid {
+                starting_bid: response.amount,
+            },
+            RejectionReason::BelowMinimumIncrement => Rejection::BelowMinimumIncrement {
+                minimum: response.amount,
+            },
+            RejectionReason::UnsupportedTransaction => Rejection::UnsupportedTransaction,
+            RejectionReason::AlreadyCommitted => Rejection::AlreadyCommitted,
+            RejectionReason::NotInRevealPhase => Rejection::NotInRevealPhase,
+            RejectionReason::CommitmentMismatch => Rejection::CommitmentMismatch,
+            RejectionReason::AlreadyRevealed => Rejection::AlreadyRevealed,
+            RejectionReason::AlreadyAccepted => Rejection::AlreadyAccepted,
+            RejectionReason::StaleTimestamp => Rejection::StaleTimestamp,
+            RejectionReason::NotSeller => Rejection::NotSeller,
+            RejectionReason::AuctionHasBids => Rejection::AuctionHasBids,
+            RejectionReason::AuctionNotFound => Rejection::AuctionNotFound,
+            RejectionReason::HopLimitReached => Rejection::HopLimitReached,
+            RejectionReason::DepositNotHeld => Rejection::DepositNotHeld {
+                deposit: response.amount,
+            },
+            RejectionReason::AlreadyListed => Rejection::AlreadyListed,
+            RejectionReason::InvalidAmount => Rejection::InvalidAmount,
+            RejectionReason::InsufficientCredits => Rejection::InsufficientCredits {
+                credits: response.amount,
+            },
+            RejectionReason::Unspecified => Rejection::Peer(response.detail.clone()),
+        }),
+    }
+}
+
+impl fmt::Display for Rejection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Rejection::InvalidSignature => write!(f, "transaction signature is not valid"),
+            Rejection::AuctionClosed => write!(f, "auction is closed"),
+            Rejection::NotYetOpen { start_time } => {
+                write!(
+                    f,
+                    "bidding opens at {}, you will be notified when it does",
+                    start_time
+                )
+            }
+            Rejection::SellerBid => write!(f, "sellers cannot bid on their own auction"),
+            Rejection::BelowStartingBid { starting_bid } => {
+                write!(f, "bid is below the starting bid of {}", starting_bid)
+            }
+            Rejection::BelowMinimumIncrement { minimum } => {
+                write!(f, "bid must be at least {}", minimum)
+            }
+            Rejection::UnsupportedTransaction => {
+                write!(f, "transaction is not supported by this auction type")
+            }
+            Rejection::AlreadyCommitted => write!(f, "a sealed bid was already committed"),
+            Rejection::NotInRevealPhase => write!(f, "auction is not in its reveal phase"),
+            Rejection::CommitmentMismatch => {
+                write!(f, "reveal does not match any sealed bid commitment")
+            }
+            Rejection::AlreadyRevealed => write!(f, "sealed bid was already revealed"),
+            Rejection::AlreadyAccepted => write!(f, "price was already accepted for a unit"),
+            Rejection::StaleTimestamp => {
+                write!(
+                    f,
+                    "transaction timestamp is too far from the validator clock"
+                )
+            }
+            Rejection::NotSeller => write!(f, "only the seller can cancel the auction"),
+            Rejection::AuctionHasBids => write!(f, "auction already received bids"),
+            Rejection::AuctionNotFound => write!(f, "no reachable node holds the auction"),
+            Rejection::HopLimitReached => {
+                write!(f, "transaction reached its hop limit before the auction")
+            }
+            Rejection::DepositNotHeld { deposit } => {
+                write!(f, "credits do not cover the deposit of {}", deposit)
+            }
+            Rejection::AlreadyListed => write!(f, "auction is already listed"),
+            Rejection::InvalidAmount => write!(f, "amount is not finite or out of range"),
+            Rejection::InsufficientCredits { credits } => {
+                write!(f, "credits of {} do not cover the amount", credits)
+            }
+            Rejection::Peer(reason) => write!(f, "{}", reason),
+        }
+    }
+}
+
 pub async fn transaction_handler(
     transaction: Transaction,
     shared_auction_house: &mut Arc<Mutex<AuctionHouse>>,
     requester_addr: String,
-    routing_table: Vec<String>,
-) {
+    route: Route,
+) -> Result<Applied, Rejection> {
     match transaction {
         Transaction::Bid(ref value) => {
             println!("\n{:?}", value);
@@ -22,26 +303,99 @@ pub async fn transaction_handler(
                 value,
                 transaction.clone(),
                 requester_addr,
-                routing_table.clone(),
+                route.clone(),
+            )
+            .await
+        }
+        Transaction::Commit(ref value) => {
+            println!("\n{:?}", value);
+            commit_sealed_bid(
+                &mut shared_auction_house.clone(),
+                value,
+                transaction.clone(),
+                requester_addr,
+                route.clone(),
             )
-            .await;
+            .await
+        }
+        Transaction::Reveal(ref value) => {
+            println!("\n{:?}", value);
+            reveal_sealed_bid(
+                &mut shared_auction_house.clone(),
+                value,
+                transaction.clone(),
+                requester_addr,
+                route.clone(),
+            )
+            .await
+        }
+        Transaction::Accept(ref value) => {
+            println!("\n{:?}", value);
+            accept_dutch_price(
+                &mut shared_auction_house.clone(),
+                value,
+                transaction.clone(),
+                requester_addr,
+                route.clone(),
+            )
+            .await
+        }
+        Transaction::Cancel(ref value) => {
+            println!("\n{:?}", value);
+            cancel_auction(
+                &mut shared_auction_house.clone(),
+                value,
+                transaction.clone(),
+                requester_addr,
+                route.clone(),
+            )
+            .await
+        }
+        Transaction::Subscription(ref value) => {
+            println!("\n{:?}", value);
+            update_subscription(
+                &mut shared_auction_house.clone(),
+                value,
+                transaction.clone(),
+                requester_addr,
+                route.clone(),
+            )
+            .await
         }
         Transaction::Auction(value) => {
             println!("\n{:?}", value);
             let mut auction_house = shared_auction_house.lock().await;
-            let signed_content = digest(
-                value.item_name.clone()
-                    + &value.starting_bid.to_string()
-                    + &value.user_id.to_string(),
-            );
-            match validate_tx_integrity(&signed_content, &value.user_id, value.signature.clone())
-                .await
+            // a signed auction is public, replaying it never lists it twice
+            if auction_house
+                .auctions
+                .iter()
+                .any(|auction| auction.signature == value.signature)
+            {
+                return Err(Rejection::AlreadyListed);
+            }
+            check_terms(&value)?;
+            match validate_tx_integrity(
+                &value.signed_content(),
+                &value.user_id,
+                value.signature.clone(),
+            )
+            .await
             {
                 Ok(true) => {
-                    auction_house.add_auction(value);
+                    // only the signed fields come from the seller, bids, commitments and the
+                    // state start afresh as they do for a record from a peer
+                    let auction = listing(&value);
+                    auction_house.add_auction(auction.clone());
+                    Ok(Applied::Here {
+                        before: None,
+                        after: Box::new(auction),
+                    })
+                }
+                Ok(false) => Err(Rejection::InvalidSignature),
+                Err(e) => {
+                    println!("{:?}", e);
+                    Err(Rejection::InvalidSignature)
                 }
-                Ok(false) => println!("signature is not valid"),
-                Err(e) => println!("{:?}", e),
             }
         }
     }
@@ -51,24 +405,98 @@ pub async fn validate_tx_integrity(
     uid: &String,
     sig_string: String,
 ) -> Result<bool, Box<dyn std::error::Error>> {
-    // get puiblic key from uid hex value
-    let public_key = VerifyingKey::from_sec1_bytes(&hex::decode(uid).unwrap())?;
+    // get puiblic key from uid hex value, records read from the DHT may carry malformed values
+    let public_key = VerifyingKey::from_sec1_bytes(&hex::decode(uid)?)?;
 
-    let sig: Signature = Signature::from_bytes(&GenericArray::clone_from_slice(
-        &hex::decode(sig_string).unwrap(),
-    ))?;
+    let sig: Signature = Signature::from_slice(&hex::decode(sig_string)?)?;
 
     // validate signature with the concat of parameters
     Ok(public_key.verify(signed_content.as_bytes(), &sig).is_ok())
 }
 
+// The node accepting a bid stamps it with its own clock, whatever the client sent, so
+// nobody can backdate a bid or move it out of the soft close window. Replicas keep that
+// stamp through replication and derive the same extension from it. The stamp is cut to
+// whole seconds, the precision Bid.timestamp has on the wire, so every replica ranks
+// equal bids the same way.
+pub fn stamp_bid(bid: &Bid, now: DateTime<Utc>) -> Bid {
+    Bid {
+        timestamp: now.trunc_subsecs(0),
+        ..bid.clone()
+    }
+}
+
+// Bids pay a finite amount above zero. NaN is below no price, the rules alone would let it
+// through and every bid after it too.
+pub fn check_amount(amount: f32) -> Result<(), Rejection> {
+    if !amount.is_finite() || amount <= 0.0 {
+        return Err(Rejection::InvalidAmount);
+    }
+    Ok(())
+}
+
+// every price the seller sets is a finite amount, none of them below zero
+pub fn check_terms(auction: &Auction) -> Result<(), Rejection> {
+    let (BidIncrement::Absolute(increment) | BidIncrement::Percent(increment)) =
+        auction.rules.min_increment;
+    let mut amounts = vec![auction.starting_bid, auction.rules.reserve_price, increment];
+    amounts.extend(auction.rules.buy_now_price);
+    match auction.kind {
+        AuctionKind::English => {}
+        AuctionKind::SealedBid { deposit, .. } => amounts.push(deposit),
+        AuctionKind::Dutch {
+            start_price,
+            decrement,
+            floor_price,
+            ..
+        } => amounts.extend([start_price, decrement, floor_price]),
+    }
+    if amounts
+        .iter()
+        .all(|amount| amount.is_finite() && *amount >= 0.0)
+    {
+        Ok(())
+    } else {
+        Err(Rejection::InvalidAmount)
+    }
+}
+
+// check the bid against the auction rules chosen by the seller
+pub fn check_bid(auction: &Auction, bid: &Bid, now: DateTime<Utc>) -> Result<(), Rejection> {
+    if auction.kind != AuctionKind::English {
+        return Err(Rejection::UnsupportedTransaction);
+    }
+    if !auction.active || auction.cancelled || auction.closing_time() <= now {
+        return Err(Rejection::AuctionClosed);
+    }
+    if now < auction.start_time {
+        return Err(Rejection::NotYetOpen {
+            start_time: auction.start_time,
+        });
+    }
+    if bid.bidder == auction.user_id {
+        return Err(Rejection::SellerBid);
+    }
+    match auction.lowest_winning_bid() {
+        None if bid.amount < auction.starting_bid => Err(Rejection::BelowStartingBid {
+            starting_bid: auction.starting_bid,
+        }),
+        Some(lowest) if bid.amount <= lowest.amount || bid.amount < auction.minimum_next_bid() => {
+            Err(Rejection::BelowMinimumIncrement {
+                minimum: auction.minimum_next_bid(),
+            })
+        }
+        _ => check_amount(bid.amount),
+    }
+}
+
 pub async fn find_auction_to_bid(
     shared_auction_house: &mut Arc<Mutex<AuctionHouse>>,
     bid: &Bid,
     transaction: Transaction,
     requester_addr: String,
-    routing_table: Vec<String>,
-) {
+    route: Route,
+) -> Result<Applied, Rejection> {
     let mut auction_house = shared_auction_house.lock().await;
 
     if let Some(auction) = auction_house
@@ -77,79 +505,611 @@ pub async fn find_auction_to_bid(
         .iter()
         .find(|auction| auction.signature == bid.auction_signature)
     {
-        let signed_content =
-            digest(bid.auction_signature.clone() + &bid.bidder + &bid.amount.to_string());
+        let now = Utc::now();
+        if let Err(rejection) = check_bid(auction, bid, now) {
+            // early bidders are subscribed so they hear when bidding opens
+            if let Rejection::NotYetOpen { .. } = rejection {
+                if let Ok(true) =
+                    validate_tx_integrity(&bid.signed_content(), &bid.bidder, bid.signature.clone())
+                        .await
+                {
+                    subscribe(&mut auction_house, &auction.signature, &bid.bidder);
+                }
+            }
+            return Err(rejection);
+        }
+        let bid = &stamp_bid(bid, now);
 
-        let mut last_highest_bid = 0.0;
-        if !auction.clone().bids.is_empty() {
-            last_highest_bid = auction.clone().bids[auction.clone().bids.len() - 1].amount;
+        match validate_tx_integrity(&bid.signed_content(), &bid.bidder, bid.signature.clone()).await
+        {
+            Ok(true) => {}
+            Ok(false) => return Err(Rejection::InvalidSignature),
+            Err(e) => {
+                println!("{:?}", e);
+                return Err(Rejection::InvalidSignature);
+            }
         }
+        check_credits(&bid.bidder, bid.amount, &auction.signature).await?;
+        let target_auction_position = auction_house
+            .auctions
+            .iter()
+            .position(|i| i.signature == auction.signature)
+            .unwrap();
 
-        if &auction.clone().end_time > &Utc::now() && last_highest_bid < bid.amount {
-            match validate_tx_integrity(&signed_content, &bid.bidder, bid.signature.clone()).await {
-                Ok(true) => {
-                    let target_auction_position = auction_house
-                        .auctions
-                        .iter()
-                        .position(|i| i.signature == auction.signature)
-                        .unwrap();
-
-                    auction_house.auctions[target_auction_position]
-                        .bids
-                        .push(bid.clone());
-
-                    //Bid is valid send notification to client
-                    if !auction_house.auctions[target_auction_position]
-                        .subscribers
-                        .contains(&requester_addr)
-                    {
-                        println!(
-                            "New subscriber {} to auction: {}\n",
-                            requester_addr.clone(),
-                            auction_house.auctions[target_auction_position].signature
-                        );
-                        auction_house.auctions[target_auction_position]
-                            .subscribers
-                            .push(requester_addr.clone());
-                    }
-                    for peer in auction_house.clone().auctions[target_auction_position]
-                        .subscribers
-                        .clone()
-                    {
-                        if peer != requester_addr.clone() {
-                            println!("sending notification to {}", peer.clone());
-                            tokio::task::spawn(send_notification(peer.clone(), bid.clone()));
-                        }
-                    }
-                }
+        let closing_time = auction.closing_time();
+        auction_house.auctions[target_auction_position]
+            .bids
+            .push(bid.clone());
+        if auction_house.auctions[target_auction_position].closing_time() != closing_time {
+            println!(
+                "Late bid, auction {} extended to {}\n",
+                auction.signature,
+                auction_house.auctions[target_auction_position].closing_time()
+            );
+        }
 
-                Ok(false) => println!("Error integrity signature is not valid"),
-                Err(e) => println!("{:?}", e),
-            }
+        // the bidder follows the auction from now on, the server queues the
+        // notification for the other subscribers
+        if !auction_house.auctions[target_auction_position]
+            .subscribers
+            .contains(&bid.bidder)
+        {
+            println!(
+                "New subscriber {} to auction: {}\n",
+                bid.bidder, auction_house.auctions[target_auction_position].signature
+            );
+            auction_house.auctions[target_auction_position]
+                .subscribers
+                .push(bid.bidder.clone());
         }
+        Ok(Applied::Here {
+            before: Some(Box::new(auction.clone())),
+            after: Box::new(auction_house.auctions[target_auction_position].clone()),
+        })
     } else {
-        println!("Auction not present, sending to peers\n");
-        //
-        let dest_ip = routing_table
-            .get(0)
-            .unwrap()
-            .clone()
-            .to_string()
-            .split(":")
-            .next()
-            .unwrap()
-            .to_owned();
-
-        match send_transaction(transaction, &dest_ip, requester_addr).await {
-            Ok(result) => {
-                println!(
-                    "Dispaching Transaction to peer:{:?} {:?} ",
-                    &dest_ip, result
-                );
+        // peers may forward back to this node, the lock is not held across the hops
+        drop(auction_house);
+        forward_transaction(transaction, requester_addr, route).await
+    }
+}
+
+// commitments are only accepted while bidding is open, one per bidder
+pub fn check_commitment(
+    auction: &Auction,
+    commitment: &BidCommitment,
+    now: DateTime<Utc>,
+) -> Result<(), Rejection> {
+    if !matches!(auction.kind, AuctionKind::SealedBid { .. }) {
+        return Err(Rejection::UnsupportedTransaction);
+    }
+    if !auction.active || auction.cancelled || auction.end_time <= now {
+        return Err(Rejection::AuctionClosed);
+    }
+    if now < auction.start_time {
+        return Err(Rejection::NotYetOpen {
+            start_time: auction.start_time,
+        });
+    }
+    if commitment.bidder == auction.user_id {
+        return Err(Rejection::SellerBid);
+    }
+    if auction
+        .commitments
+        .iter()
+        .any(|committed| committed.bidder == commitment.bidder)
+    {
+        return Err(Rejection::AlreadyCommitted);
+    }
+    Ok(())
+}
+
+// reveals are only accepted between `end_time` and the settlement time
+pub fn check_reveal(
+    auction: &Auction,
+    reveal: &BidReveal,
+    now: DateTime<Utc>,
+) -> Result<(), Rejection> {
+    if !matches!(auction.kind, AuctionKind::SealedBid { .. }) {
+        return Err(Rejection::UnsupportedTransaction);
+    }
+    if !auction.active || now < auction.end_time || auction.settlement_time() <= now {
+        return Err(Rejection::NotInRevealPhase);
+    }
+    if auction
+        .bids
+        .iter()
+        .any(|bid| bid.bidder == reveal.bid.bidder)
+    {
+        return Err(Rejection::AlreadyRevealed);
+    }
+    if !auction.commitments.iter().any(|committed| {
+        committed.bidder == reveal.bid.bidder && committed.commitment == reveal.commitment()
+    }) {
+        return Err(Rejection::CommitmentMismatch);
+    }
+    if reveal.bid.amount < auction.starting_bid {
+        return Err(Rejection::BelowStartingBid {
+            starting_bid: auction.starting_bid,
+        });
+    }
+    check_amount(reveal.bid.amount)
+}
+
+// A bid, reveal or accept is only taken when the bidder's credits cover it. The node keeping
+// the user records reads them without the auction lock, so it is asked while the lock is held.
+async fn check_credits(
+    bidder: &str,
+    amount: f32,
+    auction_signature: &str,
+) -> Result<(), Rejection> {
+    let mut client = run_client(USER_RECORDS_NODE)
+        .await
+        .map_err(|e| Rejection::Peer(format!("credits could not be checked: {}", e)))?;
+    let request = tonic::Request::new(GetUsersRequest {
+        id: bidder.to_string(),
+    });
+    // a bidder without a record has no credits
+    let credits = match client.get_users(request).await {
+        Ok(response) => response
+            .into_inner()
+            .account
+            .map(|account| available_credits(&User::from(account), auction_signature))
+            .unwrap_or(0.0),
+        Err(status) if status.code() == Code::NotFound => 0.0,
+        Err(status) => {
+            return Err(Rejection::Peer(format!(
+                "credits could not be checked: {}",
+                status.message()
+            )))
+        }
+    };
+    if credits < amount {
+        return Err(Rejection::InsufficientCredits { credits });
+    }
+    Ok(())
+}
+
+// The deposit of a sealed bid is held on the bidder's credits by the node keeping the user
+// records, the bidder must be able to lose it before committing
+async fn escrow_deposit(commitment: &BidCommitment, auction: &Auction) -> Result<(), Rejection> {
+    let AuctionKind::SealedBid { deposit, .. } = auction.kind else {
+        return Ok(());
+    };
+    if deposit <= 0.0 {
+        return Ok(());
+    }
+    let mut client = run_client(USER_RECORDS_NODE)
+        .await
+        .map_err(|e| Rejection::Peer(format!("deposit could not be held: {}", e)))?;
+    let request = tonic::Request::new(HoldDepositRequest {
+        commitment: Some(commitment.clone().into()),
+        auction: Some(auction.clone().into()),
+    });
+    match client.hold_deposit(request).await {
+        Ok(_) => Ok(()),
+        Err(status) => Err(match status.code() {
+            Code::FailedPrecondition => Rejection::DepositNotHeld { deposit },
+            // held for another commitment of the bidder
+            Code::AlreadyExists => Rejection::AlreadyCommitted,
+            _ => Rejection::Peer(format!("deposit could not be held: {}", status.message())),
+        }),
+    }
+}
+
+// A deposit held for a commitment that was not taken after all goes back to the bidder
+async fn return_deposit(bidder: String, auction: Auction) {
+    let request = tonic::Request::new(ReleaseDepositRequest {
+        bidder,
+        auction: Some(auction.into()),
+    });
+    let result = match run_client(USER_RECORDS_NODE).await {
+        Ok(mut client) => client
+            .release_deposit(request)
+            .await
+            .map(|_| ())
+            .map_err(|e| e.to_string()),
+        Err(e) => Err(e.to_string()),
+    };
+    if let Err(e) = result {
+        println!("Failed to return a deposit: {}", e);
+    }
+}
+
+pub async fn commit_sealed_bid(
+    shared_auction_house: &mut Arc<Mutex<AuctionHouse>>,
+    commitment: &BidCommitment,
+    transaction: Transaction,
+    requester_addr: String,
+    route: Route,
+) -> Result<Applied, Rejection> {
+    let auction_house = shared_auction_house.lock().await;
+
+    let Some(auction) = auction_house
+        .auctions
+        .iter()
+        .find(|auction| auction.signature == commitment.auction_signature)
+    else {
+        // peers may forward back to this node, the lock is not held across the hops
+        drop(auction_house);
+        return forward_transaction(transaction, requester_addr, route).await;
+    };
+    // the commitment is taken as of its arrival, however long holding the deposit takes
+    let now = Utc::now();
+    check_commitment(auction, commitment, now)?;
+
+    match validate_tx_integrity(
+        &commitment.signed_content(),
+        &commitment.bidder,
+        commitment.signature.clone(),
+    )
+    .await
+    {
+        Ok(true) => {}
+        Ok(false) => return Err(Rejection::InvalidSignature),
+        Err(e) => {
+            println!("{:?}", e);
+            return Err(Rejection::InvalidSignature);
+        }
+    }
+
+    // the node keeping the user records may be this one and takes the lock too
+    let record = auction.clone();
+    drop(auction_house);
+    escrow_deposit(commitment, &record).await?;
+
+    let mut auction_house = shared_auction_house.lock().await;
+    let taken = match auction_house
+        .auctions
+        .iter_mut()
+        .find(|auction| auction.signature == commitment.auction_signature)
+    {
+        Some(auction) => check_commitment(auction, commitment, now).map(|_| auction),
+        None => Err(Rejection::AuctionNotFound),
+    };
+    match taken {
+        Ok(auction) => {
+            let before = auction.clone();
+            // amounts stay hidden, so subscribers are not notified of commitments
+            auction.commitments.push(commitment.clone());
+            if !auction.subscribers.contains(&commitment.bidder) {
+                auction.subscribers.push(commitment.bidder.clone());
             }
-            Err(e) => {
-                println!("error {}", e);
+            Ok(Applied::Here {
+                before: Some(Box::new(before)),
+                after: Box::new(auction.clone()),
+            })
+        }
+        Err(rejection) => {
+            tokio::spawn(return_deposit(commitment.bidder.clone(), record));
+            Err(rejection)
+        }
+    }
+}
+
+pub async fn reveal_sealed_bid(
+    shared_auction_house: &mut Arc<Mutex<AuctionHouse>>,
+    reveal: &BidReveal,
+    transaction: Transaction,
+    requester_addr: String,
+    route: Route,
+) -> Result<Applied, Rejection> {
+    let mut auction_house = shared_auction_house.lock().await;
+
+    let Some(auction) = auction_house
+        .auctions
+        .iter_mut()
+        .find(|auction| auction.signature == reveal.bid.auction_signature)
+    else {
+        // peers may forward back to this node, the lock is not held across the hops
+        drop(auction_house);
+        return forward_transaction(transaction, requester_addr, route).await;
+    };
+    let now = Utc::now();
+    check_reveal(auction, reveal, now)?;
+    let before = auction.clone();
+
+    match validate_tx_integrity(
+        &reveal.bid.signed_content(),
+        &reveal.bid.bidder,
+        reveal.bid.signature.clone(),
+    )
+    .await
+    {
+        Ok(true) => {}
+        Ok(false) => return Err(Rejection::InvalidSignature),
+        Err(e) => {
+            println!("{:?}", e);
+            return Err(Rejection::InvalidSignature);
+        }
+    }
+    check_credits(&reveal.bid.bidder, reveal.bid.amount, &auction.signature).await?;
+    // the salt stays with the bid so every replica can open the commitment again
+    auction.bids.push(Bid {
+        salt: reveal.salt.clone(),
+        ..stamp_bid(&reveal.bid, now)
+    });
+    Ok(Applied::Here {
+        before: Some(Box::new(before)),
+        after: Box::new(auction.clone()),
+    })
+}
+
+// one accept per unit, taken while the auction runs
+pub fn check_accept(
+    auction: &Auction,
+    accept: &DutchAccept,
+    now: DateTime<Utc>,
+) -> Result<(), Rejection> {
+    if !matches!(auction.kind, AuctionKind::Dutch { .. }) {
+        return Err(Rejection::UnsupportedTransaction);
+    }
+    // the price is derived from the timestamp, one ahead of the validator clock would take
+    // a lower price step early
+    if accept.timestamp > now || (now - accept.timestamp).num_seconds() > MAX_CLOCK_SKEW {
+        return Err(Rejection::StaleTimestamp);
+    }
+    if !auction.active
+        || auction.cancelled
+        || auction.bids.len() >= auction.quantity as usize
+        || auction.end_time <= accept.timestamp
+    {
+        return Err(Rejection::AuctionClosed);
+    }
+    if accept.timestamp < auction.start_time {
+        return Err(Rejection::NotYetOpen {
+            start_time: auction.start_time,
+        });
+    }
+    if accept.bidder == auction.user_id {
+        return Err(Rejection::SellerBid);
+    }
+    if auction.bids.iter().any(|bid| bid.bidder == accept.bidder) {
+        return Err(Rejection::AlreadyAccepted);
+    }
+    Ok(())
+}
+
+pub async fn accept_dutch_price(
+    shared_auction_house: &mut Arc<Mutex<AuctionHouse>>,
+    accept: &DutchAccept,
+    transaction: Transaction,
+    requester_addr: String,
+    route: Route,
+) -> Result<Applied, Rejection> {
+    let mut auction_house = shared_auction_house.lock().await;
+
+    let Some(auction) = auction_house
+        .auctions
+        .iter_mut()
+        .find(|auction| auction.signature == accept.auction_signature)
+    else {
+        // peers may forward back to this node, the lock is not held across the hops
+        drop(auction_house);
+        return forward_transaction(transaction, requester_addr, route).await;
+    };
+    check_accept(auction, accept, Utc::now())?;
+    let before = auction.clone();
+
+    match validate_tx_integrity(
+        &accept.signed_content(),
+        &accept.bidder,
+        accept.signature.clone(),
+    )
+    .await
+    {
+        Ok(true) => {}
+        Ok(false) => return Err(Rejection::InvalidSignature),
+        Err(e) => {
+            println!("{:?}", e);
+            return Err(Rejection::InvalidSignature);
+        }
+    }
+    // each accept is recorded as a bid, taking the last unit closes the auction through
+    // `closing_time`
+    let price = auction.dutch_price_at(accept.timestamp).unwrap();
+    check_credits(&accept.bidder, price, &auction.signature).await?;
+    auction.bids.push(Bid {
+        bidder: accept.bidder.clone(),
+        amount: price,
+        signature: accept.signature.clone(),
+        auction_signature: accept.auction_signature.clone(),
+        timestamp: accept.timestamp,
+        salt: String::new(),
+    });
+    if !auction.subscribers.contains(&accept.bidder) {
+        auction.subscribers.push(accept.bidder.clone());
+    }
+    Ok(Applied::Here {
+        before: Some(Box::new(before)),
+        after: Box::new(auction.clone()),
+    })
+}
+
+fn subscribe(auction_house: &mut AuctionHouse, auction_signature: &str, user_id: &str) {
+    if let Some(auction) = auction_house
+        .auctions
+        .iter_mut()
+        .find(|auction| auction.signature == auction_signature)
+    {
+        if !auction
+            .subscribers
+            .iter()
+            .any(|subscriber| subscriber == user_id)
+        {
+            auction.subscribers.push(user_id.to_string());
+        }
+    }
+}
+
+// subscriptions are signed with a recent timestamp so they can not be replayed later,
+// following a closed auction is pointless but unsubscribing from one is allowed
+pub fn check_subscription(
+    auction: &Auction,
+    subscription: &Subscription,
+    now: DateTime<Utc>,
+) -> Result<(), Rejection> {
+    if (subscription.timestamp - now).num_seconds().abs() > MAX_CLOCK_SKEW {
+        return Err(Rejection::StaleTimestamp);
+    }
+    if subscription.subscribe && (!auction.active || auction.cancelled) {
+        return Err(Rejection::AuctionClosed);
+    }
+    Ok(())
+}
+
+pub async fn update_subscription(
+    shared_auction_house: &mut Arc<Mutex<AuctionHouse>>,
+    subscription: &Subscription,
+    transaction: Transaction,
+    requester_addr: String,
+    route: Route,
+) -> Result<Applied, Rejection> {
+    let mut auction_house = shared_auction_house.lock().await;
+
+    let Some(auction) = auction_house
+        .auctions
+        .iter_mut()
+        .find(|auction| auction.signature == subscription.auction_signature)
+    else {
+        // peers may forward back to this node, the lock is not held across the hops
+        drop(auction_house);
+        return forward_transaction(transaction, requester_addr, route).await;
+    };
+    check_subscription(auction, subscription, Utc::now())?;
+    let before = auction.clone();
+
+    match validate_tx_integrity(
+        &subscription.signed_content(),
+        &subscription.subscriber,
+        subscription.signature.clone(),
+    )
+    .await
+    {
+        // a replayed or reordered subscription never overrides a newer one
+        Ok(true) if auction.record_subscription(subscription) => Ok(Applied::Here {
+            before: Some(Box::new(before)),
+            after: Box::new(auction.clone()),
+        }),
+        Ok(true) => Err(Rejection::StaleTimestamp),
+        Ok(false) => Err(Rejection::InvalidSignature),
+        Err(e) => {
+            println!("{:?}", e);
+            Err(Rejection::InvalidSignature)
+        }
+    }
+}
+
+// an endpoint is only taken from its owner and only while the registration is recent
+pub async fn check_endpoint(endpoint: &Endpoint, now: DateTime<Utc>) -> Result<(), Rejection> {
+    if (endpoint.timestamp - now).num_seconds().abs() > MAX_CLOCK_SKEW {
+        return Err(Rejection::StaleTimestamp);
+    }
+    match validate_tx_integrity(
+        &endpoint.signed_content(),
+        &endpoint.user_id,
+        endpoint.signature.clone(),
+    )
+    .await
+    {
+        Ok(true) => Ok(()),
+        _ => Err(Rejection::InvalidSignature),
+    }
+}
+
+// sellers can only withdraw an auction nobody has bid on yet
+pub fn check_cancel(auction: &Auction, cancel: &AuctionCancel) -> Result<(), Rejection> {
+    if cancel.seller != auction.user_id {
+        return Err(Rejection::NotSeller);
+    }
+    if !auction.active || auction.cancelled {
+        return Err(Rejection::AuctionClosed);
+    }
+    if !auction.bids.is_empty() || !auction.commitments.is_empty() {
+        return Err(Rejection::AuctionHasBids);
+    }
+    Ok(())
+}
+
+pub async fn cancel_auction(
+    shared_auction_house: &mut Arc<Mutex<AuctionHouse>>,
+    cancel: &AuctionCancel,
+    transaction: Transaction,
+    requester_addr: String,
+    route: Route,
+) -> Result<Applied, Rejection> {
+    let mut auction_house = shared_auction_house.lock().await;
+
+    let Some(auction) = auction_house
+        .auctions
+        .iter_mut()
+        .find(|auction| auction.signature == cancel.auction_signature)
+    else {
+        // peers may forward back to this node, the lock is not held across the hops
+        drop(auction_house);
+        return forward_transaction(transaction, requester_addr, route).await;
+    };
+    check_cancel(auction, cancel)?;
+    let before = auction.clone();
+
+    match validate_tx_integrity(
+        &cancel.signed_content(),
+        &cancel.seller,
+        cancel.signature.clone(),
+    )
+    .await
+    {
+        Ok(true) => {
+            // the validator records the cancellation in the next block, replicas take it
+            // from the signed request
+            auction.cancelled = true;
+            auction.cancellation = Some(cancel.clone());
+            Ok(Applied::Here {
+                before: Some(Box::new(before)),
+                after: Box::new(auction.clone()),
+            })
+        }
+        Ok(false) => Err(Rejection::InvalidSignature),
+        Err(e) => {
+            println!("{:?}", e);
+            Err(Rejection::InvalidSignature)
+        }
+    }
+}
+
+// the auction is not held by this node, hand the transaction over to the peers closest to
+// it. Visited nodes are never tried again so the transaction can not loop. Only nodes
+// missing the auction or unreachable ones are skipped, a rejection by the node holding the
+// auction or any other error status is final. The node that applied the transaction is
+// reported back.
+async fn forward_transaction(
+    transaction: Transaction,
+    requester_addr: String,
+    route: Route,
+) -> Result<Applied, Rejection> {
+    if route.ttl == 0 {
+        return Err(Rejection::HopLimitReached);
+    }
+    let candidates = route.candidates();
+    let transaction_info = TransactionInfo {
+        subscriber_addrs: requester_addr,
+        transaction,
+        ttl: route.ttl - 1,
+        visited: route.next_visited(&candidates),
+    };
+    for peer in candidates.iter() {
+        println!("Auction not present, forwarding to {}\n", peer);
+        match relay_transaction(&transaction_info, peer).await {
+            Ok(Ok(Outcome::Accepted)) => return Ok(Applied::Forwarded { node: peer.clone() }),
+            Ok(Ok(Outcome::Forwarded { node })) => return Ok(Applied::Forwarded { node }),
+            Ok(Err(rejection)) if rejection.is_routing_failure() => continue,
+            Ok(Err(rejection)) => return Err(rejection),
+            Err(status)
+                if matches!(status.code(), Code::Unavailable | Code::DeadlineExceeded) =>
+            {
+                continue
             }
+            Err(status) => return Err(Rejection::Peer(status.message().to_string())),
         }
     }
+    Err(Rejection::AuctionNotFound)
 }
@@ -0,0 +1,111 @@
+use crate::auction_app::auction::{Auction, Bid, Notification};
+use crate::auction_server::settlement::Settlement;
+
+// Who a notification is for, users are identified by their public key
+#[derive(Debug, Clone, PartialEq)]
+pub enum Recipient {
+    Subscribers,
+    // every subscriber but the given user, who already knows
+    SubscribersExcept(String),
+    User(String),
+}
+
+// notifications for a bid the node just accepted, `before` is the auction without it
+pub fn bid_notifications(
+    before: &Auction,
+    after: &Auction,
+    bid: &Bid,
+) -> Vec<(Recipient, Notification)> {
+    let mut notifications = vec![(
+        Recipient::SubscribersExcept(bid.bidder.clone()),
+        Notification::Bid(bid.clone()),
+    )];
+
+    // bidders that held a unit before the bid and no longer do
+    let winners: Vec<&str> = after
+        .winning_bids()
+        .iter()
+        .map(|winning_bid| winning_bid.bidder.as_str())
+        .collect();
+    for previous in before.winning_bids() {
+        if previous.bidder != bid.bidder && !winners.contains(&previous.bidder.as_str()) {
+            notifications.push((
+                Recipient::User(previous.bidder.clone()),
+                Notification::Outbid {
+                    auction_signature: after.signature.clone(),
+                    item_name: after.item_name.clone(),
+                    bid: bid.clone(),
+                },
+            ));
+        }
+    }
+
+    if after.closing_time() > before.closing_time() {
+        notifications.push((
+            Recipient::Subscribers,
+            Notification::Extended {
+                auction_signature: after.signature.clone(),
+                item_name: after.item_name.clone(),
+                closing_time: after.closing_time(),
+            },
+        ));
+    }
+    notifications
+}
+
+// notifications for a settled auction: every award winner, every bidder left without a unit
+// and the seller
+pub fn settlement_notifications(
+    auction: &Auction,
+    settlement: &Settlement,
+) -> Vec<(Recipient, Notification)> {
+    let auction_signature = auction.signature.clone();
+    let item_name = auction.item_name.clone();
+    let mut notifications = Vec::new();
+
+    for award in settlement.awards.iter() {
+        notifications.push((
+            Recipient::User(award.bid.bidder.clone()),
+            Notification::Won {
+                auction_signature: auction_signature.clone(),
+                item_name: item_name.clone(),
+                price: award.price,
+            },
+        ));
+    }
+    for bid in auction.ranked_bids() {
+        if !settlement
+            .awards
+            .iter()
+            .any(|award| award.bid.bidder == bid.bidder)
+        {
+            notifications.push((
+                Recipient::User(bid.bidder.clone()),
+                Notification::Lost {
+                    auction_signature: auction_signature.clone(),
+                    item_name: item_name.clone(),
+                },
+            ));
+        }
+    }
+
+    if settlement.awards.is_empty() {
+        let ended = Notification::EndedWithoutBids {
+            auction_signature,
+            item_name,
+        };
+        notifications.push((Recipient::Subscribers, ended.clone()));
+        notifications.push((Recipient::User(auction.user_id.clone()), ended));
+    } else {
+        notifications.push((
+            Recipient::User(auction.user_id.clone()),
+            Notification::SellerPayout {
+                auction_signature,
+                item_name,
+                amount: settlement.awards.iter().map(|award| award.price).sum(),
+                units_sold: settlement.awards.len() as u32,
+            },
+        ));
+    }
+    notifications
+}
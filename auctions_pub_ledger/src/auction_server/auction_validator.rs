@@ -1,17 +1,145 @@
-use crate::auction_app::auction::AuctionHouse;
-use crate::auction_app::auction_operation::client::update_user;
+use crate::auction_app::auction::{Auction, AuctionHouse, Bid, Notification};
+use crate::auction_app::auction_operation::client::run_client;
+use crate::auction_client::ClientError;
+use crate::auction_server::auction_directory::publish_auction;
+use crate::auction_server::auction_events::{publish_changes, EventSender};
+use crate::auction_server::auction_notifications::{settlement_notifications, Recipient};
 use crate::auction_server::blockchain::block_generator;
 use crate::auction_server::blockchain::Blockchain;
 use crate::auction_server::blockchain_operator::block_peer_validator_client;
 use crate::auction_server::blockchain_pos::{pos_miner_puzzle, puzzle_builder};
 use crate::auction_server::blockchain_pow::{block_handler, blockchain_handler};
+use crate::auction_server::notification_outbox::{backoff, queue_notifications, SharedOutbox};
+use crate::auction_server::replication::{replica_set, replicate, SETTLEMENT_TAKEOVER};
+use crate::auction_server::settlement::{
+    recorded_settlement, settle, settles, Forfeit, Settlement,
+};
+use crate::auction_server::user_ledger::{SharedLedgerQueue, LEDGER_FILE, USER_RECORDS_NODE};
+use crate::auction_tx::{ChargeDepositRequest, ReleaseDepositRequest, UpdateUsersRequest};
 use crate::kademlia_node_search::node::Node;
 use crate::kademlia_node_search::node_functions::routing_table::Bucket;
-use chrono::Utc;
+use chrono::{DateTime, Duration, Utc};
 use colored::*;
 use std::sync::Arc;
 use std::vec::Vec;
 use tokio::sync::Mutex;
+use tokio::time::sleep;
+use tonic::Code;
+
+// Seconds between two passes of the validator loop
+const VALIDATOR_INTERVAL: u64 = 1;
+
+// Charge of a settlement still to be applied to the user records, with the settled auction
+// as evidence for the node keeping them
+enum Charge {
+    Award(Bid, Auction),
+    Forfeit(Forfeit, Auction),
+    // the held deposit of a sealed bidder
+    Release(String, Auction),
+}
+
+// Send a charge to the node keeping the user records. The node works the price out from
+// the auction, completed by its own copy, the amount of an award is not trusted.
+async fn charge(charge: &Charge) -> Result<(), ClientError> {
+    let mut client = run_client(USER_RECORDS_NODE).await?;
+    match charge {
+        Charge::Award(bid, auction) => {
+            let request = tonic::Request::new(UpdateUsersRequest {
+                bid: Some(bid.clone().into()),
+                auction: Some(auction.clone().into()),
+            });
+            client.update_users(request).await?;
+        }
+        Charge::Forfeit(forfeit, auction) => {
+            let request = tonic::Request::new(ChargeDepositRequest {
+                deposit: Some(forfeit.clone().into()),
+                auction: Some(auction.clone().into()),
+            });
+            client.charge_deposit(request).await?;
+        }
+        Charge::Release(bidder, auction) => {
+            let request = tonic::Request::new(ReleaseDepositRequest {
+                bidder: bidder.clone(),
+                auction: Some(auction.clone().into()),
+            });
+            client.release_deposit(request).await?;
+        }
+    }
+    Ok(())
+}
+
+// Charges waiting to be applied, each with its failed attempts and the time it is tried
+// next. A charge that fails again waits as long as a notification would.
+#[derive(Debug)]
+pub struct ChargeQueue<T> {
+    pending: Vec<(T, u32, DateTime<Utc>)>,
+}
+
+impl<T> Default for ChargeQueue<T> {
+    fn default() -> Self {
+        ChargeQueue {
+            pending: Vec::new(),
+        }
+    }
+}
+
+impl<T> ChargeQueue<T> {
+    // a new charge is tried right away
+    pub fn push(&mut self, charge: T, now: DateTime<Utc>) {
+        self.pending.push((charge, 0, now));
+    }
+
+    // charges whose next attempt is due leave the queue, with the attempts that failed
+    pub fn due(&mut self, now: DateTime<Utc>) -> Vec<(T, u32)> {
+        let (due, waiting) = std::mem::take(&mut self.pending)
+            .into_iter()
+            .partition(|(_, _, next_attempt)| *next_attempt <= now);
+        self.pending = waiting;
+        due.into_iter()
+            .map(|(charge, attempts, _)| (charge, attempts))
+            .collect()
+    }
+
+    // a charge that failed once more, returns when it is tried next
+    pub fn retry(&mut self, charge: T, attempts: u32, now: DateTime<Utc>) -> DateTime<Utc> {
+        let attempts = attempts + 1;
+        let next_attempt = now + backoff(attempts);
+        self.pending.push((charge, attempts, next_attempt));
+        next_attempt
+    }
+
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+}
+
+// Charges that are due are sent, the ones that fail go back to the queue for later. One the
+// node already applied is done.
+async fn apply_charges(charges: &mut ChargeQueue<Charge>) {
+    let now = Utc::now();
+    for (pending, attempts) in charges.due(now) {
+        match charge(&pending).await {
+            Ok(_) => {}
+            Err(ClientError::Status(status)) if status.code() == Code::AlreadyExists => {}
+            Err(e) => {
+                let next_attempt = charges.retry(pending, attempts, now);
+                eprintln!(
+                    "{}",
+                    format!(
+                        "Failed to charge a settlement, retrying at {}: {}",
+                        next_attempt, e
+                    )
+                    .red()
+                );
+            }
+        }
+    }
+}
+
 // Function executed in loop to validate if auctions is closed (10 in 10 seconds)
 // if aution is closed create transaction and prepare to generate block
 pub async fn auctions_validator(
@@ -19,154 +147,295 @@ pub async fn auctions_validator(
     shared_blockchain_vector: Arc<Mutex<Vec<Blockchain>>>,
     shared_auction_house: Arc<Mutex<AuctionHouse>>,
     validation_type: Option<String>,
+    events: EventSender,
+    outbox: SharedOutbox,
+    ledger: SharedLedgerQueue,
 ) {
     let mut byte_count = 0;
     let mut tx: Vec<String> = Vec::new();
+    let mut charges: ChargeQueue<Charge> = ChargeQueue::default();
 
     loop {
+        // settlements are charged before any lock is taken
+        apply_charges(&mut charges).await;
+        // deposits stay queued until a block holds them
+        for entry in ledger.lock().await.entries.iter() {
+            if !tx.contains(entry) {
+                byte_count += entry.len();
+                tx.push(entry.clone());
+            }
+        }
+        // closed auctions and notifications are published once the locks are released
+        let mut closed: Vec<Auction> = Vec::new();
+        let mut notices: Vec<(Auction, Vec<(Recipient, Notification)>)> = Vec::new();
         let mut auction_house = shared_auction_house.lock().await;
         let shared_node = dest_ip.lock().await;
         let rt = <Vec<Bucket> as Clone>::clone(&shared_node.routing_table.lock().await.buckets)
             .into_iter()
-            .map(|x| {
+            .flat_map(|x| {
                 x.nodes
                     .into_iter()
                     .map(|node_info| node_info.addr.to_string())
                     .collect::<Vec<String>>()
             })
-            .flatten()
             .collect::<Vec<String>>();
 
         for auction in auction_house.auctions.iter_mut() {
-            if &auction.end_time < &Utc::now()
-                && !tx.contains(&auction.signature)
-                && !auction.bids.is_empty()
-                && auction.active
+            // only the primary replica announces and settles, the others take over when it
+            // has not settled the auction in time
+            let primary = {
+                let routing_table = shared_node.routing_table.lock().await;
+                replica_set(
+                    &shared_node.id,
+                    shared_node.addr,
+                    &routing_table,
+                    &auction.signature,
+                )[0]
+                .id == shared_node.id
+            };
+            let takeover =
+                auction.settlement_time() + Duration::seconds(SETTLEMENT_TAKEOVER) < Utc::now();
+
+            // scheduled auction reached its start time, let subscribers know bidding is open
+            if primary && !auction.opening_notified && auction.start_time <= Utc::now() {
+                auction.opening_notified = true;
+                if auction.active && !auction.cancelled {
+                    notices.push((
+                        auction.clone(),
+                        vec![(
+                            Recipient::Subscribers,
+                            Notification::BiddingOpen {
+                                auction_signature: auction.signature.clone(),
+                                item_name: auction.item_name.clone(),
+                            },
+                        )],
+                    ));
+                }
+            }
+            if auction.active
+                && (auction.cancelled || auction.settlement_time() < Utc::now())
+                && (primary || takeover)
             {
-                // update winning user
-                let _ = update_user("10.10.0.2", &auction.bids.last().unwrap()).await;
+                let before = auction.clone();
+                auction.active = false;
+                publish_changes(&events, Some(&before), auction);
+                closed.push(auction.clone());
 
-                // insert into transaction vector
-                tx.push(auction.signature.to_string());
+                // `active` is local to each replica, a settlement already waiting for a block
+                // or found in the chain is never made again, by the primary or by a replica
+                // that lagged behind it
+                if settles(&tx, &auction.signature)
+                    || recorded_settlement(
+                        &shared_blockchain_vector.lock().await,
+                        &auction.signature,
+                    )
+                {
+                    println!(
+                        "{}",
+                        format!("Auction already settled: {:?}\n", auction.signature).yellow()
+                    );
+                    continue;
+                }
+
+                let settlement = if auction.cancelled {
+                    // nothing to settle, the cancellation is recorded in the block
+                    println!(
+                        "{}",
+                        format!("Auction cancelled by seller: {:?}\n", auction.signature).yellow()
+                    );
+                    Settlement {
+                        auction_signature: auction.signature.clone(),
+                        awards: Vec::new(),
+                        forfeits: Vec::new(),
+                    }
+                } else {
+                    let settlement = settle(auction);
+
+                    // sealed bidders that never revealed lose their deposit
+                    for forfeit in settlement.forfeits.iter() {
+                        charges.push(
+                            Charge::Forfeit(forfeit.clone(), auction.clone()),
+                            Utc::now(),
+                        );
+                    }
+                    // update every winning user, charged the clearing price rather than its bid
+                    for award in settlement.awards.iter() {
+                        let charged_bid = Bid {
+                            amount: award.price,
+                            ..award.bid.clone()
+                        };
+                        charges.push(Charge::Award(charged_bid, auction.clone()), Utc::now());
+                    }
+                    notices.push((
+                        auction.clone(),
+                        settlement_notifications(auction, &settlement),
+                    ));
+                    if settlement.awards.is_empty() {
+                        // no bids or reserve not met, the item stays with the seller. The
+                        // settlement is still recorded so no other replica settles it again.
+                        println!(
+                            "{}",
+                            format!("Auction closed without a winner: {:?}\n", auction.signature)
+                                .yellow()
+                        );
+                    }
+                    settlement
+                };
+                // the other sealed bidders get their deposit back, all of them when the
+                // auction was cancelled
+                for committed in auction.commitments.iter() {
+                    if !settlement
+                        .forfeits
+                        .iter()
+                        .any(|forfeit| forfeit.bidder == committed.bidder)
+                    {
+                        charges.push(
+                            Charge::Release(committed.bidder.clone(), auction.clone()),
+                            Utc::now(),
+                        );
+                    }
+                }
+
+                // the whole settlement, every winner included, is a single transaction
+                let settlement_tx = serde_json::to_string(&settlement).unwrap();
 
-                auction.active = false;
                 println!(
                     "{}",
                     format!("Auction expired: {:?}\n", auction.signature).blue()
                 );
-                byte_count += &auction.signature.as_bytes().len();
-                if byte_count >= 5 {
-                    let mut result_validation = false;
-                    let new_block = block_generator(shared_blockchain_vector.clone(), tx).await;
-
-                    let mut new_block_to_validate = new_block.clone();
-                    new_block_to_validate.hash = "".to_string();
-                    new_block_to_validate.nounce = 0;
-
-                    let mut list_peer_validation: Vec<bool> = Vec::new();
-
-                    let validation_nounce =
-                        block_handler(&mut shared_blockchain_vector.clone(), new_block.clone())
-                            .await;
-                    if validation_nounce == new_block.nounce {
-                        result_validation = true;
-                    }
+                byte_count += settlement_tx.len();
+                tx.push(settlement_tx);
+            }
+        }
+        drop(shared_node);
+        drop(auction_house);
 
-                    match validation_type.as_ref() {
-                        Some(s) if s == "pos" => {
-                            // create puzzle with solution
-                            let mut peer_puzzle_winner = "".to_string();
-                            let (puzzle_set, puzzle_solution_set) = puzzle_builder().await;
-                            let mut handle_puzzle_results = Vec::new();
-
-                            for peer in rt.clone() {
-                                // send puzzle to peers
-                                let handle_puzzle_result = tokio::task::spawn(pos_miner_puzzle(
-                                    puzzle_set.clone(),
-                                    peer.clone().split(':').next().unwrap().to_owned(),
-                                ));
-                                handle_puzzle_results.push(handle_puzzle_result);
-                            }
+        for auction in closed {
+            tokio::spawn(publish_auction(dest_ip.clone(), auction.clone()));
+            tokio::spawn(replicate(dest_ip.clone(), auction));
+        }
+        for (auction, notifications) in notices {
+            queue_notifications(&outbox, &auction, notifications).await;
+        }
+        // settlements and deposits of this round go into a block
+        if byte_count >= 5 {
+            let mut result_validation = false;
+            let new_block = block_generator(shared_blockchain_vector.clone(), tx).await;
 
-                            for handle_puzzle_result in handle_puzzle_results {
-                                match handle_puzzle_result.await {
-                                    Ok(Ok((solution_result, peer_ip))) => {
-                                        if solution_result == puzzle_solution_set {
-                                            peer_puzzle_winner = peer_ip;
-                                            break;
-                                        }
-                                    }
-                                    Ok(Err(e)) => eprintln!("Puzzle result error: {}", e),
-                                    Err(e) => eprintln!("Puzzle request error: {}", e),
-                                }
-                            }
-                            if !peer_puzzle_winner.is_empty() {
-                                println!(
-                                    "{}",
-                                    format!("Node Puzzle Winner: {}\n", peer_puzzle_winner).green()
-                                );
-                                // send block to the first node that retrieves the puzzle corretly
-                                let block_validated_nounce = block_peer_validator_client(
-                                    new_block_to_validate,
-                                    peer_puzzle_winner.clone(),
-                                )
-                                .await
-                                .expect("error getting validation from peer");
+            let mut new_block_to_validate = new_block.clone();
+            new_block_to_validate.hash = "".to_string();
+            new_block_to_validate.nounce = 0;
 
-                                if block_validated_nounce == new_block.clone().nounce {
-                                    list_peer_validation.push(true);
-                                    println!(
-                                        "{}",
-                                        format!(
-                                            "Peer validation {}: {}\n",
-                                            &peer_puzzle_winner, true
-                                        )
-                                        .green()
-                                    );
+            let mut list_peer_validation: Vec<bool> = Vec::new();
+
+            let validation_nounce =
+                block_handler(&mut shared_blockchain_vector.clone(), new_block.clone()).await;
+            if validation_nounce == new_block.nounce {
+                result_validation = true;
+            }
+
+            match validation_type.as_ref() {
+                Some(s) if s == "pos" => {
+                    // create puzzle with solution
+                    let mut peer_puzzle_winner = "".to_string();
+                    let (puzzle_set, puzzle_solution_set) = puzzle_builder().await;
+                    let mut handle_puzzle_results = Vec::new();
+
+                    for peer in rt.clone() {
+                        // send puzzle to peers
+                        let handle_puzzle_result = tokio::task::spawn(pos_miner_puzzle(
+                            puzzle_set.clone(),
+                            peer.clone().split(':').next().unwrap().to_owned(),
+                        ));
+                        handle_puzzle_results.push(handle_puzzle_result);
+                    }
+
+                    for handle_puzzle_result in handle_puzzle_results {
+                        match handle_puzzle_result.await {
+                            Ok(Ok((solution_result, peer_ip))) => {
+                                if solution_result == puzzle_solution_set {
+                                    peer_puzzle_winner = peer_ip;
+                                    break;
                                 }
                             }
+                            Ok(Err(e)) => eprintln!("Puzzle result error: {}", e),
+                            Err(e) => eprintln!("Puzzle request error: {}", e),
                         }
-                        Some(s) if s == "pow" => {
-                            let mut handle_peer_validation_results = Vec::new();
-
-                            for peer in rt.clone() {
-                                // send puzzle to peers
-                                let handle_puzzle_result =
-                                    tokio::task::spawn(block_peer_validator_client(
-                                        new_block_to_validate.clone(),
-                                        peer.clone().split(':').next().unwrap().to_owned(),
-                                    ));
-                                handle_peer_validation_results.push(handle_puzzle_result);
-                            }
+                    }
+                    if !peer_puzzle_winner.is_empty() {
+                        println!(
+                            "{}",
+                            format!("Node Puzzle Winner: {}\n", peer_puzzle_winner).green()
+                        );
+                        // send block to the first node that retrieves the puzzle corretly
+                        let block_validated_nounce = block_peer_validator_client(
+                            new_block_to_validate,
+                            peer_puzzle_winner.clone(),
+                        )
+                        .await
+                        .expect("error getting validation from peer");
 
-                            for handle_peer_validation_result in handle_peer_validation_results {
-                                match handle_peer_validation_result.await {
-                                    Ok(Ok(block_validated_nounce)) => {
-                                        if block_validated_nounce == new_block.clone().nounce {
-                                            list_peer_validation.push(true);
-                                        }
-                                    }
+                        if block_validated_nounce == new_block.clone().nounce {
+                            list_peer_validation.push(true);
+                            println!(
+                                "{}",
+                                format!("Peer validation {}: {}\n", &peer_puzzle_winner, true)
+                                    .green()
+                            );
+                        }
+                    }
+                }
+                Some(s) if s == "pow" => {
+                    let mut handle_peer_validation_results = Vec::new();
 
-                                    Ok(Err(e)) => eprintln!("Peer validation result error: {}", e),
-                                    Err(e) => eprintln!("Peer validation request error: {}", e),
+                    for peer in rt.clone() {
+                        // send puzzle to peers
+                        let handle_puzzle_result = tokio::task::spawn(block_peer_validator_client(
+                            new_block_to_validate.clone(),
+                            peer.clone().split(':').next().unwrap().to_owned(),
+                        ));
+                        handle_peer_validation_results.push(handle_puzzle_result);
+                    }
+
+                    for handle_peer_validation_result in handle_peer_validation_results {
+                        match handle_peer_validation_result.await {
+                            Ok(Ok(block_validated_nounce)) => {
+                                if block_validated_nounce == new_block.clone().nounce {
+                                    list_peer_validation.push(true);
                                 }
                             }
-                        }
-                        _ => {}
-                    }
 
-                    tx = Vec::new();
-                    if result_validation
-                        && list_peer_validation
-                            .iter()
-                            .any(|r_validation| r_validation == &true)
-                    {
-                        blockchain_handler(&mut shared_blockchain_vector.clone()).await;
+                            Ok(Err(e)) => eprintln!("Peer validation result error: {}", e),
+                            Err(e) => eprintln!("Peer validation request error: {}", e),
+                        }
                     }
+                }
+                _ => {}
+            }
 
-                    byte_count = 0;
+            tx = Vec::new();
+            // deposits written to a block this node holds leave the queue, the others go
+            // into the next one
+            if result_validation {
+                if let Err(e) = ledger
+                    .lock()
+                    .await
+                    .remove_recorded(LEDGER_FILE, &new_block.tx)
+                    .await
+                {
+                    eprintln!("Failed to save the ledger queue: {}", e);
                 }
             }
+            if result_validation
+                && list_peer_validation
+                    .iter()
+                    .any(|r_validation| r_validation == &true)
+            {
+                blockchain_handler(&mut shared_blockchain_vector.clone()).await;
+            }
+
+            byte_count = 0;
         }
+        sleep(tokio::time::Duration::from_secs(VALIDATOR_INTERVAL)).await;
     }
 }
@@ -8,6 +8,7 @@ use crate::blockchain_grpc::ProofOfStakePuzzleResponse;
 use crate::blockchain_grpc::{
     ProofOfWorkRequest, ProofOfWorkResponse, RetrieveBlockchainRequest, RetrieveBlockchainResponse,
 };
+use crate::conversions::DecodeError;
 use std::sync::Arc;
 use std::vec::Vec;
 use tokio::sync::Mutex;
@@ -31,15 +32,18 @@ impl BlockchainGrpc for BlockchainServer {
         // here get the shared state and lock it
         let bch = &self.shared_blockchain_state.lock().await;
         // send main blockchain to client!!!
-        let blockchain = serde_json::to_string(&bch.get(0).unwrap()).unwrap();
+        let chain = bch.get(0).unwrap().clone().into();
 
-        Ok(Response::new(RetrieveBlockchainResponse { blockchain }))
+        Ok(Response::new(RetrieveBlockchainResponse {
+            chain: Some(chain),
+        }))
     }
     async fn proof_of_work(
         &self,
         request: Request<ProofOfWorkRequest>,
     ) -> BlockchainGrpcResult<ProofOfWorkResponse> {
-        let block: Block = serde_json::from_str(&request.into_inner().block).unwrap();
+        let candidate = request.into_inner().candidate;
+        let block = Block::from(candidate.ok_or(DecodeError::MissingField("candidate"))?);
 
         println!(
         "{}",
@@ -50,16 +54,15 @@ impl BlockchainGrpc for BlockchainServer {
         .green()
     );
 
-        let nounce_validation =
-            block_handler(&mut self.shared_blockchain_state.clone(), block).await;
-        let validation = nounce_validation.to_string();
-        Ok(Response::new(ProofOfWorkResponse { validation }))
+        let nounce = block_handler(&mut self.shared_blockchain_state.clone(), block).await;
+        Ok(Response::new(ProofOfWorkResponse { nounce }))
     }
     async fn proof_of_stake_puzzle(
         &self,
         request: Request<ProofOfStakePuzzleRequest>,
     ) -> BlockchainGrpcResult<ProofOfStakePuzzleResponse> {
-        let puzzleset: PuzzleSet = serde_json::from_str(&request.into_inner().puzzle).unwrap();
+        let puzzles = request.into_inner().puzzles;
+        let puzzleset = PuzzleSet::from(puzzles.ok_or(DecodeError::MissingField("puzzles"))?);
         let mut puzzle_solution: PuzzleSet = PuzzleSet {
             puzzle_list: vec![],
         };
@@ -85,8 +88,9 @@ impl BlockchainGrpc for BlockchainServer {
             }
             println!("puzzle solved nounce:{}\n", nounce_solution);
         }
-        let result = serde_json::to_string(&puzzle_solution).unwrap();
-        Ok(Response::new(ProofOfStakePuzzleResponse { result }))
+        Ok(Response::new(ProofOfStakePuzzleResponse {
+            solutions: Some(puzzle_solution.into()),
+        }))
     }
 }
 
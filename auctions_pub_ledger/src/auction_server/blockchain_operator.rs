@@ -3,6 +3,7 @@ use crate::auction_server::blockchain_operation::client::blockchain_client;
 use crate::auction_server::blockchain_operation::client::blockchain_client_async;
 use crate::blockchain_grpc::ProofOfWorkRequest;
 use crate::blockchain_grpc::RetrieveBlockchainRequest;
+use crate::conversions::DecodeError;
 use std::fs;
 use std::vec::Vec;
 
@@ -14,8 +15,8 @@ pub async fn get_remote_blockchain(
     let response = client.retrieve_blockchain(request).await?;
 
     let mut blockchain_vector: Vec<Blockchain> = Vec::new();
-    let bch_str = response.into_inner().blockchain;
-    let main_blockchain: Blockchain = serde_json::from_str(&bch_str).unwrap();
+    let chain = response.into_inner().chain;
+    let main_blockchain = Blockchain::from(chain.ok_or(DecodeError::MissingField("chain"))?);
     blockchain_vector.push(main_blockchain);
 
     Ok(blockchain_vector)
@@ -27,13 +28,12 @@ pub async fn block_peer_validator_client(
 ) -> Result<u64, Box<dyn std::error::Error + Send + Sync>> {
     let mut client = blockchain_client_async(peer).await?;
 
-    let block = serde_json::to_string(&block_to_validate).unwrap();
-    let request = tonic::Request::new(ProofOfWorkRequest { block });
+    let request = tonic::Request::new(ProofOfWorkRequest {
+        candidate: Some(block_to_validate.into()),
+    });
     let response = client.proof_of_work(request).await?;
 
-    let nounce_str: String = response.into_inner().validation;
-
-    Ok(nounce_str.parse::<u64>().unwrap())
+    Ok(response.into_inner().nounce)
 }
 
 pub async fn save_blockchain_locally(blockchain: &Blockchain, file_path: &str) {
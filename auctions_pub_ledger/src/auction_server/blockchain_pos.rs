@@ -1,6 +1,7 @@
 use crate::auction_server::blockchain::Blockchain;
 use crate::auction_server::blockchain_operation::client::blockchain_client_async;
 use crate::blockchain_grpc::ProofOfStakePuzzleRequest;
+use crate::conversions::DecodeError;
 use colored::*;
 use rand::{distributions::Alphanumeric, Rng};
 use serde::{Deserialize, Serialize};
@@ -77,12 +78,13 @@ pub async fn pos_miner_puzzle(
 
     let mut client = blockchain_client_async(peer.clone()).await?;
 
-    let puzzle = serde_json::to_string(&puzzleset).unwrap();
-    let request = tonic::Request::new(ProofOfStakePuzzleRequest { puzzle });
+    let request = tonic::Request::new(ProofOfStakePuzzleRequest {
+        puzzles: Some(puzzleset.into()),
+    });
     let response = client.proof_of_stake_puzzle(request).await?;
 
-    let result_response = response.into_inner().result;
-    let solution_puzzle: PuzzleSet = serde_json::from_str(&result_response).unwrap();
+    let solutions = response.into_inner().solutions;
+    let solution_puzzle = PuzzleSet::from(solutions.ok_or(DecodeError::MissingField("solutions"))?);
 
     Ok((solution_puzzle, peer))
 }
@@ -1,3 +1,6 @@
+pub mod auction_directory;
+pub mod auction_events;
+pub mod auction_notifications;
 pub mod auction_handler;
 pub mod auction_validator;
 pub mod blockchain;
@@ -5,3 +8,9 @@ pub mod blockchain_operation;
 pub mod blockchain_operator;
 pub mod blockchain_pos;
 pub mod blockchain_pow;
+pub mod notification_outbox;
+pub mod settlement;
+pub mod replication;
+pub mod search;
+pub mod user_sessions;
+pub mod user_ledger;
@@ -0,0 +1,332 @@
+use crate::auction_app::auction::{Auction, Endpoint, Notification};
+use crate::auction_app::notifications::notify_client::send_notification;
+use crate::auction_server::auction_notifications::Recipient;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tokio::time::sleep;
+
+// File the outbox survives restarts in
+pub const OUTBOX_FILE: &str = "notification_outbox.json";
+// Notifications are retried and can be caught up on for this long
+pub const OUTBOX_RETENTION_HOURS: i64 = 24;
+// Seconds before the first retry, doubled on every failure up to the cap
+pub const RETRY_BASE: i64 = 2;
+pub const RETRY_CAP: i64 = 600;
+// Seconds between two passes of the delivery loop
+const DELIVERY_INTERVAL: u64 = 1;
+
+pub type SharedOutbox = Arc<Mutex<NotificationOutbox>>;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SequencedNotification {
+    pub sequence: u64,
+    pub notification: Notification,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutboxEntry {
+    pub subscriber: String,
+    pub sequence: u64,
+    pub notification: Notification,
+    pub created_at: DateTime<Utc>,
+    pub delivered: bool,
+}
+
+// Delivery state of one subscriber, notifications reach it in sequence order so a failed
+// delivery holds back the ones after it
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct SubscriberState {
+    pub last_sequence: u64,
+    pub attempts: u32,
+    pub next_attempt: Option<DateTime<Utc>>,
+    #[serde(skip)]
+    pub in_flight: bool,
+}
+
+// Notifications are queued per user public key and delivered to the address the user last
+// proved to be at, so a user keeps their notifications when their address changes
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct NotificationOutbox {
+    pub subscribers: BTreeMap<String, SubscriberState>,
+    pub entries: Vec<OutboxEntry>,
+    // latest endpoint signed by each user, notifications are delivered to its address
+    #[serde(default)]
+    pub endpoints: BTreeMap<String, Endpoint>,
+    // highest sequence handed to a pruned subscriber, a subscriber coming back counts on
+    // from it so it never sees a sequence number twice
+    #[serde(default)]
+    pub sequence_floor: u64,
+}
+
+// wait before the next attempt after `attempts` failed deliveries in a row
+pub fn backoff(attempts: u32) -> Duration {
+    let seconds = RETRY_BASE.saturating_mul(1 << attempts.saturating_sub(1).min(30));
+    Duration::seconds(seconds.min(RETRY_CAP))
+}
+
+impl NotificationOutbox {
+    // an outbox that was never saved is empty, one that can not be read is an error
+    pub fn load(path: &str) -> io::Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(data) => serde_json::from_str(&data)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(NotificationOutbox::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    // written next to the file and renamed over it, a crash never leaves half an outbox
+    pub async fn save(&self, path: &str) -> io::Result<()> {
+        let serialized = serde_json::to_string_pretty(self)?;
+        let temporary = format!("{}.tmp", path);
+        tokio::fs::write(&temporary, serialized).await?;
+        tokio::fs::rename(&temporary, path).await
+    }
+
+    // persisting is retried on the next change, a failure only loses what changed since
+    pub async fn persist(&self) {
+        if let Err(e) = self.save(OUTBOX_FILE).await {
+            println!("Failed to save the notification outbox: {}", e);
+        }
+    }
+
+    // keep an endpoint whose signature was checked, unless a newer one of the user is known
+    pub fn remember(&mut self, endpoint: &Endpoint) -> bool {
+        match self.endpoints.get(&endpoint.user_id) {
+            Some(known) if known.timestamp >= endpoint.timestamp => false,
+            _ => {
+                self.endpoints
+                    .insert(endpoint.user_id.clone(), endpoint.clone());
+                true
+            }
+        }
+    }
+
+    // users a notification goes to, `followers` are those of the auction it is about
+    pub fn resolve(followers: &[String], recipient: &Recipient) -> Vec<String> {
+        match recipient {
+            Recipient::Subscribers => followers.to_vec(),
+            Recipient::SubscribersExcept(user_id) => followers
+                .iter()
+                .filter(|follower| *follower != user_id)
+                .cloned()
+                .collect(),
+            Recipient::User(user_id) => vec![user_id.clone()],
+        }
+    }
+
+    // queue a notification, returns the sequence number the subscriber sees it under
+    pub fn enqueue(
+        &mut self,
+        subscriber: &str,
+        notification: Notification,
+        now: DateTime<Utc>,
+    ) -> u64 {
+        let state = self
+            .subscribers
+            .entry(subscriber.to_string())
+            .or_insert_with(|| SubscriberState {
+                last_sequence: self.sequence_floor,
+                ..SubscriberState::default()
+            });
+        state.last_sequence += 1;
+        self.entries.push(OutboxEntry {
+            subscriber: subscriber.to_string(),
+            sequence: state.last_sequence,
+            notification,
+            created_at: now,
+            delivered: false,
+        });
+        state.last_sequence
+    }
+
+    // undelivered notifications of a subscriber, oldest first
+    pub fn pending(&self, subscriber: &str) -> Vec<SequencedNotification> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.subscriber == subscriber && !entry.delivered)
+            .map(|entry| SequencedNotification {
+                sequence: entry.sequence,
+                notification: entry.notification.clone(),
+            })
+            .collect()
+    }
+
+    // subscribers with pending notifications whose backoff has run out, marked in flight.
+    // Those without a known address wait until they register one or catch up.
+    pub fn take_due(&mut self, now: DateTime<Utc>) -> Vec<String> {
+        let mut due = Vec::new();
+        for (subscriber, state) in self.subscribers.iter_mut() {
+            let waiting = self
+                .entries
+                .iter()
+                .any(|entry| &entry.subscriber == subscriber && !entry.delivered);
+            if waiting
+                && self.endpoints.contains_key(subscriber)
+                && !state.in_flight
+                && state.next_attempt.is_none_or(|next| next <= now)
+            {
+                state.in_flight = true;
+                due.push(subscriber.clone());
+            }
+        }
+        due
+    }
+
+    pub fn delivered(&mut self, subscriber: &str, sequence: u64) {
+        for entry in self.entries.iter_mut() {
+            if entry.subscriber == subscriber && entry.sequence <= sequence {
+                entry.delivered = true;
+            }
+        }
+        if let Some(state) = self.subscribers.get_mut(subscriber) {
+            state.attempts = 0;
+            state.next_attempt = None;
+        }
+    }
+
+    pub fn failed(&mut self, subscriber: &str, now: DateTime<Utc>) {
+        if let Some(state) = self.subscribers.get_mut(subscriber) {
+            state.attempts += 1;
+            state.next_attempt = Some(now + backoff(state.attempts));
+        }
+    }
+
+    pub fn release(&mut self, subscriber: &str) {
+        if let Some(state) = self.subscribers.get_mut(subscriber) {
+            state.in_flight = false;
+        }
+    }
+
+    // notifications after `since` still retained. `since` is the last sequence the subscriber
+    // received, so it acknowledges what came before. The ones handed out now stay pending
+    // until the next catch up or a push delivery confirms them, the response may get lost.
+    pub fn catch_up(&mut self, subscriber: &str, since: u64) -> Vec<SequencedNotification> {
+        if since > 0 {
+            self.delivered(subscriber, since);
+        }
+        self.entries
+            .iter()
+            .filter(|entry| entry.subscriber == subscriber && entry.sequence > since)
+            .map(|entry| SequencedNotification {
+                sequence: entry.sequence,
+                notification: entry.notification.clone(),
+            })
+            .collect()
+    }
+
+    // drop what is older than the retention and the subscribers left without notifications,
+    // sequence numbers keep counting
+    pub fn prune(&mut self, now: DateTime<Utc>) -> bool {
+        let before = (self.entries.len(), self.subscribers.len());
+        self.entries
+            .retain(|entry| now - entry.created_at < Duration::hours(OUTBOX_RETENTION_HOURS));
+
+        let entries = &self.entries;
+        let mut floor = self.sequence_floor;
+        self.subscribers.retain(|subscriber, state| {
+            let idle =
+                !state.in_flight && !entries.iter().any(|entry| &entry.subscriber == subscriber);
+            if idle {
+                floor = floor.max(state.last_sequence);
+            }
+            !idle
+        });
+        self.sequence_floor = floor;
+        (self.entries.len(), self.subscribers.len()) != before
+    }
+}
+
+// Deliver queued notifications, one task per subscriber so an unreachable subscriber does
+// not hold back the others. `origin` is the address subscribers catch up from.
+pub async fn deliver_notifications(outbox: SharedOutbox, origin: String) {
+    loop {
+        sleep(tokio::time::Duration::from_secs(DELIVERY_INTERVAL)).await;
+
+        let due = {
+            let mut outbox_lock = outbox.lock().await;
+            if outbox_lock.prune(Utc::now()) {
+                outbox_lock.persist().await;
+            }
+            outbox_lock.take_due(Utc::now())
+        };
+        for subscriber in due {
+            tokio::spawn(deliver_to(outbox.clone(), origin.clone(), subscriber));
+        }
+    }
+}
+
+async fn deliver_to(outbox: SharedOutbox, origin: String, subscriber: String) {
+    let (addr, pending) = {
+        let outbox_lock = outbox.lock().await;
+        (
+            outbox_lock
+                .endpoints
+                .get(&subscriber)
+                .map(|endpoint| endpoint.addr.clone())
+                .unwrap_or_default(),
+            outbox_lock.pending(&subscriber),
+        )
+    };
+    for sequenced in pending {
+        let result = send_notification(
+            addr.clone(),
+            origin.clone(),
+            sequenced.sequence,
+            sequenced.notification,
+        )
+        .await;
+
+        let mut outbox_lock = outbox.lock().await;
+        match result {
+            Ok(_) => outbox_lock.delivered(&subscriber, sequenced.sequence),
+            Err(e) => {
+                outbox_lock.failed(&subscriber, Utc::now());
+                println!(
+                    "Notification to {} at {} failed, retrying later: {}",
+                    subscriber, addr, e
+                );
+                break;
+            }
+        }
+    }
+    let mut outbox_lock = outbox.lock().await;
+    outbox_lock.release(&subscriber);
+    outbox_lock.persist().await;
+}
+
+// queue notifications about `auction` for their recipients, persisted before delivery
+// starts. A user reached through several recipients gets the same notification once. The
+// endpoints replicated with the auction reach users that never talked to this node.
+pub async fn queue_notifications(
+    outbox: &SharedOutbox,
+    auction: &Auction,
+    notifications: Vec<(Recipient, Notification)>,
+) {
+    if notifications.is_empty() {
+        return;
+    }
+    let followers = auction.followers();
+    let mut queued: Vec<(String, Notification)> = Vec::new();
+    for (recipient, notification) in notifications {
+        for user_id in NotificationOutbox::resolve(&followers, &recipient) {
+            let item = (user_id, notification.clone());
+            if !queued.contains(&item) {
+                queued.push(item);
+            }
+        }
+    }
+    let mut outbox_lock = outbox.lock().await;
+    for endpoint in auction.endpoints.iter() {
+        outbox_lock.remember(endpoint);
+    }
+    for (user_id, notification) in queued {
+        outbox_lock.enqueue(&user_id, notification, Utc::now());
+    }
+    outbox_lock.persist().await;
+}
@@ -0,0 +1,392 @@
+use crate::auction_app::auction::{
+    Auction, AuctionHouse, AuctionKind, Bid, BidReveal, DutchAccept,
+};
+use crate::auction_app::auction_operation::client::run_client;
+use crate::auction_app::auction_operation::dht::auction_key;
+use crate::auction_client::ClientError;
+use crate::auction_server::auction_handler::{
+    check_accept, check_bid, check_reveal, check_terms, validate_tx_integrity, Rejection,
+    MAX_CLOCK_SKEW,
+};
+use crate::auction_tx::ReplicateAuctionRequest;
+use crate::kademlia_node_search::node::Node;
+use crate::kademlia_node_search::node_functions::routing_table::{NodeInfo, RoutingTable};
+use bytes::Bytes;
+use chrono::Utc;
+use colored::*;
+use std::net::IpAddr;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tokio::time::{sleep, Duration};
+
+// Number of nodes holding a copy of each auction
+pub const REPLICATION_FACTOR: usize = 3;
+// Seconds between two anti-entropy rounds
+pub const ANTI_ENTROPY_INTERVAL: u64 = 30;
+// Seconds the other replicas wait on the primary before settling an auction themselves
+pub const SETTLEMENT_TAKEOVER: i64 = 60;
+
+// The replicas of an auction are the nodes closest to its DHT key, this node included.
+// The first one is the primary, the only replica settling the auction and announcing it.
+pub fn replica_set(
+    own_id: &Bytes,
+    own_addr: std::net::SocketAddr,
+    routing_table: &RoutingTable,
+    auction_signature: &str,
+) -> Vec<NodeInfo> {
+    let key = Bytes::from(auction_key(auction_signature));
+    let mut replicas = routing_table.find_closest(&key);
+    replicas.push(NodeInfo::new(own_id.clone(), own_addr));
+    // the id breaks distance ties so every node orders the set the same way
+    replicas.sort_by(|a, b| {
+        RoutingTable::xor_distance(&a.id, &key)
+            .cmp(&RoutingTable::xor_distance(&b.id, &key))
+            .then_with(|| a.id.cmp(&b.id))
+    });
+    replicas.dedup();
+    replicas.truncate(REPLICATION_FACTOR);
+    replicas
+}
+
+// A Dutch accept is kept as a bid signed over the accept, the timestamp is part of it
+fn as_accept(bid: &Bid) -> DutchAccept {
+    DutchAccept {
+        bidder: bid.bidder.clone(),
+        auction_signature: bid.auction_signature.clone(),
+        timestamp: bid.timestamp,
+        signature: bid.signature.clone(),
+    }
+}
+
+// A sealed bid is kept with the salt of its reveal, which opens the commitment again
+fn as_reveal(bid: &Bid) -> BidReveal {
+    BidReveal {
+        bid: Bid {
+            salt: String::new(),
+            ..bid.clone()
+        },
+        salt: bid.salt.clone(),
+    }
+}
+
+// Bids taken from a peer are checked again by the auction rules at the stamp of the node
+// that accepted them, against the bids stamped before. A sealed bid must open the
+// commitment of its bidder, a peer can not change the amount that was revealed.
+pub fn check_replicated_bid(auction: &Auction, bid: &Bid) -> Result<(), Rejection> {
+    match auction.kind {
+        AuctionKind::English => check_bid(auction, bid, bid.timestamp),
+        AuctionKind::Dutch { .. } => check_accept(auction, &as_accept(bid), bid.timestamp),
+        AuctionKind::SealedBid { .. } => {
+            if auction.cancelled {
+                return Err(Rejection::NotInRevealPhase);
+            }
+            check_reveal(auction, &as_reveal(bid), bid.timestamp)
+        }
+    }
+}
+
+// Replicas converge by exchanging whole records. Bids and commitments are unioned, then
+// the bids are replayed in stamp order and only the ones the rules allow are kept, so
+// every replica holding the same bids keeps the same ones. The latest subscription of each
+// user wins and a signed cancellation of the seller wins over the bids. Whether the auction
+// is still open is never taken from a peer, each replica closes it by the clock. The
+// signed fields never change. Returns whether `local` changed.
+pub fn merge_auction(local: &mut Auction, remote: &Auction) -> bool {
+    if local.signature != remote.signature {
+        return false;
+    }
+    let before = serde_json::to_string(local).unwrap();
+
+    if local.cancellation.is_none() {
+        if let Some(cancel) = remote
+            .cancellation
+            .as_ref()
+            .filter(|cancel| cancel.seller == local.user_id)
+        {
+            local.cancellation = Some(cancel.clone());
+            local.cancelled = true;
+        }
+    }
+    for commitment in remote.commitments.iter() {
+        if !local
+            .commitments
+            .iter()
+            .any(|known| known.signature == commitment.signature)
+        {
+            local.commitments.push(commitment.clone());
+        }
+    }
+    // one commitment per bidder, the same one on every replica
+    local.commitments.sort_by(|a, b| {
+        a.bidder
+            .cmp(&b.bidder)
+            .then_with(|| a.signature.cmp(&b.signature))
+    });
+    local.commitments.dedup_by(|a, b| a.bidder == b.bidder);
+    local
+        .commitments
+        .retain(|commitment| commitment.bidder != local.user_id);
+
+    let mut bids = local.bids.clone();
+    for bid in remote.bids.iter() {
+        if !bids.iter().any(|known| known.signature == bid.signature) {
+            bids.push(bid.clone());
+        }
+    }
+    // bid order decides ties, every replica sorts by the stamp of the accepting node
+    bids.sort_by(|a, b| {
+        a.timestamp
+            .cmp(&b.timestamp)
+            .then_with(|| a.signature.cmp(&b.signature))
+    });
+    let mut replayed = Auction {
+        bids: Vec::new(),
+        active: true,
+        cancelled: local.cancellation.is_some(),
+        ..local.clone()
+    };
+    for bid in bids {
+        // an accept pays the price at its signed timestamp, whatever amount the peer sent
+        let bid = match replayed.dutch_price_at(bid.timestamp) {
+            Some(price) => Bid {
+                amount: price,
+                ..bid
+            },
+            None => bid,
+        };
+        if check_replicated_bid(&replayed, &bid).is_ok() {
+            replayed.bids.push(bid);
+        }
+    }
+    local.bids = replayed.bids;
+
+    // bidders follow the auction as they do on the node accepting their bid
+    let bidders: Vec<String> = local
+        .bids
+        .iter()
+        .map(|bid| bid.bidder.clone())
+        .chain(
+            local
+                .commitments
+                .iter()
+                .map(|commitment| commitment.bidder.clone()),
+        )
+        .collect();
+    for bidder in bidders {
+        if !local.subscribers.contains(&bidder) {
+            local.subscribers.push(bidder);
+        }
+    }
+    for subscription in remote.subscriptions.iter() {
+        local.record_subscription(subscription);
+    }
+    for endpoint in remote.endpoints.iter() {
+        local.record_endpoint(endpoint);
+    }
+    local.opening_notified |= remote.opening_notified;
+
+    serde_json::to_string(local).unwrap() != before
+}
+
+// Records coming from peers are only trusted as far as their signatures go, bids,
+// commitments, subscriptions, endpoints and cancellations that do not verify are dropped,
+// as are bids stamped ahead of this node's clock. `None` when the seller signature is invalid
+// or a price of the seller is not a finite amount.
+pub async fn verified_record(auction: Auction) -> Option<Auction> {
+    if check_terms(&auction).is_err()
+        || !validate_tx_integrity(
+            &auction.signed_content(),
+            &auction.user_id,
+            auction.signature.clone(),
+        )
+        .await
+        .unwrap_or(false)
+    {
+        return None;
+    }
+
+    let mut record = Auction {
+        bids: Vec::new(),
+        commitments: Vec::new(),
+        subscriptions: Vec::new(),
+        endpoints: Vec::new(),
+        cancellation: None,
+        ..auction.clone()
+    };
+    let latest_stamp = Utc::now() + chrono::Duration::seconds(MAX_CLOCK_SKEW);
+    for bid in auction.bids {
+        let signed_content = match record.kind {
+            AuctionKind::Dutch { .. } => as_accept(&bid).signed_content(),
+            _ => bid.signed_content(),
+        };
+        if bid.auction_signature == record.signature
+            && bid.timestamp <= latest_stamp
+            && validate_tx_integrity(&signed_content, &bid.bidder, bid.signature.clone())
+                .await
+                .unwrap_or(false)
+        {
+            record.bids.push(bid);
+        }
+    }
+    for commitment in auction.commitments {
+        if commitment.auction_signature == record.signature
+            && validate_tx_integrity(
+                &commitment.signed_content(),
+                &commitment.bidder,
+                commitment.signature.clone(),
+            )
+            .await
+            .unwrap_or(false)
+        {
+            record.commitments.push(commitment);
+        }
+    }
+    for subscription in auction.subscriptions {
+        if subscription.auction_signature == record.signature
+            && validate_tx_integrity(
+                &subscription.signed_content(),
+                &subscription.subscriber,
+                subscription.signature.clone(),
+            )
+            .await
+            .unwrap_or(false)
+        {
+            record.record_subscription(&subscription);
+        }
+    }
+    for endpoint in auction.endpoints {
+        if validate_tx_integrity(
+            &endpoint.signed_content(),
+            &endpoint.user_id,
+            endpoint.signature.clone(),
+        )
+        .await
+        .unwrap_or(false)
+        {
+            record.record_endpoint(&endpoint);
+        }
+    }
+    if let Some(cancel) = auction.cancellation {
+        if cancel.auction_signature == record.signature
+            && cancel.seller == record.user_id
+            && validate_tx_integrity(
+                &cancel.signed_content(),
+                &cancel.seller,
+                cancel.signature.clone(),
+            )
+            .await
+            .unwrap_or(false)
+        {
+            record.cancellation = Some(cancel);
+        }
+    }
+    Some(record)
+}
+
+// A record new to this node starts out as the seller listed it and takes the rest through
+// the merge, so nothing the sender did not prove is kept
+pub fn listing(record: &Auction) -> Auction {
+    Auction {
+        bids: Vec::new(),
+        commitments: Vec::new(),
+        active: true,
+        cancelled: false,
+        cancellation: None,
+        opening_notified: record.start_time <= Utc::now(),
+        subscribers: vec![record.user_id.clone()],
+        subscriptions: Vec::new(),
+        endpoints: Vec::new(),
+        ..record.clone()
+    }
+}
+
+// Insert a record received from a peer or merge it into the local copy
+pub fn apply_record(auction_house: &mut AuctionHouse, record: Auction) -> bool {
+    match auction_house
+        .auctions
+        .iter_mut()
+        .find(|auction| auction.signature == record.signature)
+    {
+        Some(auction) => merge_auction(auction, &record),
+        None => {
+            let mut auction = listing(&record);
+            merge_auction(&mut auction, &record);
+            auction_house.add_auction(auction);
+            true
+        }
+    }
+}
+
+// the other replicas of the auction as this node sees them
+async fn replica_peers(node: &Arc<Mutex<Node>>, auction_signature: &str) -> Vec<NodeInfo> {
+    let node_lock = node.lock().await;
+    let routing_table = node_lock.routing_table.lock().await;
+    replica_set(
+        &node_lock.id,
+        node_lock.addr,
+        &routing_table,
+        auction_signature,
+    )
+    .into_iter()
+    .filter(|replica| replica.id != node_lock.id)
+    .collect()
+}
+
+// whether the node at `ip` is a replica of the auction, this node included when it is one.
+// Only replicas push records of an auction and charge its settlement.
+pub async fn is_replica(node: &Arc<Mutex<Node>>, auction_signature: &str, ip: IpAddr) -> bool {
+    let node_lock = node.lock().await;
+    let routing_table = node_lock.routing_table.lock().await;
+    replica_set(
+        &node_lock.id,
+        node_lock.addr,
+        &routing_table,
+        auction_signature,
+    )
+    .iter()
+    .any(|replica| replica.addr.ip() == ip || (replica.id == node_lock.id && ip.is_loopback()))
+}
+
+// push an auction record to a replica, returns whether the replica learned something new
+async fn replicate_auction(peer: &str, auction: &Auction) -> Result<bool, ClientError> {
+    let request = tonic::Request::new(ReplicateAuctionRequest {
+        record: Some(auction.clone().into()),
+    });
+    let response = run_client(peer).await?.replicate_auction(request).await?;
+    Ok(response.into_inner().updated)
+}
+
+// push the record to the other replicas of the auction
+pub async fn replicate(node: Arc<Mutex<Node>>, auction: Auction) {
+    let peers: Vec<String> = replica_peers(&node, &auction.signature)
+        .await
+        .into_iter()
+        .map(|replica| replica.addr.ip().to_string())
+        .collect();
+
+    for peer in peers {
+        if let Err(e) = replicate_auction(&peer, &auction).await {
+            eprintln!(
+                "{}",
+                format!(
+                    "Failed to replicate auction {} to {}: {}",
+                    auction.signature, peer, e
+                )
+                .red()
+            );
+        }
+    }
+}
+
+// Anti-entropy: every round each held auction is pushed to its current replica set, which
+// repairs replicas that missed an update, restarted empty or just joined closer to the key
+pub async fn anti_entropy(shared_auction_house: Arc<Mutex<AuctionHouse>>, node: Arc<Mutex<Node>>) {
+    loop {
+        sleep(Duration::from_secs(ANTI_ENTROPY_INTERVAL)).await;
+
+        let auctions = shared_auction_house.lock().await.auctions.clone();
+        for auction in auctions {
+            replicate(node.clone(), auction).await;
+        }
+    }
+}
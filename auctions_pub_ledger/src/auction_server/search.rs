@@ -0,0 +1,159 @@
+use crate::auction_app::auction::Auction;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+
+// Largest page a node hands out for a single query
+pub const MAX_PAGE_SIZE: usize = 100;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub enum StateFilter {
+    #[default]
+    Any,
+    Active,
+    Closed,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub enum SortKey {
+    #[default]
+    EndingSoonest,
+    PriceLowest,
+    PriceHighest,
+    Newest,
+}
+
+// Filters left as `None` match every auction, `page` starts at 0
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct AuctionQuery {
+    pub state: StateFilter,
+    pub seller: Option<String>,
+    pub category: Option<String>,
+    pub min_price: Option<f32>,
+    pub max_price: Option<f32>,
+    #[serde(with = "chrono::serde::ts_seconds_option")]
+    pub ending_before: Option<DateTime<Utc>>,
+    pub sort: SortKey,
+    pub page: usize,
+    pub page_size: usize,
+    // only auctions this user watches
+    pub watcher: Option<String>,
+}
+
+impl Default for AuctionQuery {
+    fn default() -> Self {
+        AuctionQuery {
+            state: StateFilter::Any,
+            seller: None,
+            category: None,
+            min_price: None,
+            max_price: None,
+            ending_before: None,
+            sort: SortKey::EndingSoonest,
+            page: 0,
+            page_size: 20,
+            watcher: None,
+        }
+    }
+}
+
+// One page of results, `total` counts every auction matching the filters
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SearchPage {
+    pub auctions: Vec<Auction>,
+    pub total: usize,
+    pub page: usize,
+    pub page_size: usize,
+}
+
+impl SearchPage {
+    pub fn page_count(&self) -> usize {
+        self.total.div_ceil(self.page_size.max(1))
+    }
+}
+
+impl AuctionQuery {
+    pub fn matches(&self, auction: &Auction) -> bool {
+        let open = auction.active && !auction.cancelled;
+        let state = match self.state {
+            StateFilter::Any => true,
+            StateFilter::Active => open,
+            StateFilter::Closed => !open,
+        };
+        let price = auction.current_price();
+
+        state
+            && self
+                .seller
+                .as_ref()
+                .is_none_or(|seller| &auction.user_id == seller)
+            && self
+                .category
+                .as_ref()
+                .is_none_or(|category| auction.metadata.category.eq_ignore_ascii_case(category))
+            && self.min_price.is_none_or(|min_price| price >= min_price)
+            && self.max_price.is_none_or(|max_price| price <= max_price)
+            && self
+                .ending_before
+                .is_none_or(|ending_before| auction.closing_time() < ending_before)
+            && self
+                .watcher
+                .as_ref()
+                .is_none_or(|watcher| auction.watched_by(watcher))
+    }
+
+    fn compare(&self, a: &Auction, b: &Auction) -> Ordering {
+        let ordering = match self.sort {
+            SortKey::EndingSoonest => a.closing_time().cmp(&b.closing_time()),
+            SortKey::PriceLowest => a.current_price().total_cmp(&b.current_price()),
+            SortKey::PriceHighest => b.current_price().total_cmp(&a.current_price()),
+            SortKey::Newest => b.start_time.cmp(&a.start_time),
+        };
+        // the signature breaks ties so pages stay stable between queries
+        ordering.then_with(|| a.signature.cmp(&b.signature))
+    }
+}
+
+pub fn search(auctions: &[Auction], query: &AuctionQuery) -> SearchPage {
+    let page_size = query.page_size.clamp(1, MAX_PAGE_SIZE);
+    let mut found: Vec<&Auction> = auctions
+        .iter()
+        .filter(|auction| query.matches(auction))
+        .collect();
+    found.sort_by(|a, b| query.compare(a, b));
+
+    SearchPage {
+        total: found.len(),
+        auctions: found
+            .into_iter()
+            .skip(query.page.saturating_mul(page_size))
+            .take(page_size)
+            .cloned()
+            .collect(),
+        page: query.page,
+        page_size,
+    }
+}
+
+// Results of several nodes for the same query as a single list, sorted as the query asks.
+// An auction held by several replicas is listed once, as the replica that saw the most bids
+// holds it.
+pub fn merge_results(
+    query: &AuctionQuery,
+    results: impl IntoIterator<Item = Vec<Auction>>,
+) -> Vec<Auction> {
+    let mut merged: Vec<Auction> = Vec::new();
+    for auction in results.into_iter().flatten() {
+        match merged
+            .iter_mut()
+            .find(|known| known.signature == auction.signature)
+        {
+            Some(known) if known.bids.len() < auction.bids.len() => *known = auction,
+            Some(_) => {}
+            None => merged.push(auction),
+        }
+    }
+    merged.sort_by(|a, b| query.compare(a, b));
+    merged
+}
@@ -0,0 +1,120 @@
+use crate::auction_app::auction::{Auction, AuctionKind, Bid, Pricing};
+use crate::auction_server::blockchain::Blockchain;
+use serde::{Deserialize, Serialize};
+
+// Deposit kept from a sealed-bid bidder that never revealed its commitment
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Forfeit {
+    pub bidder: String,
+    pub auction_signature: String,
+    pub amount: f32,
+}
+
+// Unit won by a bid, `price` is what the bidder pays for it
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Award {
+    pub bid: Bid,
+    pub price: f32,
+}
+
+// Outcome of a closed auction, recorded as a single ledger transaction
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Settlement {
+    pub auction_signature: String,
+    pub awards: Vec<Award>,
+    pub forfeits: Vec<Forfeit>,
+}
+
+// price owed by one of the winning bids under the auction pricing rule
+pub fn clearing_price(auction: &Auction, winning_bids: &[&Bid], winning_bid: &Bid) -> f32 {
+    // a Dutch accept already carries the price it was taken at
+    if let AuctionKind::Dutch { .. } = auction.kind {
+        return winning_bid.amount;
+    }
+    // buying now pays the listed price, whatever pricing rule the auction uses
+    if let Some(buy_now_price) = auction
+        .rules
+        .buy_now_price
+        .filter(|_| auction.meets_buy_now(winning_bid))
+    {
+        return buy_now_price;
+    }
+    match auction.rules.pricing {
+        Pricing::FirstPrice => winning_bid.amount,
+        Pricing::Uniform => winning_bids
+            .iter()
+            .map(|bid| bid.amount)
+            .fold(winning_bid.amount, f32::min),
+        Pricing::SecondPrice => {
+            // best bid from any bidder left without a unit, or the reserve when there is none
+            let second_price = auction
+                .ranked_bids()
+                .iter()
+                .filter(|bid| {
+                    !winning_bids
+                        .iter()
+                        .any(|winner| winner.bidder == bid.bidder)
+                })
+                .map(|bid| bid.amount)
+                .fold(auction.reserve_floor(), f32::max);
+            second_price.min(winning_bid.amount)
+        }
+    }
+}
+
+// settle an auction once its settlement time has passed
+pub fn settle(auction: &Auction) -> Settlement {
+    let winning_bids: Vec<&Bid> = auction
+        .winning_bids()
+        .into_iter()
+        .filter(|bid| bid.amount >= auction.rules.reserve_price)
+        .collect();
+    let awards = winning_bids
+        .iter()
+        .map(|winning_bid| Award {
+            bid: (*winning_bid).clone(),
+            price: clearing_price(auction, &winning_bids, winning_bid),
+        })
+        .collect();
+
+    let forfeits = match auction.kind {
+        AuctionKind::English | AuctionKind::Dutch { .. } => Vec::new(),
+        AuctionKind::SealedBid { deposit, .. } => auction
+            .commitments
+            .iter()
+            .filter(|committed| {
+                !auction
+                    .bids
+                    .iter()
+                    .any(|bid| bid.bidder == committed.bidder)
+            })
+            .map(|committed| Forfeit {
+                bidder: committed.bidder.clone(),
+                auction_signature: auction.signature.clone(),
+                amount: deposit,
+            })
+            .collect(),
+    };
+
+    Settlement {
+        auction_signature: auction.signature.clone(),
+        awards,
+        forfeits,
+    }
+}
+
+// whether the transactions hold a settlement of the auction
+pub fn settles(tx: &[String], auction_signature: &str) -> bool {
+    tx.iter()
+        .filter_map(|tx| serde_json::from_str::<Settlement>(tx).ok())
+        .any(|settlement| settlement.auction_signature == auction_signature)
+}
+
+// whether a block of any known chain already records the settlement of the auction, a
+// replica taking over from the primary leaves such an auction alone
+pub fn recorded_settlement(blockchains: &[Blockchain], auction_signature: &str) -> bool {
+    blockchains
+        .iter()
+        .flat_map(|blockchain| blockchain.blocks.iter())
+        .any(|block| settles(&block.tx, auction_signature))
+}
@@ -0,0 +1,447 @@
+use crate::auction_app::auction::{Auction, AuctionKind, Bid, BidCommitment, Transaction};
+use crate::auction_app::user::{User, UserActivity, UserOperation};
+use crate::auction_server::auction_handler::{validate_tx_integrity, MAX_CLOCK_SKEW};
+use crate::auction_server::settlement::{settle, Forfeit, Settlement};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::{fmt, fs, io};
+use tokio::sync::Mutex;
+use tonic::Status;
+
+// File the ledger queue survives restarts in
+pub const LEDGER_FILE: &str = "ledger_queue.json";
+// Node keeping the user records, settlements are charged there
+pub const USER_RECORDS_NODE: &str = "10.10.0.2";
+
+pub type SharedLedgerQueue = Arc<Mutex<LedgerQueue>>;
+
+// Entries waiting for the next block, credit changes that are not part of a settlement. An
+// entry is saved before the credits change and stays queued until a block holds it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+pub struct LedgerQueue {
+    pub entries: Vec<String>,
+}
+
+impl LedgerQueue {
+    // a queue that was never saved is empty, one that can not be read is an error
+    pub fn load(path: &str) -> io::Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(data) => serde_json::from_str(&data)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(LedgerQueue::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    // written next to the file and renamed over it, a crash never leaves half a queue
+    pub async fn save(&self, path: &str) -> io::Result<()> {
+        let serialized = serde_json::to_string_pretty(self)?;
+        let temporary = format!("{}.tmp", path);
+        tokio::fs::write(&temporary, serialized).await?;
+        tokio::fs::rename(&temporary, path).await
+    }
+
+    // queue an entry, it is only kept once it is saved
+    pub async fn record(&mut self, path: &str, entry: String) -> io::Result<()> {
+        self.entries.push(entry);
+        if let Err(e) = self.save(path).await {
+            self.entries.pop();
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    // entries a block was written with leave the queue
+    pub async fn remove_recorded(&mut self, path: &str, recorded: &[String]) -> io::Result<()> {
+        self.entries.retain(|entry| !recorded.contains(entry));
+        self.save(path).await
+    }
+}
+
+// Reasons a change to a user record is refused
+#[derive(Debug, Clone, PartialEq)]
+pub enum UserOperationError {
+    // not signed by the owner of the record
+    InvalidSignature,
+    StaleTimestamp,
+    InvalidAmount,
+    AlreadyRecorded,
+    // subscriptions are not part of the activity
+    NotAnActivity,
+    AuctionNotFound,
+    NotSettled,
+    // the settlement of the auction charges nothing for it
+    NotInSettlement,
+    // the credits left do not cover the deposit
+    InsufficientCredits,
+    // the deposit stays held until the auction settles
+    DepositStillHeld,
+}
+
+impl fmt::Display for UserOperationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            UserOperationError::InvalidSignature => {
+                write!(f, "operation is not signed by the owner of the record")
+            }
+            UserOperationError::StaleTimestamp => write!(f, "operation timestamp is too far off"),
+            UserOperationError::InvalidAmount => write!(f, "amount must be a positive number"),
+            UserOperationError::AlreadyRecorded => write!(f, "operation is already recorded"),
+            UserOperationError::NotAnActivity => {
+                write!(f, "transaction is not recorded in the activity")
+            }
+            UserOperationError::AuctionNotFound => write!(f, "auction not known by this node"),
+            UserOperationError::NotSettled => write!(f, "auction is not settled yet"),
+            UserOperationError::NotInSettlement => {
+                write!(f, "nothing is charged for it in the settlement")
+            }
+            UserOperationError::InsufficientCredits => {
+                write!(f, "credits do not cover the deposit")
+            }
+            UserOperationError::DepositStillHeld => {
+                write!(
+                    f,
+                    "deposit stays held until the auction settles without forfeiting it"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for UserOperationError {}
+
+impl From<UserOperationError> for Status {
+    fn from(error: UserOperationError) -> Self {
+        match error {
+            UserOperationError::InvalidSignature => Status::permission_denied(error.to_string()),
+            UserOperationError::AlreadyRecorded => Status::already_exists(error.to_string()),
+            UserOperationError::AuctionNotFound => Status::not_found(error.to_string()),
+            UserOperationError::NotSettled
+            | UserOperationError::InsufficientCredits
+            | UserOperationError::DepositStillHeld => {
+                Status::failed_precondition(error.to_string())
+            }
+            _ => Status::invalid_argument(error.to_string()),
+        }
+    }
+}
+
+// Apply an operation to the record of the user that signed it. Credits only change through
+// deposits, auction transactions are recorded with the amounts the user signed.
+pub async fn apply_operation(
+    user: &mut User,
+    operation: &UserOperation,
+    auctions: &[Auction],
+    now: DateTime<Utc>,
+) -> Result<(), UserOperationError> {
+    if operation.user_id() != user.uid {
+        return Err(UserOperationError::InvalidSignature);
+    }
+    match validate_tx_integrity(
+        &operation.signed_content(),
+        &user.uid,
+        operation.signature().to_string(),
+    )
+    .await
+    {
+        Ok(true) => {}
+        _ => return Err(UserOperationError::InvalidSignature),
+    }
+
+    let activity = match operation {
+        UserOperation::Deposit(deposit) => {
+            if (deposit.timestamp - now).num_seconds().abs() > MAX_CLOCK_SKEW {
+                return Err(UserOperationError::StaleTimestamp);
+            }
+            if !deposit.amount.is_finite() || deposit.amount <= 0.0 {
+                return Err(UserOperationError::InvalidAmount);
+            }
+            UserActivity {
+                activity_type: "Deposit".to_string(),
+                auction_signature: String::new(),
+                amount: deposit.amount,
+                signature: deposit.signature.clone(),
+            }
+        }
+        UserOperation::Activity(transaction) => activity(transaction, auctions)?,
+    };
+    if user.recorded(&activity.activity_type, &activity.signature) {
+        return Err(UserOperationError::AlreadyRecorded);
+    }
+    if let UserOperation::Deposit(deposit) = operation {
+        user.credits += deposit.amount;
+    }
+    user.activity.push(activity);
+    Ok(())
+}
+
+// activity entry of a signed auction transaction
+fn activity(
+    transaction: &Transaction,
+    auctions: &[Auction],
+) -> Result<UserActivity, UserOperationError> {
+    let (activity_type, amount) = match transaction {
+        Transaction::Auction(auction) => ("AuctionCreation", auction.starting_bid),
+        Transaction::Bid(bid) => ("Bid", bid.amount),
+        // the amount stays sealed until the reveal
+        Transaction::Commit(_) => ("SealedBid", 0.0),
+        Transaction::Reveal(reveal) => ("Reveal", reveal.bid.amount),
+        // the price is the one the auction was selling at when the user signed
+        Transaction::Accept(accept) => (
+            "DutchAccept",
+            auctions
+                .iter()
+                .find(|auction| auction.signature == accept.auction_signature)
+                .ok_or(UserOperationError::AuctionNotFound)?
+                .dutch_price_at(accept.timestamp)
+                .ok_or(UserOperationError::InvalidAmount)?,
+        ),
+        Transaction::Cancel(_) => ("AuctionCancel", 0.0),
+        Transaction::Subscription(_) => return Err(UserOperationError::NotAnActivity),
+    };
+    Ok(UserActivity {
+        activity_type: activity_type.to_string(),
+        auction_signature: transaction.auction_signature().to_string(),
+        amount,
+        signature: transaction.signature().to_string(),
+    })
+}
+
+// settlement of an auction this node holds, worked out again rather than taken from the caller
+fn settlement(
+    auctions: &[Auction],
+    auction_signature: &str,
+    now: DateTime<Utc>,
+) -> Result<Settlement, UserOperationError> {
+    let auction = auctions
+        .iter()
+        .find(|auction| auction.signature == auction_signature)
+        .ok_or(UserOperationError::AuctionNotFound)?;
+    if !auction.cancelled && auction.settlement_time() > now {
+        return Err(UserOperationError::NotSettled);
+    }
+    Ok(settle(auction))
+}
+
+// What charging a winner came to, the price of the award either way
+#[derive(Debug, Clone, PartialEq)]
+pub enum AwardCharge {
+    Paid(f32),
+    // the credits did not cover the price, the unit and any deposit held for it are lost
+    Forfeited(f32),
+}
+
+// Credits a bid on the auction can count on, a deposit held for it comes back to the
+// winner before the price is charged
+pub fn available_credits(user: &User, auction_signature: &str) -> f32 {
+    user.credits + held_deposit(user, auction_signature).unwrap_or(0.0)
+}
+
+// Charge the winner of `bid` the price the settlement awards it at. Credits never go below
+// zero, a winner that can not pay forfeits the award instead.
+pub fn charge_award(
+    user: &mut User,
+    bid: &Bid,
+    auctions: &[Auction],
+    now: DateTime<Utc>,
+) -> Result<AwardCharge, UserOperationError> {
+    let award = settlement(auctions, &bid.auction_signature, now)?
+        .awards
+        .into_iter()
+        .find(|award| award.bid.signature == bid.signature && award.bid.bidder == user.uid)
+        .ok_or(UserOperationError::NotInSettlement)?;
+    if user.recorded("Won", &award.bid.signature)
+        || user.recorded("AwardForfeit", &award.bid.signature)
+    {
+        return Err(UserOperationError::AlreadyRecorded);
+    }
+
+    let held = held_deposit(user, &award.bid.auction_signature);
+    if available_credits(user, &award.bid.auction_signature) < award.price {
+        if let Some(amount) = held {
+            user.activity.push(UserActivity {
+                activity_type: "DepositForfeit".to_string(),
+                auction_signature: award.bid.auction_signature.clone(),
+                amount,
+                signature: String::new(),
+            });
+        }
+        user.activity.push(UserActivity {
+            activity_type: "AwardForfeit".to_string(),
+            auction_signature: award.bid.auction_signature,
+            amount: award.price,
+            signature: award.bid.signature,
+        });
+        return Ok(AwardCharge::Forfeited(award.price));
+    }
+    if let Some(amount) = held {
+        user.credits += amount;
+        user.activity.push(UserActivity {
+            activity_type: "DepositReturned".to_string(),
+            auction_signature: award.bid.auction_signature.clone(),
+            amount,
+            signature: String::new(),
+        });
+    }
+    user.credits -= award.price;
+    if !user.auctions_winner.contains(&award.bid.auction_signature) {
+        user.auctions_winner
+            .push(award.bid.auction_signature.clone());
+    }
+    user.activity.push(UserActivity {
+        activity_type: "Won".to_string(),
+        auction_signature: award.bid.auction_signature,
+        amount: award.price,
+        signature: award.bid.signature,
+    });
+    Ok(AwardCharge::Paid(award.price))
+}
+
+// Keep the deposit of a sealed bidder that never revealed, as the settlement has it
+pub fn charge_forfeit(
+    user: &mut User,
+    forfeit: &Forfeit,
+    auctions: &[Auction],
+    now: DateTime<Utc>,
+) -> Result<f32, UserOperationError> {
+    let forfeit = settlement(auctions, &forfeit.auction_signature, now)?
+        .forfeits
+        .into_iter()
+        .find(|kept| kept.bidder == user.uid && kept.auction_signature == forfeit.auction_signature)
+        .ok_or(UserOperationError::NotInSettlement)?;
+    // a bidder commits once per auction, so forfeits once
+    if user.activity.iter().any(|activity| {
+        activity.activity_type == "DepositForfeit"
+            && activity.auction_signature == forfeit.auction_signature
+    }) {
+        return Err(UserOperationError::AlreadyRecorded);
+    }
+
+    // a held deposit already left the credits
+    if held_deposit(user, &forfeit.auction_signature).is_none() {
+        user.credits -= forfeit.amount;
+    }
+    user.activity.push(UserActivity {
+        activity_type: "DepositForfeit".to_string(),
+        auction_signature: forfeit.auction_signature,
+        amount: forfeit.amount,
+        signature: String::new(),
+    });
+    Ok(forfeit.amount)
+}
+
+// deposit held for a sealed bid on the auction and not yet returned or forfeited
+fn held_deposit(user: &User, auction_signature: &str) -> Option<f32> {
+    let mut held = None;
+    for activity in user
+        .activity
+        .iter()
+        .filter(|activity| activity.auction_signature == auction_signature)
+    {
+        match activity.activity_type.as_str() {
+            "DepositHeld" => held = Some(activity.amount),
+            "DepositReturned" | "DepositForfeit" => held = None,
+            _ => {}
+        }
+    }
+    held
+}
+
+// Take the deposit of a sealed bid out of the bidder's credits until the auction settles,
+// before the commitment is taken. Returns the deposit held.
+pub async fn hold_deposit(
+    user: &mut User,
+    commitment: &BidCommitment,
+    auction: &Auction,
+) -> Result<f32, UserOperationError> {
+    let AuctionKind::SealedBid { deposit, .. } = auction.kind else {
+        return Err(UserOperationError::NotAnActivity);
+    };
+    if commitment.bidder != user.uid || commitment.auction_signature != auction.signature {
+        return Err(UserOperationError::InvalidSignature);
+    }
+    match validate_tx_integrity(
+        &commitment.signed_content(),
+        &user.uid,
+        commitment.signature.clone(),
+    )
+    .await
+    {
+        Ok(true) => {}
+        _ => return Err(UserOperationError::InvalidSignature),
+    }
+    if !deposit.is_finite() || deposit < 0.0 {
+        return Err(UserOperationError::InvalidAmount);
+    }
+    // a bidder commits once per auction, so is held once
+    if user.activity.iter().any(|activity| {
+        activity.activity_type == "DepositHeld" && activity.auction_signature == auction.signature
+    }) {
+        return Err(UserOperationError::AlreadyRecorded);
+    }
+    if user.credits < deposit {
+        return Err(UserOperationError::InsufficientCredits);
+    }
+
+    user.credits -= deposit;
+    user.activity.push(UserActivity {
+        activity_type: "DepositHeld".to_string(),
+        auction_signature: auction.signature.clone(),
+        amount: deposit,
+        signature: commitment.signature.clone(),
+    });
+    Ok(deposit)
+}
+
+// Give a held deposit back to a bidder that revealed, of a cancelled auction, or whose
+// commitment never made it into the auction. Returns the credits given back, none when
+// nothing is held.
+pub fn release_deposit(
+    user: &mut User,
+    auctions: &[Auction],
+    auction_signature: &str,
+    now: DateTime<Utc>,
+) -> Result<f32, UserOperationError> {
+    let Some(amount) = held_deposit(user, auction_signature) else {
+        if user.activity.iter().any(|activity| {
+            activity.auction_signature == auction_signature
+                && matches!(
+                    activity.activity_type.as_str(),
+                    "DepositReturned" | "DepositForfeit"
+                )
+        }) {
+            return Err(UserOperationError::AlreadyRecorded);
+        }
+        return Ok(0.0);
+    };
+    let auction = auctions
+        .iter()
+        .find(|auction| auction.signature == auction_signature)
+        .ok_or(UserOperationError::AuctionNotFound)?;
+    let committed = auction
+        .commitments
+        .iter()
+        .any(|committed| committed.bidder == user.uid);
+    if committed && !auction.cancelled {
+        if auction.settlement_time() > now {
+            return Err(UserOperationError::DepositStillHeld);
+        }
+        if settle(auction)
+            .forfeits
+            .iter()
+            .any(|forfeit| forfeit.bidder == user.uid)
+        {
+            return Err(UserOperationError::DepositStillHeld);
+        }
+    }
+
+    user.credits += amount;
+    user.activity.push(UserActivity {
+        activity_type: "DepositReturned".to_string(),
+        auction_signature: auction_signature.to_string(),
+        amount,
+        signature: String::new(),
+    });
+    Ok(amount)
+}
@@ -0,0 +1,172 @@
+use chrono::{DateTime, Duration, Utc};
+use k256::ecdsa::{signature::Verifier, Signature, VerifyingKey};
+use rand_core::{OsRng, RngCore};
+use sha256::digest;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+// Seconds a login challenge can be answered in
+pub const CHALLENGE_TTL: i64 = 60;
+// Seconds a session proves who the user is after they logged in
+pub const SESSION_TTL: i64 = 3600;
+// Challenges a user can have pending, the oldest is dropped for a new one
+pub const MAX_PENDING_CHALLENGES: usize = 4;
+// Challenges pending for all users together, new ones are refused until some expire
+pub const MAX_CHALLENGES: usize = 4096;
+
+pub type SharedSessions = Arc<Mutex<UserSessions>>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AuthError {
+    // the nonce was never issued for the user, already used or expired
+    UnknownChallenge,
+    InvalidSignature,
+    // the session is unknown or expired
+    UnknownSession,
+    // the session belongs to another user
+    WrongUser,
+    // too many challenges are pending on the node
+    Busy,
+}
+
+impl fmt::Display for AuthError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AuthError::UnknownChallenge => write!(f, "challenge unknown, used or expired"),
+            AuthError::InvalidSignature => write!(f, "challenge signature is not valid"),
+            AuthError::UnknownSession => write!(f, "session unknown or expired, log in again"),
+            AuthError::WrongUser => write!(f, "session belongs to another user"),
+            AuthError::Busy => write!(f, "too many logins pending, try again later"),
+        }
+    }
+}
+
+impl std::error::Error for AuthError {}
+
+// content the user signs to answer a challenge: sha256(nonce + user id + "login")
+pub fn login_content(user_id: &str, nonce: &str) -> String {
+    digest(nonce.to_string() + user_id + "login")
+}
+
+// user id and expiry of a challenge or a session
+#[derive(Debug, Clone)]
+struct Grant {
+    user_id: String,
+    expires_at: DateTime<Utc>,
+}
+
+// Challenges and sessions of a node, kept in memory: a restart logs everybody out
+#[derive(Debug, Default)]
+pub struct UserSessions {
+    challenges: BTreeMap<String, Grant>,
+    sessions: BTreeMap<String, Grant>,
+}
+
+fn random_token() -> String {
+    let mut token = [0u8; 32];
+    OsRng.fill_bytes(&mut token);
+    hex::encode(token)
+}
+
+fn valid_signature(content: &str, user_id: &str, signature: &str) -> bool {
+    let public_key = hex::decode(user_id)
+        .ok()
+        .and_then(|bytes| VerifyingKey::from_sec1_bytes(&bytes).ok());
+    let signature = hex::decode(signature)
+        .ok()
+        .and_then(|bytes| Signature::from_slice(&bytes).ok());
+    match (public_key, signature) {
+        (Some(public_key), Some(signature)) => {
+            public_key.verify(content.as_bytes(), &signature).is_ok()
+        }
+        _ => false,
+    }
+}
+
+impl UserSessions {
+    // a fresh nonce for `user_id`, with the time it must be answered by. Ids are public keys
+    // anybody can make up, so the challenges of all users together are capped too.
+    pub fn issue_challenge(
+        &mut self,
+        user_id: &str,
+        now: DateTime<Utc>,
+    ) -> Result<(String, DateTime<Utc>), AuthError> {
+        self.expire(now);
+        let mut pending: Vec<(DateTime<Utc>, String)> = self
+            .challenges
+            .iter()
+            .filter(|(_, grant)| grant.user_id == user_id)
+            .map(|(nonce, grant)| (grant.expires_at, nonce.clone()))
+            .collect();
+        pending.sort();
+        for (_, nonce) in pending.iter().rev().skip(MAX_PENDING_CHALLENGES - 1) {
+            self.challenges.remove(nonce);
+        }
+        if self.challenges.len() >= MAX_CHALLENGES {
+            return Err(AuthError::Busy);
+        }
+
+        let nonce = random_token();
+        let expires_at = now + Duration::seconds(CHALLENGE_TTL);
+        self.challenges.insert(
+            nonce.clone(),
+            Grant {
+                user_id: user_id.to_string(),
+                expires_at,
+            },
+        );
+        Ok((nonce, expires_at))
+    }
+
+    // Trade a signed challenge for a session. The nonce is spent whatever the outcome, so
+    // a signature can not be tried twice.
+    pub fn login(
+        &mut self,
+        user_id: &str,
+        nonce: &str,
+        signature: &str,
+        now: DateTime<Utc>,
+    ) -> Result<(String, DateTime<Utc>), AuthError> {
+        self.expire(now);
+        match self.challenges.remove(nonce) {
+            Some(challenge) if challenge.user_id == user_id => {}
+            _ => return Err(AuthError::UnknownChallenge),
+        }
+        if !valid_signature(&login_content(user_id, nonce), user_id, signature) {
+            return Err(AuthError::InvalidSignature);
+        }
+
+        let session = random_token();
+        let expires_at = now + Duration::seconds(SESSION_TTL);
+        self.sessions.insert(
+            session.clone(),
+            Grant {
+                user_id: user_id.to_string(),
+                expires_at,
+            },
+        );
+        Ok((session, expires_at))
+    }
+
+    // whether `session` proves the request comes from `user_id`
+    pub fn authorize(
+        &mut self,
+        session: &str,
+        user_id: &str,
+        now: DateTime<Utc>,
+    ) -> Result<(), AuthError> {
+        self.expire(now);
+        match self.sessions.get(session) {
+            Some(grant) if grant.user_id == user_id => Ok(()),
+            Some(_) => Err(AuthError::WrongUser),
+            None => Err(AuthError::UnknownSession),
+        }
+    }
+
+    fn expire(&mut self, now: DateTime<Utc>) {
+        self.challenges.retain(|_, grant| grant.expires_at > now);
+        self.sessions.retain(|_, grant| grant.expires_at > now);
+    }
+}
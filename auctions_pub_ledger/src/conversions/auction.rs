@@ -0,0 +1,726 @@
+use super::{required, timestamp, DecodeError};
+use crate::auction_app::auction::{
+    Auction, AuctionCancel, AuctionKind, AuctionMetadata, AuctionRules, Bid, BidCommitment,
+    BidIncrement, BidReveal, DutchAccept, Endpoint, Notification, Pricing, SoftClose, Subscription,
+    Transaction,
+};
+use crate::auction_app::auction_operation::client::{TransactionInfo, MAX_HOPS};
+use crate::auction_server::notification_outbox::SequencedNotification;
+use crate::auction_server::search::{AuctionQuery, SearchPage, SortKey, StateFilter};
+use crate::auction_server::settlement::Forfeit;
+use crate::auction_tx;
+use crate::auction_types as proto;
+use proto::auction_kind::{Dutch, English, Kind, SealedBid};
+use proto::bid_increment::Increment;
+
+impl From<Bid> for proto::Bid {
+    fn from(bid: Bid) -> Self {
+        proto::Bid {
+            bidder: bid.bidder,
+            amount: bid.amount,
+            signature: bid.signature,
+            auction_signature: bid.auction_signature,
+            timestamp: bid.timestamp.timestamp(),
+            salt: bid.salt,
+        }
+    }
+}
+
+impl TryFrom<proto::Bid> for Bid {
+    type Error = DecodeError;
+
+    fn try_from(bid: proto::Bid) -> Result<Self, Self::Error> {
+        Ok(Bid {
+            bidder: bid.bidder,
+            amount: bid.amount,
+            signature: bid.signature,
+            auction_signature: bid.auction_signature,
+            timestamp: timestamp(bid.timestamp, "bid.timestamp")?,
+            salt: bid.salt,
+        })
+    }
+}
+
+impl From<BidCommitment> for proto::BidCommitment {
+    fn from(commitment: BidCommitment) -> Self {
+        proto::BidCommitment {
+            bidder: commitment.bidder,
+            auction_signature: commitment.auction_signature,
+            commitment: commitment.commitment,
+            signature: commitment.signature,
+        }
+    }
+}
+
+impl From<proto::BidCommitment> for BidCommitment {
+    fn from(commitment: proto::BidCommitment) -> Self {
+        BidCommitment {
+            bidder: commitment.bidder,
+            auction_signature: commitment.auction_signature,
+            commitment: commitment.commitment,
+            signature: commitment.signature,
+        }
+    }
+}
+
+impl From<BidReveal> for proto::BidReveal {
+    fn from(reveal: BidReveal) -> Self {
+        proto::BidReveal {
+            bid: Some(reveal.bid.into()),
+            salt: reveal.salt,
+        }
+    }
+}
+
+impl TryFrom<proto::BidReveal> for BidReveal {
+    type Error = DecodeError;
+
+    fn try_from(reveal: proto::BidReveal) -> Result<Self, Self::Error> {
+        Ok(BidReveal {
+            bid: required(reveal.bid, "reveal.bid")?.try_into()?,
+            salt: reveal.salt,
+        })
+    }
+}
+
+impl From<DutchAccept> for proto::DutchAccept {
+    fn from(accept: DutchAccept) -> Self {
+        proto::DutchAccept {
+            bidder: accept.bidder,
+            auction_signature: accept.auction_signature,
+            timestamp: accept.timestamp.timestamp(),
+            signature: accept.signature,
+        }
+    }
+}
+
+impl TryFrom<proto::DutchAccept> for DutchAccept {
+    type Error = DecodeError;
+
+    fn try_from(accept: proto::DutchAccept) -> Result<Self, Self::Error> {
+        Ok(DutchAccept {
+            bidder: accept.bidder,
+            auction_signature: accept.auction_signature,
+            timestamp: timestamp(accept.timestamp, "accept.timestamp")?,
+            signature: accept.signature,
+        })
+    }
+}
+
+impl From<AuctionCancel> for proto::AuctionCancel {
+    fn from(cancel: AuctionCancel) -> Self {
+        proto::AuctionCancel {
+            seller: cancel.seller,
+            auction_signature: cancel.auction_signature,
+            signature: cancel.signature,
+        }
+    }
+}
+
+impl From<proto::AuctionCancel> for AuctionCancel {
+    fn from(cancel: proto::AuctionCancel) -> Self {
+        AuctionCancel {
+            seller: cancel.seller,
+            auction_signature: cancel.auction_signature,
+            signature: cancel.signature,
+        }
+    }
+}
+
+impl From<Subscription> for proto::Subscription {
+    fn from(subscription: Subscription) -> Self {
+        proto::Subscription {
+            subscriber: subscription.subscriber,
+            auction_signature: subscription.auction_signature,
+            subscribe: subscription.subscribe,
+            timestamp: subscription.timestamp.timestamp(),
+            signature: subscription.signature,
+        }
+    }
+}
+
+impl TryFrom<proto::Subscription> for Subscription {
+    type Error = DecodeError;
+
+    fn try_from(subscription: proto::Subscription) -> Result<Self, Self::Error> {
+        Ok(Subscription {
+            subscriber: subscription.subscriber,
+            auction_signature: subscription.auction_signature,
+            subscribe: subscription.subscribe,
+            timestamp: timestamp(subscription.timestamp, "subscription.timestamp")?,
+            signature: subscription.signature,
+        })
+    }
+}
+
+impl From<Endpoint> for proto::Endpoint {
+    fn from(endpoint: Endpoint) -> Self {
+        proto::Endpoint {
+            user_id: endpoint.user_id,
+            addr: endpoint.addr,
+            timestamp: endpoint.timestamp.timestamp(),
+            signature: endpoint.signature,
+        }
+    }
+}
+
+impl TryFrom<proto::Endpoint> for Endpoint {
+    type Error = DecodeError;
+
+    fn try_from(endpoint: proto::Endpoint) -> Result<Self, Self::Error> {
+        Ok(Endpoint {
+            user_id: endpoint.user_id,
+            addr: endpoint.addr,
+            timestamp: timestamp(endpoint.timestamp, "endpoint.timestamp")?,
+            signature: endpoint.signature,
+        })
+    }
+}
+
+impl From<BidIncrement> for proto::BidIncrement {
+    fn from(increment: BidIncrement) -> Self {
+        proto::BidIncrement {
+            increment: Some(match increment {
+                BidIncrement::Absolute(amount) => Increment::Absolute(amount),
+                BidIncrement::Percent(percent) => Increment::Percent(percent),
+            }),
+        }
+    }
+}
+
+impl TryFrom<proto::BidIncrement> for BidIncrement {
+    type Error = DecodeError;
+
+    fn try_from(increment: proto::BidIncrement) -> Result<Self, Self::Error> {
+        Ok(
+            match required(increment.increment, "rules.min_increment")? {
+                Increment::Absolute(amount) => BidIncrement::Absolute(amount),
+                Increment::Percent(percent) => BidIncrement::Percent(percent),
+            },
+        )
+    }
+}
+
+impl From<Pricing> for proto::Pricing {
+    fn from(pricing: Pricing) -> Self {
+        match pricing {
+            Pricing::FirstPrice => proto::Pricing::FirstPrice,
+            Pricing::SecondPrice => proto::Pricing::SecondPrice,
+            Pricing::Uniform => proto::Pricing::Uniform,
+        }
+    }
+}
+
+impl From<proto::Pricing> for Pricing {
+    fn from(pricing: proto::Pricing) -> Self {
+        match pricing {
+            proto::Pricing::FirstPrice => Pricing::FirstPrice,
+            proto::Pricing::SecondPrice => Pricing::SecondPrice,
+            proto::Pricing::Uniform => Pricing::Uniform,
+        }
+    }
+}
+
+impl From<SoftClose> for proto::SoftClose {
+    fn from(soft_close: SoftClose) -> Self {
+        proto::SoftClose {
+            window_seconds: soft_close.window_seconds,
+            extension_seconds: soft_close.extension_seconds,
+            max_extension_seconds: soft_close.max_extension_seconds,
+        }
+    }
+}
+
+impl From<proto::SoftClose> for SoftClose {
+    fn from(soft_close: proto::SoftClose) -> Self {
+        SoftClose {
+            window_seconds: soft_close.window_seconds,
+            extension_seconds: soft_close.extension_seconds,
+            max_extension_seconds: soft_close.max_extension_seconds,
+        }
+    }
+}
+
+impl From<AuctionRules> for proto::AuctionRules {
+    fn from(rules: AuctionRules) -> Self {
+        proto::AuctionRules {
+            reserve_price: rules.reserve_price,
+            min_increment: Some(rules.min_increment.into()),
+            pricing: proto::Pricing::from(rules.pricing).into(),
+            soft_close: rules.soft_close.map(Into::into),
+            buy_now_price: rules.buy_now_price,
+        }
+    }
+}
+
+impl TryFrom<proto::AuctionRules> for AuctionRules {
+    type Error = DecodeError;
+
+    fn try_from(rules: proto::AuctionRules) -> Result<Self, Self::Error> {
+        Ok(AuctionRules {
+            reserve_price: rules.reserve_price,
+            min_increment: required(rules.min_increment, "rules.min_increment")?.try_into()?,
+            pricing: proto::Pricing::try_from(rules.pricing)
+                .map_err(|_| DecodeError::UnknownValue("rules.pricing"))?
+                .into(),
+            soft_close: rules.soft_close.map(Into::into),
+            buy_now_price: rules.buy_now_price,
+        })
+    }
+}
+
+impl From<AuctionMetadata> for proto::AuctionMetadata {
+    fn from(metadata: AuctionMetadata) -> Self {
+        proto::AuctionMetadata {
+            description: metadata.description,
+            category: metadata.category,
+            tags: metadata.tags,
+            attributes: metadata.attributes.into_iter().collect(),
+            images: metadata.images,
+        }
+    }
+}
+
+impl From<proto::AuctionMetadata> for AuctionMetadata {
+    fn from(metadata: proto::AuctionMetadata) -> Self {
+        AuctionMetadata {
+            description: metadata.description,
+            category: metadata.category,
+            tags: metadata.tags,
+            attributes: metadata.attributes.into_iter().collect(),
+            images: metadata.images,
+        }
+    }
+}
+
+impl From<AuctionKind> for proto::AuctionKind {
+    fn from(kind: AuctionKind) -> Self {
+        proto::AuctionKind {
+            kind: Some(match kind {
+                AuctionKind::English => Kind::English(English {}),
+                AuctionKind::SealedBid {
+                    deposit,
+                    reveal_seconds,
+                } => Kind::SealedBid(SealedBid {
+                    deposit,
+                    reveal_seconds,
+                }),
+                AuctionKind::Dutch {
+                    start_price,
+                    decrement,
+                    decrement_seconds,
+                    floor_price,
+                } => Kind::Dutch(Dutch {
+                    start_price,
+                    decrement,
+                    decrement_seconds,
+                    floor_price,
+                }),
+            }),
+        }
+    }
+}
+
+// an unset kind is an English auction, as in the JSON records
+impl From<proto::AuctionKind> for AuctionKind {
+    fn from(kind: proto::AuctionKind) -> Self {
+        match kind.kind {
+            None | Some(Kind::English(_)) => AuctionKind::English,
+            Some(Kind::SealedBid(sealed_bid)) => AuctionKind::SealedBid {
+                deposit: sealed_bid.deposit,
+                reveal_seconds: sealed_bid.reveal_seconds,
+            },
+            Some(Kind::Dutch(dutch)) => AuctionKind::Dutch {
+                start_price: dutch.start_price,
+                decrement: dutch.decrement,
+                decrement_seconds: dutch.decrement_seconds,
+                floor_price: dutch.floor_price,
+            },
+        }
+    }
+}
+
+impl From<Auction> for proto::Auction {
+    fn from(auction: Auction) -> Self {
+        proto::Auction {
+            item_name: auction.item_name,
+            start_time: auction.start_time.timestamp(),
+            end_time: auction.end_time.timestamp(),
+            starting_bid: auction.starting_bid,
+            quantity: auction.quantity,
+            lot: auction.lot,
+            metadata: Some(auction.metadata.into()),
+            rules: Some(auction.rules.into()),
+            kind: Some(auction.kind.into()),
+            bids: auction.bids.into_iter().map(Into::into).collect(),
+            commitments: auction.commitments.into_iter().map(Into::into).collect(),
+            active: auction.active,
+            cancelled: auction.cancelled,
+            cancellation: auction.cancellation.map(Into::into),
+            opening_notified: auction.opening_notified,
+            user_id: auction.user_id,
+            signature: auction.signature,
+            subscribers: auction.subscribers,
+            subscriptions: auction.subscriptions.into_iter().map(Into::into).collect(),
+            endpoints: auction.endpoints.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl TryFrom<proto::Auction> for Auction {
+    type Error = DecodeError;
+
+    fn try_from(auction: proto::Auction) -> Result<Self, Self::Error> {
+        Ok(Auction {
+            item_name: auction.item_name,
+            start_time: timestamp(auction.start_time, "auction.start_time")?,
+            end_time: timestamp(auction.end_time, "auction.end_time")?,
+            starting_bid: auction.starting_bid,
+            quantity: auction.quantity.max(1),
+            lot: auction.lot,
+            metadata: auction.metadata.map(Into::into).unwrap_or_default(),
+            rules: match auction.rules {
+                Some(rules) => rules.try_into()?,
+                None => AuctionRules::default(),
+            },
+            kind: auction.kind.map(Into::into).unwrap_or_default(),
+            bids: auction
+                .bids
+                .into_iter()
+                .map(TryInto::try_into)
+                .collect::<Result<_, _>>()?,
+            commitments: auction.commitments.into_iter().map(Into::into).collect(),
+            active: auction.active,
+            cancelled: auction.cancelled,
+            cancellation: auction.cancellation.map(Into::into),
+            opening_notified: auction.opening_notified,
+            user_id: auction.user_id,
+            signature: auction.signature,
+            subscribers: auction.subscribers,
+            subscriptions: auction
+                .subscriptions
+                .into_iter()
+                .map(TryInto::try_into)
+                .collect::<Result<_, _>>()?,
+            endpoints: auction
+                .endpoints
+                .into_iter()
+                .map(TryInto::try_into)
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+impl From<Transaction> for proto::Transaction {
+    fn from(transaction: Transaction) -> Self {
+        use proto::transaction::Transaction as Tx;
+        proto::Transaction {
+            transaction: Some(match transaction {
+                Transaction::Auction(auction) => Tx::Auction((*auction).into()),
+                Transaction::Bid(bid) => Tx::Bid(bid.into()),
+                Transaction::Commit(commitment) => Tx::Commit(commitment.into()),
+                Transaction::Reveal(reveal) => Tx::Reveal(reveal.into()),
+                Transaction::Accept(accept) => Tx::Accept(accept.into()),
+                Transaction::Cancel(cancel) => Tx::Cancel(cancel.into()),
+                Transaction::Subscription(subscription) => Tx::Subscription(subscription.into()),
+            }),
+        }
+    }
+}
+
+impl TryFrom<proto::Transaction> for Transaction {
+    type Error = DecodeError;
+
+    fn try_from(transaction: proto::Transaction) -> Result<Self, Self::Error> {
+        use proto::transaction::Transaction as Tx;
+        Ok(match required(transaction.transaction, "transaction")? {
+            Tx::Auction(auction) => Transaction::Auction(Box::new(auction.try_into()?)),
+            Tx::Bid(bid) => Transaction::Bid(bid.try_into()?),
+            Tx::Commit(commitment) => Transaction::Commit(commitment.into()),
+            Tx::Reveal(reveal) => Transaction::Reveal(reveal.try_into()?),
+            Tx::Accept(accept) => Transaction::Accept(accept.try_into()?),
+            Tx::Cancel(cancel) => Transaction::Cancel(cancel.into()),
+            Tx::Subscription(subscription) => Transaction::Subscription(subscription.try_into()?),
+        })
+    }
+}
+
+impl From<TransactionInfo> for auction_tx::SubmitTransactionRequest {
+    fn from(transaction_info: TransactionInfo) -> Self {
+        auction_tx::SubmitTransactionRequest {
+            subscriber_addr: transaction_info.subscriber_addrs,
+            tx: Some(transaction_info.transaction.into()),
+            ttl: Some(transaction_info.ttl.into()),
+            visited: transaction_info.visited,
+        }
+    }
+}
+
+// an unset ttl comes from a client, hop limits above ours are cut down to it
+impl TryFrom<auction_tx::SubmitTransactionRequest> for TransactionInfo {
+    type Error = DecodeError;
+
+    fn try_from(request: auction_tx::SubmitTransactionRequest) -> Result<Self, Self::Error> {
+        Ok(TransactionInfo {
+            subscriber_addrs: request.subscriber_addr,
+            transaction: required(request.tx, "tx")?.try_into()?,
+            ttl: request
+                .ttl
+                .map_or(MAX_HOPS, |ttl| ttl.min(MAX_HOPS.into()) as u8),
+            visited: request.visited,
+        })
+    }
+}
+
+impl From<Notification> for proto::Notification {
+    fn from(notification: Notification) -> Self {
+        use proto::notification::{
+            BiddingOpen, EndedWithoutBids, Extended, Lost, Notification as Event, Outbid,
+            SellerPayout, Won,
+        };
+        proto::Notification {
+            notification: Some(match notification {
+                Notification::Bid(bid) => Event::Bid(bid.into()),
+                Notification::BiddingOpen {
+                    auction_signature,
+                    item_name,
+                } => Event::BiddingOpen(BiddingOpen {
+                    auction_signature,
+                    item_name,
+                }),
+                Notification::Outbid {
+                    auction_signature,
+                    item_name,
+                    bid,
+                } => Event::Outbid(Outbid {
+                    auction_signature,
+                    item_name,
+                    bid: Some(bid.into()),
+                }),
+                Notification::Extended {
+                    auction_signature,
+                    item_name,
+                    closing_time,
+                } => Event::Extended(Extended {
+                    auction_signature,
+                    item_name,
+                    closing_time: closing_time.timestamp(),
+                }),
+                Notification::Won {
+                    auction_signature,
+                    item_name,
+                    price,
+                } => Event::Won(Won {
+                    auction_signature,
+                    item_name,
+                    price,
+                }),
+                Notification::Lost {
+                    auction_signature,
+                    item_name,
+                } => Event::Lost(Lost {
+                    auction_signature,
+                    item_name,
+                }),
+                Notification::EndedWithoutBids {
+                    auction_signature,
+                    item_name,
+                } => Event::EndedWithoutBids(EndedWithoutBids {
+                    auction_signature,
+                    item_name,
+                }),
+                Notification::SellerPayout {
+                    auction_signature,
+                    item_name,
+                    amount,
+                    units_sold,
+                } => Event::SellerPayout(SellerPayout {
+                    auction_signature,
+                    item_name,
+                    amount,
+                    units_sold,
+                }),
+            }),
+        }
+    }
+}
+
+impl TryFrom<proto::Notification> for Notification {
+    type Error = DecodeError;
+
+    fn try_from(notification: proto::Notification) -> Result<Self, Self::Error> {
+        use proto::notification::Notification as Event;
+        Ok(match required(notification.notification, "notification")? {
+            Event::Bid(bid) => Notification::Bid(bid.try_into()?),
+            Event::BiddingOpen(opening) => Notification::BiddingOpen {
+                auction_signature: opening.auction_signature,
+                item_name: opening.item_name,
+            },
+            Event::Outbid(outbid) => Notification::Outbid {
+                auction_signature: outbid.auction_signature,
+                item_name: outbid.item_name,
+                bid: required(outbid.bid, "outbid.bid")?.try_into()?,
+            },
+            Event::Extended(extended) => Notification::Extended {
+                auction_signature: extended.auction_signature,
+                item_name: extended.item_name,
+                closing_time: timestamp(extended.closing_time, "extended.closing_time")?,
+            },
+            Event::Won(won) => Notification::Won {
+                auction_signature: won.auction_signature,
+                item_name: won.item_name,
+                price: won.price,
+            },
+            Event::Lost(lost) => Notification::Lost {
+                auction_signature: lost.auction_signature,
+                item_name: lost.item_name,
+            },
+            Event::EndedWithoutBids(ended) => Notification::EndedWithoutBids {
+                auction_signature: ended.auction_signature,
+                item_name: ended.item_name,
+            },
+            Event::SellerPayout(payout) => Notification::SellerPayout {
+                auction_signature: payout.auction_signature,
+                item_name: payout.item_name,
+                amount: payout.amount,
+                units_sold: payout.units_sold,
+            },
+        })
+    }
+}
+
+impl From<Forfeit> for proto::Forfeit {
+    fn from(forfeit: Forfeit) -> Self {
+        proto::Forfeit {
+            bidder: forfeit.bidder,
+            auction_signature: forfeit.auction_signature,
+            amount: forfeit.amount,
+        }
+    }
+}
+
+impl From<proto::Forfeit> for Forfeit {
+    fn from(forfeit: proto::Forfeit) -> Self {
+        Forfeit {
+            bidder: forfeit.bidder,
+            auction_signature: forfeit.auction_signature,
+            amount: forfeit.amount,
+        }
+    }
+}
+
+impl From<AuctionQuery> for auction_tx::AuctionQuery {
+    fn from(query: AuctionQuery) -> Self {
+        auction_tx::AuctionQuery {
+            state: match query.state {
+                StateFilter::Any => auction_tx::StateFilter::Any,
+                StateFilter::Active => auction_tx::StateFilter::Active,
+                StateFilter::Closed => auction_tx::StateFilter::Closed,
+            }
+            .into(),
+            seller: query.seller,
+            category: query.category,
+            min_price: query.min_price,
+            max_price: query.max_price,
+            ending_before: query
+                .ending_before
+                .map(|ending_before| ending_before.timestamp()),
+            sort: match query.sort {
+                SortKey::EndingSoonest => auction_tx::SortKey::EndingSoonest,
+                SortKey::PriceLowest => auction_tx::SortKey::PriceLowest,
+                SortKey::PriceHighest => auction_tx::SortKey::PriceHighest,
+                SortKey::Newest => auction_tx::SortKey::Newest,
+            }
+            .into(),
+            page: query.page as u64,
+            page_size: query.page_size as u64,
+            watcher: query.watcher,
+        }
+    }
+}
+
+impl TryFrom<auction_tx::AuctionQuery> for AuctionQuery {
+    type Error = DecodeError;
+
+    fn try_from(query: auction_tx::AuctionQuery) -> Result<Self, Self::Error> {
+        let state = auction_tx::StateFilter::try_from(query.state)
+            .map_err(|_| DecodeError::UnknownValue("query.state"))?;
+        let sort = auction_tx::SortKey::try_from(query.sort)
+            .map_err(|_| DecodeError::UnknownValue("query.sort"))?;
+        Ok(AuctionQuery {
+            state: match state {
+                auction_tx::StateFilter::Any => StateFilter::Any,
+                auction_tx::StateFilter::Active => StateFilter::Active,
+                auction_tx::StateFilter::Closed => StateFilter::Closed,
+            },
+            seller: query.seller,
+            category: query.category,
+            min_price: query.min_price,
+            max_price: query.max_price,
+            ending_before: query
+                .ending_before
+                .map(|seconds| timestamp(seconds, "query.ending_before"))
+                .transpose()?,
+            sort: match sort {
+                auction_tx::SortKey::EndingSoonest => SortKey::EndingSoonest,
+                auction_tx::SortKey::PriceLowest => SortKey::PriceLowest,
+                auction_tx::SortKey::PriceHighest => SortKey::PriceHighest,
+                auction_tx::SortKey::Newest => SortKey::Newest,
+            },
+            page: query.page as usize,
+            page_size: match query.page_size {
+                0 => AuctionQuery::default().page_size,
+                page_size => page_size as usize,
+            },
+            watcher: query.watcher,
+        })
+    }
+}
+
+impl From<SearchPage> for auction_tx::SearchPage {
+    fn from(page: SearchPage) -> Self {
+        auction_tx::SearchPage {
+            auctions: page.auctions.into_iter().map(Into::into).collect(),
+            total: page.total as u64,
+            page: page.page as u64,
+            page_size: page.page_size as u64,
+        }
+    }
+}
+
+impl TryFrom<auction_tx::SearchPage> for SearchPage {
+    type Error = DecodeError;
+
+    fn try_from(page: auction_tx::SearchPage) -> Result<Self, Self::Error> {
+        Ok(SearchPage {
+            auctions: page
+                .auctions
+                .into_iter()
+                .map(TryInto::try_into)
+                .collect::<Result<_, _>>()?,
+            total: page.total as usize,
+            page: page.page as usize,
+            page_size: page.page_size as usize,
+        })
+    }
+}
+
+impl From<SequencedNotification> for auction_tx::SequencedNotification {
+    fn from(sequenced: SequencedNotification) -> Self {
+        auction_tx::SequencedNotification {
+            sequence: sequenced.sequence,
+            event: Some(sequenced.notification.into()),
+        }
+    }
+}
+
+impl TryFrom<auction_tx::SequencedNotification> for SequencedNotification {
+    type Error = DecodeError;
+
+    fn try_from(sequenced: auction_tx::SequencedNotification) -> Result<Self, Self::Error> {
+        Ok(SequencedNotification {
+            sequence: sequenced.sequence,
+            notification: required(sequenced.event, "event")?.try_into()?,
+        })
+    }
+}
@@ -0,0 +1,81 @@
+use crate::auction_server::blockchain::{Block, Blockchain};
+use crate::auction_server::blockchain_pos::{Puzzle, PuzzleSet};
+use crate::auction_types as proto;
+
+impl From<Block> for proto::Block {
+    fn from(block: Block) -> Self {
+        proto::Block {
+            index: block.index,
+            prev_hash: block.prev_hash,
+            nounce: block.nounce,
+            timestamp: block.timestamp,
+            hash: block.hash,
+            tx: block.tx,
+        }
+    }
+}
+
+impl From<proto::Block> for Block {
+    fn from(block: proto::Block) -> Self {
+        Block {
+            index: block.index,
+            prev_hash: block.prev_hash,
+            nounce: block.nounce,
+            timestamp: block.timestamp,
+            hash: block.hash,
+            tx: block.tx,
+        }
+    }
+}
+
+impl From<Blockchain> for proto::Blockchain {
+    fn from(blockchain: Blockchain) -> Self {
+        proto::Blockchain {
+            blocks: blockchain.blocks.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<proto::Blockchain> for Blockchain {
+    fn from(blockchain: proto::Blockchain) -> Self {
+        Blockchain {
+            blocks: blockchain.blocks.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<Puzzle> for proto::Puzzle {
+    fn from(puzzle: Puzzle) -> Self {
+        proto::Puzzle {
+            solution: puzzle.solution,
+            nounce: puzzle.nounce,
+            code: puzzle.code,
+        }
+    }
+}
+
+impl From<proto::Puzzle> for Puzzle {
+    fn from(puzzle: proto::Puzzle) -> Self {
+        Puzzle {
+            solution: puzzle.solution,
+            nounce: puzzle.nounce,
+            code: puzzle.code,
+        }
+    }
+}
+
+impl From<PuzzleSet> for proto::PuzzleSet {
+    fn from(puzzles: PuzzleSet) -> Self {
+        proto::PuzzleSet {
+            puzzle_list: puzzles.puzzle_list.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<proto::PuzzleSet> for PuzzleSet {
+    fn from(puzzles: proto::PuzzleSet) -> Self {
+        PuzzleSet {
+            puzzle_list: puzzles.puzzle_list.into_iter().map(Into::into).collect(),
+        }
+    }
+}
@@ -0,0 +1,94 @@
+use super::{required, timestamp, DecodeError};
+use crate::auction_server::auction_events::{AuctionEvent, EventFilter, EventKind};
+use crate::auction_server::settlement::Award;
+use crate::auction_tx;
+use crate::auction_types as proto;
+use auction_tx::auction_event::{Closed, Event, Extended, Settled};
+
+impl From<Award> for proto::Award {
+    fn from(award: Award) -> Self {
+        proto::Award {
+            bid: Some(award.bid.into()),
+            price: award.price,
+        }
+    }
+}
+
+impl TryFrom<proto::Award> for Award {
+    type Error = DecodeError;
+
+    fn try_from(award: proto::Award) -> Result<Self, Self::Error> {
+        Ok(Award {
+            bid: required(award.bid, "award.bid")?.try_into()?,
+            price: award.price,
+        })
+    }
+}
+
+impl From<AuctionEvent> for auction_tx::AuctionEvent {
+    fn from(event: AuctionEvent) -> Self {
+        auction_tx::AuctionEvent {
+            auction_signature: event.auction_signature,
+            seller: event.seller,
+            category: event.category,
+            event: Some(match event.kind {
+                EventKind::NewBid(bid) => Event::NewBid(bid.into()),
+                EventKind::Extended { closing_time } => Event::Extended(Extended {
+                    closing_time: closing_time.timestamp(),
+                }),
+                EventKind::Closed { cancelled } => Event::Closed(Closed { cancelled }),
+                EventKind::Settled { awards } => Event::Settled(Settled {
+                    awards: awards.into_iter().map(Into::into).collect(),
+                }),
+            }),
+        }
+    }
+}
+
+impl TryFrom<auction_tx::AuctionEvent> for AuctionEvent {
+    type Error = DecodeError;
+
+    fn try_from(event: auction_tx::AuctionEvent) -> Result<Self, Self::Error> {
+        Ok(AuctionEvent {
+            auction_signature: event.auction_signature,
+            seller: event.seller,
+            category: event.category,
+            kind: match required(event.event, "event")? {
+                Event::NewBid(bid) => EventKind::NewBid(bid.try_into()?),
+                Event::Extended(extended) => EventKind::Extended {
+                    closing_time: timestamp(extended.closing_time, "extended.closing_time")?,
+                },
+                Event::Closed(closed) => EventKind::Closed {
+                    cancelled: closed.cancelled,
+                },
+                Event::Settled(settled) => EventKind::Settled {
+                    awards: settled
+                        .awards
+                        .into_iter()
+                        .map(TryInto::try_into)
+                        .collect::<Result<_, _>>()?,
+                },
+            },
+        })
+    }
+}
+
+impl From<EventFilter> for auction_tx::SubscribeAuctionEventsRequest {
+    fn from(filter: EventFilter) -> Self {
+        auction_tx::SubscribeAuctionEventsRequest {
+            auction_signatures: filter.auction_signatures,
+            seller: filter.seller,
+            category: filter.category,
+        }
+    }
+}
+
+impl From<auction_tx::SubscribeAuctionEventsRequest> for EventFilter {
+    fn from(request: auction_tx::SubscribeAuctionEventsRequest) -> Self {
+        EventFilter {
+            auction_signatures: request.auction_signatures,
+            seller: request.seller,
+            category: request.category,
+        }
+    }
+}
@@ -0,0 +1,47 @@
+// Conversions between the Rust structs and the protobuf messages of the gRPC services.
+// Encoding can not fail. Decoding checks what protobuf can not express (required
+// messages, valid timestamps, known enum values) so bad payloads are refused before they
+// reach the handlers.
+pub mod auction;
+pub mod blockchain;
+pub mod events;
+pub mod user;
+
+use chrono::{DateTime, Utc};
+use std::fmt;
+use tonic::Status;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DecodeError {
+    MissingField(&'static str),
+    InvalidTimestamp(&'static str),
+    UnknownValue(&'static str),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DecodeError::MissingField(field) => write!(f, "missing field `{}`", field),
+            DecodeError::InvalidTimestamp(field) => {
+                write!(f, "field `{}` is not a valid timestamp", field)
+            }
+            DecodeError::UnknownValue(field) => write!(f, "unknown value for field `{}`", field),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+impl From<DecodeError> for Status {
+    fn from(error: DecodeError) -> Self {
+        Status::invalid_argument(error.to_string())
+    }
+}
+
+pub(crate) fn required<T>(value: Option<T>, field: &'static str) -> Result<T, DecodeError> {
+    value.ok_or(DecodeError::MissingField(field))
+}
+
+pub(crate) fn timestamp(seconds: i64, field: &'static str) -> Result<DateTime<Utc>, DecodeError> {
+    DateTime::from_timestamp(seconds, 0).ok_or(DecodeError::InvalidTimestamp(field))
+}
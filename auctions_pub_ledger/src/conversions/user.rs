@@ -0,0 +1,99 @@
+use crate::auction_app::user::{Deposit, User, UserActivity, UserOperation};
+use crate::auction_types as proto;
+use crate::conversions::{required, timestamp, DecodeError};
+
+impl From<UserActivity> for proto::UserActivity {
+    fn from(activity: UserActivity) -> Self {
+        proto::UserActivity {
+            activity_type: activity.activity_type,
+            auction_signature: activity.auction_signature,
+            amount: activity.amount,
+            signature: activity.signature,
+        }
+    }
+}
+
+impl From<proto::UserActivity> for UserActivity {
+    fn from(activity: proto::UserActivity) -> Self {
+        UserActivity {
+            activity_type: activity.activity_type,
+            auction_signature: activity.auction_signature,
+            amount: activity.amount,
+            signature: activity.signature,
+        }
+    }
+}
+
+impl From<User> for proto::User {
+    fn from(user: User) -> Self {
+        proto::User {
+            uid: user.uid,
+            user_name: user.user_name,
+            credits: user.credits,
+            auctions_winner: user.auctions_winner,
+            activity: user.activity.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<proto::User> for User {
+    fn from(user: proto::User) -> Self {
+        User {
+            uid: user.uid,
+            user_name: user.user_name,
+            credits: user.credits,
+            auctions_winner: user.auctions_winner,
+            activity: user.activity.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<Deposit> for proto::Deposit {
+    fn from(deposit: Deposit) -> Self {
+        proto::Deposit {
+            user_id: deposit.user_id,
+            amount: deposit.amount,
+            timestamp: deposit.timestamp.timestamp(),
+            signature: deposit.signature,
+        }
+    }
+}
+
+impl TryFrom<proto::Deposit> for Deposit {
+    type Error = DecodeError;
+
+    fn try_from(deposit: proto::Deposit) -> Result<Self, Self::Error> {
+        Ok(Deposit {
+            user_id: deposit.user_id,
+            amount: deposit.amount,
+            timestamp: timestamp(deposit.timestamp, "deposit.timestamp")?,
+            signature: deposit.signature,
+        })
+    }
+}
+
+impl From<UserOperation> for proto::UserOperation {
+    fn from(operation: UserOperation) -> Self {
+        use proto::user_operation::Operation;
+        proto::UserOperation {
+            operation: Some(match operation {
+                UserOperation::Deposit(deposit) => Operation::Deposit(deposit.into()),
+                UserOperation::Activity(transaction) => Operation::Activity((*transaction).into()),
+            }),
+        }
+    }
+}
+
+impl TryFrom<proto::UserOperation> for UserOperation {
+    type Error = DecodeError;
+
+    fn try_from(operation: proto::UserOperation) -> Result<Self, Self::Error> {
+        use proto::user_operation::Operation;
+        Ok(match required(operation.operation, "operation")? {
+            Operation::Deposit(deposit) => UserOperation::Deposit(deposit.try_into()?),
+            Operation::Activity(transaction) => {
+                UserOperation::Activity(Box::new(transaction.try_into()?))
+            }
+        })
+    }
+}
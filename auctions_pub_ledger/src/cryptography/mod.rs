@@ -1 +1,2 @@
 pub mod ecdsa_keys;
+pub mod wallet;
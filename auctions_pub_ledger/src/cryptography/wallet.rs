@@ -0,0 +1,344 @@
+// Keystore of the users' signing keys. Every private key is encrypted with ChaCha20-Poly1305
+// under a key derived from the account passphrase with Argon2id, the public key (the user
+// id) is authenticated with it so entries can not be swapped between accounts.
+use crate::cryptography::ecdsa_keys::generate_ecdsa_keypair;
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use k256::ecdsa::SigningKey;
+use rand_core::{OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use zeroize::Zeroizing;
+
+// Wallet of the auction client, in the working directory
+pub const WALLET_FILE: &str = "wallet.json";
+const WALLET_VERSION: u32 = 1;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+#[derive(Debug)]
+pub enum WalletError {
+    Io(std::io::Error),
+    Format(serde_json::Error),
+    UnsupportedVersion(u32),
+    UnknownAccount(String),
+    DuplicateAccount(String),
+    // the passphrase is wrong or the entry was tampered with
+    WrongPassphrase,
+    InvalidKey,
+    Kdf(argon2::Error),
+}
+
+impl fmt::Display for WalletError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WalletError::Io(error) => write!(f, "{}", error),
+            WalletError::Format(error) => write!(f, "malformed wallet: {}", error),
+            WalletError::UnsupportedVersion(version) => {
+                write!(f, "wallet version {} is not supported", version)
+            }
+            WalletError::UnknownAccount(name) => write!(f, "no account named {}", name),
+            WalletError::DuplicateAccount(name) => {
+                write!(f, "an account named {} already exists", name)
+            }
+            WalletError::WrongPassphrase => write!(f, "wrong passphrase"),
+            WalletError::InvalidKey => write!(f, "not a valid secp256k1 private key"),
+            WalletError::Kdf(error) => write!(f, "key derivation failed: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for WalletError {}
+
+impl From<std::io::Error> for WalletError {
+    fn from(error: std::io::Error) -> Self {
+        WalletError::Io(error)
+    }
+}
+
+impl From<serde_json::Error> for WalletError {
+    fn from(error: serde_json::Error) -> Self {
+        WalletError::Format(error)
+    }
+}
+
+impl From<argon2::Error> for WalletError {
+    fn from(error: argon2::Error) -> Self {
+        WalletError::Kdf(error)
+    }
+}
+
+// Argon2id cost, kept with every account so it can be raised for new ones
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct KdfParams {
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+impl Default for KdfParams {
+    fn default() -> Self {
+        KdfParams {
+            memory_kib: 19 * 1024,
+            iterations: 2,
+            parallelism: 1,
+        }
+    }
+}
+
+// An encrypted signing key, also the format accounts are exported in
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WalletAccount {
+    pub name: String,
+    pub uid: String,
+    pub kdf: KdfParams,
+    pub salt: String,
+    pub nonce: String,
+    pub ciphertext: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Wallet {
+    pub version: u32,
+    pub accounts: Vec<WalletAccount>,
+    // cost of the key derivation for accounts added from now on
+    #[serde(skip)]
+    pub kdf: KdfParams,
+}
+
+impl Default for Wallet {
+    fn default() -> Self {
+        Wallet::new()
+    }
+}
+
+// hex encoded compressed public key, the id of the user owning the key
+pub fn user_id(signing_key: &SigningKey) -> String {
+    hex::encode(signing_key.verifying_key().to_sec1_bytes())
+}
+
+fn derive_key(
+    passphrase: &str,
+    salt: &[u8],
+    kdf: &KdfParams,
+) -> Result<Zeroizing<[u8; 32]>, WalletError> {
+    let params = Params::new(kdf.memory_kib, kdf.iterations, kdf.parallelism, Some(32))?;
+    let mut key = Zeroizing::new([0u8; 32]);
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params).hash_password_into(
+        passphrase.as_bytes(),
+        salt,
+        key.as_mut(),
+    )?;
+    Ok(key)
+}
+
+impl WalletAccount {
+    fn seal(
+        name: &str,
+        signing_key: &SigningKey,
+        passphrase: &str,
+        kdf: KdfParams,
+    ) -> Result<Self, WalletError> {
+        let mut salt = [0u8; SALT_LEN];
+        let mut nonce = [0u8; NONCE_LEN];
+        OsRng.fill_bytes(&mut salt);
+        OsRng.fill_bytes(&mut nonce);
+
+        let uid = user_id(signing_key);
+        let key = derive_key(passphrase, &salt, &kdf)?;
+        let secret = Zeroizing::new(signing_key.to_bytes());
+        let ciphertext = ChaCha20Poly1305::new(&Key::from(*key))
+            .encrypt(
+                &Nonce::from(nonce),
+                Payload {
+                    msg: &secret[..],
+                    aad: uid.as_bytes(),
+                },
+            )
+            .map_err(|_| WalletError::InvalidKey)?;
+
+        Ok(WalletAccount {
+            name: name.to_string(),
+            uid,
+            kdf,
+            salt: hex::encode(salt),
+            nonce: hex::encode(nonce),
+            ciphertext: hex::encode(ciphertext),
+        })
+    }
+
+    // decrypt the signing key, checked against the account's user id
+    pub fn unlock(&self, passphrase: &str) -> Result<SigningKey, WalletError> {
+        let salt = hex::decode(&self.salt).map_err(|_| WalletError::WrongPassphrase)?;
+        let nonce: [u8; NONCE_LEN] = hex::decode(&self.nonce)
+            .ok()
+            .and_then(|nonce| nonce.try_into().ok())
+            .ok_or(WalletError::WrongPassphrase)?;
+        let ciphertext = hex::decode(&self.ciphertext).map_err(|_| WalletError::WrongPassphrase)?;
+
+        let key = derive_key(passphrase, &salt, &self.kdf)?;
+        let secret = Zeroizing::new(
+            ChaCha20Poly1305::new(&Key::from(*key))
+                .decrypt(
+                    &Nonce::from(nonce),
+                    Payload {
+                        msg: &ciphertext,
+                        aad: self.uid.as_bytes(),
+                    },
+                )
+                .map_err(|_| WalletError::WrongPassphrase)?,
+        );
+        let signing_key = SigningKey::from_slice(&secret).map_err(|_| WalletError::InvalidKey)?;
+        if user_id(&signing_key) != self.uid {
+            return Err(WalletError::InvalidKey);
+        }
+        Ok(signing_key)
+    }
+}
+
+impl Wallet {
+    pub fn new() -> Self {
+        Wallet {
+            version: WALLET_VERSION,
+            accounts: Vec::new(),
+            kdf: KdfParams::default(),
+        }
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, WalletError> {
+        let wallet: Wallet = serde_json::from_str(&fs::read_to_string(path)?)?;
+        if wallet.version != WALLET_VERSION {
+            return Err(WalletError::UnsupportedVersion(wallet.version));
+        }
+        Ok(wallet)
+    }
+
+    // the wallet at `path`, empty when there is none yet
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, WalletError> {
+        if path.as_ref().exists() {
+            Wallet::load(path)
+        } else {
+            Ok(Wallet::new())
+        }
+    }
+
+    // written to a temporary file first so a failed write never loses the old wallet, only
+    // the owner can read it from the moment it is created
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), WalletError> {
+        let path = path.as_ref();
+        let temporary = path.with_extension("tmp");
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut file = options.open(&temporary)?;
+        #[cfg(unix)]
+        {
+            // a temporary file left behind by an earlier save keeps its own mode
+            use std::os::unix::fs::PermissionsExt;
+            file.set_permissions(fs::Permissions::from_mode(0o600))?;
+        }
+        file.write_all(serde_json::to_string_pretty(self)?.as_bytes())?;
+        file.sync_all()?;
+        fs::rename(&temporary, path)?;
+        Ok(())
+    }
+
+    pub fn account(&self, name: &str) -> Result<&WalletAccount, WalletError> {
+        self.accounts
+            .iter()
+            .find(|account| account.name == name)
+            .ok_or_else(|| WalletError::UnknownAccount(name.to_string()))
+    }
+
+    // new account with a fresh key pair
+    pub fn create_account(
+        &mut self,
+        name: &str,
+        passphrase: &str,
+    ) -> Result<SigningKey, WalletError> {
+        let (signing_key, _) = generate_ecdsa_keypair();
+        self.add_key(name, &signing_key, passphrase)?;
+        Ok(signing_key)
+    }
+
+    pub fn add_key(
+        &mut self,
+        name: &str,
+        signing_key: &SigningKey,
+        passphrase: &str,
+    ) -> Result<(), WalletError> {
+        if self.account(name).is_ok() {
+            return Err(WalletError::DuplicateAccount(name.to_string()));
+        }
+        let account = WalletAccount::seal(name, signing_key, passphrase, self.kdf)?;
+        self.accounts.push(account);
+        Ok(())
+    }
+
+    pub fn unlock(&self, name: &str, passphrase: &str) -> Result<SigningKey, WalletError> {
+        self.account(name)?.unlock(passphrase)
+    }
+
+    // encrypt the key again under a new passphrase
+    pub fn change_passphrase(
+        &mut self,
+        name: &str,
+        passphrase: &str,
+        new_passphrase: &str,
+    ) -> Result<(), WalletError> {
+        let signing_key = self.unlock(name, passphrase)?;
+        let account = WalletAccount::seal(name, &signing_key, new_passphrase, self.kdf)?;
+        if let Some(existing) = self
+            .accounts
+            .iter_mut()
+            .find(|existing| existing.name == name)
+        {
+            *existing = account;
+        }
+        Ok(())
+    }
+
+    pub fn remove_account(&mut self, name: &str) -> Result<WalletAccount, WalletError> {
+        let position = self
+            .accounts
+            .iter()
+            .position(|account| account.name == name)
+            .ok_or_else(|| WalletError::UnknownAccount(name.to_string()))?;
+        Ok(self.accounts.remove(position))
+    }
+
+    // the account as JSON, its key stays encrypted under the account passphrase
+    pub fn export_account(&self, name: &str) -> Result<String, WalletError> {
+        Ok(serde_json::to_string_pretty(self.account(name)?)?)
+    }
+
+    pub fn import_account(&mut self, exported: &str) -> Result<&WalletAccount, WalletError> {
+        let account: WalletAccount = serde_json::from_str(exported)?;
+        if self.account(&account.name).is_ok() {
+            return Err(WalletError::DuplicateAccount(account.name));
+        }
+        self.accounts.push(account);
+        Ok(self.accounts.last().unwrap())
+    }
+
+    // raw 32 byte private key, as the key files written before the wallet existed
+    pub fn import_key_file(
+        &mut self,
+        name: &str,
+        path: impl AsRef<Path>,
+        passphrase: &str,
+    ) -> Result<SigningKey, WalletError> {
+        let secret = Zeroizing::new(fs::read(path)?);
+        let signing_key = SigningKey::from_slice(&secret).map_err(|_| WalletError::InvalidKey)?;
+        self.add_key(name, &signing_key, passphrase)?;
+        Ok(signing_key)
+    }
+}
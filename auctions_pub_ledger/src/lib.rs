@@ -1,7 +1,14 @@
 pub mod auction_app;
+pub mod auction_client;
 pub mod auction_server;
+pub mod conversions;
 pub mod cryptography;
 pub mod kademlia_node_search;
+pub mod auction_types {
+    // a transaction carries a whole auction or a single bid
+    #![allow(clippy::large_enum_variant)]
+    tonic::include_proto!("auction_types");
+}
 pub mod kademlia {
     tonic::include_proto!("kademlia");
 }
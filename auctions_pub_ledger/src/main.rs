@@ -1,11 +1,17 @@
 use auctions_pub_ledger::auction_app::auction::AuctionHouse;
 use auctions_pub_ledger::auction_app::auction_operation::server::auction_server;
+use auctions_pub_ledger::auction_server::auction_events::event_channel;
 use auctions_pub_ledger::auction_server::auction_validator::auctions_validator;
 use auctions_pub_ledger::auction_server::blockchain::init_blockchain;
 use auctions_pub_ledger::auction_server::blockchain::Blockchain;
 use auctions_pub_ledger::auction_server::blockchain_operation::server::blockchain_server;
 use auctions_pub_ledger::auction_server::blockchain_operator::get_remote_blockchain;
 use auctions_pub_ledger::auction_server::blockchain_operator::save_blockchain_locally;
+use auctions_pub_ledger::auction_server::notification_outbox::{
+    deliver_notifications, NotificationOutbox, OUTBOX_FILE,
+};
+use auctions_pub_ledger::auction_server::replication::anti_entropy;
+use auctions_pub_ledger::auction_server::user_ledger::{LedgerQueue, LEDGER_FILE};
 use auctions_pub_ledger::kademlia_node_search::node::run_server;
 use auctions_pub_ledger::kademlia_node_search::node::Node;
 use clap::{Arg, Command};
@@ -25,34 +31,70 @@ async fn destributed_auction_operator(
 ) {
     let shared_blockchain_vector = Arc::new(Mutex::new(blockchain_vector));
     println!("{:?}", bootstrap_addr.cloned());
-    let kademlia_node: Arc<Mutex<Node>> =
-        Node::new(addr.clone(), bootstrap_addr.map(|x| x.as_str()))
-            .await
-            .unwrap();
+    let kademlia_node: Arc<Mutex<Node>> = Node::new(addr, bootstrap_addr.map(|x| x.as_str()))
+        .await
+        .unwrap();
     // initialize auction house by importing from file
     let data = fs::read_to_string("auction_data.json").expect("Unable to read file");
     let auction_house: AuctionHouse =
         serde_json::from_str(&data).expect("Failed to deserialize JSON");
 
     let share_auction_house = Arc::new(Mutex::new(auction_house));
+    // changes to auctions, streamed to the clients subscribed on this node
+    let events = event_channel();
+    // notifications waiting for subscribers that could not be reached yet, a node starting
+    // empty over an unreadable outbox would overwrite it
+    let outbox = match NotificationOutbox::load(OUTBOX_FILE) {
+        Ok(outbox) => Arc::new(Mutex::new(outbox)),
+        Err(e) => {
+            eprintln!(
+                "Failed to read the notification outbox {}: {}",
+                OUTBOX_FILE, e
+            );
+            return;
+        }
+    };
+    let origin = addr.ip().to_string();
+    // deposits applied to user records and not in a block yet, dropping them would leave
+    // credits nothing in the ledger accounts for
+    let ledger = match LedgerQueue::load(LEDGER_FILE) {
+        Ok(ledger) => Arc::new(Mutex::new(ledger)),
+        Err(e) => {
+            eprintln!("Failed to read the ledger queue {}: {}", LEDGER_FILE, e);
+            return;
+        }
+    };
 
     let task1 = task::spawn(auction_server(
         share_auction_house.clone(),
         kademlia_node.clone(),
+        events.clone(),
+        outbox.clone(),
+        ledger.clone(),
     ));
     let task2 = task::spawn(auctions_validator(
         kademlia_node.clone(),
         shared_blockchain_vector.clone(),
         share_auction_house.clone(),
         mining_type.cloned(),
+        events,
+        outbox.clone(),
+        ledger,
     ));
     let task3 = task::spawn(blockchain_server(shared_blockchain_vector.clone()));
-    let task4 = task::spawn(run_server(addr.clone(), kademlia_node.clone()));
+    let task4 = task::spawn(run_server(addr, kademlia_node.clone()));
     //let task5 = task::spawn(loop_func(kademlia_node.clone()));
+    let task6 = task::spawn(anti_entropy(
+        share_auction_house.clone(),
+        kademlia_node.clone(),
+    ));
+    let task7 = task::spawn(deliver_notifications(outbox, origin));
     task1.await.unwrap();
     task2.await.unwrap();
     task3.await.unwrap();
     let _ = task4.await.unwrap();
+    task6.await.unwrap();
+    task7.await.unwrap();
 }
 
 #[tokio::main]
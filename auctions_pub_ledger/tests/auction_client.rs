@@ -0,0 +1,97 @@
+use auctions_pub_ledger::auction_client::{AuctionClient, Bid, ClientError, Session, User};
+use chrono::{DateTime, Utc};
+use k256::ecdsa::{signature::Verifier, Signature, SigningKey, VerifyingKey};
+use proptest::prelude::*;
+use rand_core::OsRng;
+use std::time::Duration;
+
+fn client(signing_key: Option<SigningKey>) -> AuctionClient {
+    let builder = AuctionClient::builder().endpoint("127.0.0.1:1");
+    match signing_key {
+        Some(signing_key) => builder.signing_key(signing_key),
+        None => builder,
+    }
+    .connect_timeout(Duration::from_millis(200))
+    .build()
+    .unwrap()
+}
+
+proptest! {
+    #[test]
+    fn signatures_verify_against_the_user_id(auction in "[0-9a-f]{16}", amount in 1u32..10_000) {
+        let client = client(Some(SigningKey::random(&mut OsRng)));
+        let user_id = client.user_id().unwrap();
+        let bid = Bid {
+            bidder: user_id.clone(),
+            amount: amount as f32,
+            signature: String::new(),
+            auction_signature: auction,
+            timestamp: DateTime::<Utc>::default(),
+            salt: String::new(),
+        };
+        let signature = client.sign(&bid.signed_content()).unwrap();
+
+        let verifying_key = VerifyingKey::from_sec1_bytes(&hex::decode(user_id).unwrap()).unwrap();
+        let signature = Signature::from_slice(&hex::decode(signature).unwrap()).unwrap();
+        prop_assert!(verifying_key
+            .verify(bid.signed_content().as_bytes(), &signature)
+            .is_ok());
+    }
+}
+
+#[test]
+fn building_needs_an_endpoint() {
+    assert!(matches!(
+        AuctionClient::builder().build(),
+        Err(ClientError::NoEndpoints)
+    ));
+    assert!(matches!(
+        AuctionClient::builder().endpoint("not a host").build(),
+        Err(ClientError::InvalidEndpoint(_))
+    ));
+}
+
+#[test]
+fn signing_needs_a_key() {
+    assert!(matches!(
+        client(None).sign("content"),
+        Err(ClientError::MissingSigningKey)
+    ));
+}
+
+#[tokio::test]
+async fn unreachable_nodes_are_errors() {
+    let client = client(Some(SigningKey::random(&mut OsRng)));
+    assert!(matches!(
+        client.bid("auction", 10.0).await,
+        Err(ClientError::Transport(_))
+    ));
+    assert!(client.get_auctions().await.is_err());
+}
+
+#[tokio::test]
+async fn sessions_stay_with_the_node_that_issued_them() {
+    let client = client(Some(SigningKey::random(&mut OsRng)));
+    let user_id = client.user_id().unwrap();
+    let session = Session {
+        user_id: user_id.clone(),
+        token: "token".to_string(),
+        expires_at: Utc::now(),
+        endpoint: "http://127.0.0.2:3000/".to_string(),
+    };
+    // the client never falls over to a node that does not know the session
+    assert!(matches!(
+        client
+            .create_user(&session, &User::new("alice".to_string(), user_id))
+            .await,
+        Err(ClientError::InvalidEndpoint(_))
+    ));
+    assert!(matches!(
+        client.deposit(&session, 10.0).await,
+        Err(ClientError::InvalidEndpoint(_))
+    ));
+    assert!(matches!(
+        client.connect_to("http://127.0.0.1:1/").await,
+        Err(ClientError::Transport(_))
+    ));
+}
@@ -0,0 +1,98 @@
+mod common;
+
+use auctions_pub_ledger::auction_app::auction::Auction;
+use auctions_pub_ledger::auction_app::auction_operation::dht::discover_auctions;
+use auctions_pub_ledger::auction_server::auction_directory::publish_auction;
+use auctions_pub_ledger::cryptography::wallet::user_id;
+use auctions_pub_ledger::kademlia_node_search::node::{run_server, Node};
+use chrono::{Duration, Utc};
+use common::sign;
+use futures::future::join_all;
+use k256::ecdsa::SigningKey;
+use rand_core::OsRng;
+use std::net::SocketAddr;
+
+// auction of its own seller, running for the next ten minutes
+fn auction(index: usize, category: &str) -> Auction {
+    let signing_key = SigningKey::random(&mut OsRng);
+    let mut auction = common::auction(&[]);
+    auction.item_name = format!("item {}", index);
+    auction.start_time = Utc::now() - Duration::minutes(1);
+    auction.end_time = auction.start_time + Duration::minutes(10);
+    auction.metadata.category = category.to_string();
+    auction.user_id = user_id(&signing_key);
+    auction.signature = sign(&signing_key, &auction.signed_content());
+    auction
+}
+
+fn signatures(auctions: Option<Vec<Auction>>) -> Vec<String> {
+    let mut signatures: Vec<String> = auctions
+        .unwrap_or_default()
+        .into_iter()
+        .map(|auction| auction.signature)
+        .collect();
+    signatures.sort();
+    signatures
+}
+
+#[tokio::test]
+async fn concurrent_publishes_keep_every_auction_listed() {
+    let addr: SocketAddr = "127.0.0.1:50051".parse().unwrap();
+    let node = Node::new(addr, None).await.unwrap();
+    tokio::spawn(run_server(addr, node.clone()));
+    let peer = "127.0.0.1";
+    for _ in 0..50 {
+        if discover_auctions(peer, None).await.is_ok() {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    assert!(discover_auctions(peer, None).await.unwrap().is_none());
+
+    let auctions: Vec<Auction> = (0..8)
+        .map(|index| auction(index, if index % 2 == 0 { "Books" } else { "" }))
+        .collect();
+    join_all(
+        auctions
+            .iter()
+            .map(|auction| publish_auction(node.clone(), auction.clone())),
+    )
+    .await;
+
+    let mut published: Vec<String> = auctions.iter().map(|a| a.signature.clone()).collect();
+    published.sort();
+    assert_eq!(
+        signatures(discover_auctions(peer, None).await.unwrap()),
+        published
+    );
+    let mut books: Vec<String> = auctions
+        .iter()
+        .step_by(2)
+        .map(|auction| auction.signature.clone())
+        .collect();
+    books.sort();
+    assert_eq!(
+        signatures(discover_auctions(peer, Some("books")).await.unwrap()),
+        books
+    );
+
+    // closing and cancelling take auctions out of the indexes
+    let mut closed = auctions[0].clone();
+    closed.active = false;
+    let mut cancelled = auctions[1].clone();
+    cancelled.cancelled = true;
+    join_all([
+        publish_auction(node.clone(), closed),
+        publish_auction(node.clone(), cancelled),
+    ])
+    .await;
+
+    let listed = signatures(discover_auctions(peer, None).await.unwrap());
+    assert_eq!(listed.len(), auctions.len() - 2);
+    assert!(!listed.contains(&auctions[0].signature));
+    assert!(!listed.contains(&auctions[1].signature));
+    assert_eq!(
+        signatures(discover_auctions(peer, Some("Books")).await.unwrap()).len(),
+        books.len() - 1
+    );
+}
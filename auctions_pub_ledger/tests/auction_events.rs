@@ -0,0 +1,55 @@
+mod common;
+
+use auctions_pub_ledger::auction_app::auction::Bid;
+use auctions_pub_ledger::auction_server::auction_events::{
+    auction_changes, AuctionEvent, EventKind,
+};
+use auctions_pub_ledger::auction_tx;
+use common::{auction, bids};
+use proptest::prelude::*;
+
+proptest! {
+    #[test]
+    fn every_new_bid_is_announced_once(known in bids(), new in bids(), close in any::<bool>()) {
+        let before = auction(&known);
+        let mut after = auction(&[known.clone(), new].concat());
+        after.active = !close;
+
+        let events = auction_changes(Some(&before), &after);
+        let announced: Vec<&Bid> = events
+            .iter()
+            .filter_map(|event| match &event.kind {
+                EventKind::NewBid(bid) => Some(bid),
+                _ => None,
+            })
+            .collect();
+        let unknown: Vec<&Bid> = after
+            .bids
+            .iter()
+            .filter(|bid| !before.bids.iter().any(|known| known.signature == bid.signature))
+            .collect();
+        prop_assert_eq!(announced, unknown);
+
+        let settled = events
+            .iter()
+            .filter(|event| matches!(event.kind, EventKind::Settled { .. }))
+            .count();
+        prop_assert_eq!(settled, close as usize);
+
+        // applying the same record again announces nothing
+        prop_assert!(auction_changes(Some(&after), &after).is_empty());
+    }
+
+    #[test]
+    fn events_survive_the_wire(bids in bids()) {
+        let mut before = auction(&bids);
+        before.bids.clear();
+        let mut after = auction(&bids);
+        after.active = false;
+
+        for event in auction_changes(Some(&before), &after) {
+            let message = auction_tx::AuctionEvent::from(event.clone());
+            prop_assert_eq!(AuctionEvent::try_from(message).unwrap(), event);
+        }
+    }
+}
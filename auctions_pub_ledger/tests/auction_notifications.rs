@@ -0,0 +1,79 @@
+mod common;
+
+use auctions_pub_ledger::auction_app::auction::{Auction, Notification};
+use auctions_pub_ledger::auction_server::auction_notifications::{
+    bid_notifications, settlement_notifications, Recipient,
+};
+use auctions_pub_ledger::auction_server::settlement::settle;
+use common::bids;
+use proptest::prelude::*;
+
+fn auction(bids: &[(u8, u16, i64)], reserve_price: f32) -> Auction {
+    let mut auction = common::auction(bids);
+    auction.rules.reserve_price = reserve_price;
+    auction
+}
+
+fn sent_to<'a>(
+    notifications: &'a [(Recipient, Notification)],
+    user_id: &str,
+) -> Vec<&'a Notification> {
+    notifications
+        .iter()
+        .filter(|(recipient, _)| *recipient == Recipient::User(user_id.to_string()))
+        .map(|(_, notification)| notification)
+        .collect()
+}
+
+proptest! {
+    #[test]
+    fn every_bidder_hears_how_the_auction_ended(bids in bids(), reserve in 0u16..1000) {
+        let auction = auction(&bids, reserve as f32);
+        let settlement = settle(&auction);
+        let notifications = settlement_notifications(&auction, &settlement);
+
+        for bid in auction.ranked_bids() {
+            let received = sent_to(&notifications, &bid.bidder);
+            let won = settlement.awards.iter().any(|award| award.bid.bidder == bid.bidder);
+            let expected = received.iter().all(|notification| match notification {
+                Notification::Won { .. } => won,
+                Notification::Lost { .. } => !won,
+                _ => false,
+            });
+            prop_assert!(!received.is_empty());
+            prop_assert!(expected);
+        }
+
+        let seller = sent_to(&notifications, "seller");
+        prop_assert_eq!(seller.len(), 1);
+        match seller[0] {
+            Notification::SellerPayout { amount, units_sold, .. } => {
+                let total: f32 = settlement.awards.iter().map(|award| award.price).sum();
+                prop_assert_eq!(*amount, total);
+                prop_assert_eq!(*units_sold as usize, settlement.awards.len());
+            }
+            Notification::EndedWithoutBids { .. } => prop_assert!(settlement.awards.is_empty()),
+            other => prop_assert!(false, "unexpected seller notification {:?}", other),
+        }
+    }
+
+    #[test]
+    fn a_displaced_winner_is_outbid(known in bids(), amount in 1u16..1000) {
+        let before = auction(&known, 0.0);
+        let after = auction(&[known.clone(), vec![(9, amount, 599)]].concat(), 0.0);
+        let bid = after.bids.last().unwrap().clone();
+
+        let notifications = bid_notifications(&before, &after, &bid);
+        for previous in before.winning_bids() {
+            let displaced = !after
+                .winning_bids()
+                .iter()
+                .any(|winning_bid| winning_bid.bidder == previous.bidder);
+            let outbid = sent_to(&notifications, &previous.bidder)
+                .iter()
+                .any(|notification| matches!(notification, Notification::Outbid { .. }));
+            prop_assert_eq!(outbid, displaced);
+        }
+        prop_assert!(sent_to(&notifications, &bid.bidder).is_empty());
+    }
+}
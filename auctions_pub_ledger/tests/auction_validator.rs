@@ -0,0 +1,35 @@
+use auctions_pub_ledger::auction_server::auction_validator::ChargeQueue;
+use auctions_pub_ledger::auction_server::notification_outbox::backoff;
+use chrono::{DateTime, Duration};
+
+#[test]
+fn failed_charges_are_retried_later_not_on_every_pass() {
+    let now = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+    let mut charges = ChargeQueue::default();
+    charges.push("award", now);
+    charges.push("release", now + Duration::seconds(1));
+
+    let (charge, attempts) = charges.due(now).pop().unwrap();
+    assert_eq!((charge, attempts), ("award", 0));
+    let next_attempt = charges.retry(charge, attempts, now);
+    assert_eq!(next_attempt, now + backoff(1));
+
+    // the passes before the next attempt leave the failed charge queued
+    assert_eq!(
+        charges.due(now + Duration::seconds(1)),
+        vec![("release", 0)]
+    );
+    for second in 1..backoff(1).num_seconds() {
+        assert!(charges.due(now + Duration::seconds(second)).is_empty());
+    }
+    assert_eq!(charges.len(), 1);
+
+    // every failure in a row waits longer
+    let (charge, attempts) = charges.due(next_attempt).pop().unwrap();
+    assert_eq!(attempts, 1);
+    let later = charges.retry(charge, attempts, next_attempt);
+    assert!(later - next_attempt > next_attempt - now);
+    assert!(charges.due(later - Duration::seconds(1)).is_empty());
+    assert_eq!(charges.due(later), vec![("award", 2)]);
+    assert!(charges.is_empty());
+}
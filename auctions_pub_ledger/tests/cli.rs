@@ -0,0 +1,85 @@
+use auctions_pub_ledger::auction_app::cli;
+use proptest::prelude::*;
+
+proptest! {
+    #[test]
+    fn bids_parse_their_arguments(user in "[a-z]{1,8}", auction in "[0-9a-f]{8}", amount in 0u32..100_000) {
+        let matches = cli::command()
+            .try_get_matches_from([
+                "auction_app",
+                "10.10.0.3,10.10.0.4",
+                "bid",
+                "--user",
+                &user,
+                "--auction",
+                &auction,
+                "--amount",
+                &amount.to_string(),
+            ])
+            .unwrap();
+        prop_assert_eq!(matches.get_one::<String>("peers").unwrap(), "10.10.0.3,10.10.0.4");
+
+        let (name, bid) = matches.subcommand().unwrap();
+        prop_assert_eq!(name, "bid");
+        prop_assert_eq!(bid.get_one::<String>("user").unwrap(), &user);
+        prop_assert_eq!(bid.get_one::<String>("auction").unwrap(), &auction);
+        prop_assert_eq!(*bid.get_one::<f32>("amount").unwrap(), amount as f32);
+    }
+
+    #[test]
+    fn malformed_numbers_are_rejected(amount in "[a-z]{1,8}") {
+        let matches = cli::command().try_get_matches_from([
+            "auction_app",
+            "10.10.0.3",
+            "add-credits",
+            "--user",
+            "alice",
+            "--amount",
+            &amount,
+        ]);
+        prop_assert!(matches.is_err());
+    }
+}
+
+#[test]
+fn reveals_and_cancellations_parse_their_arguments() {
+    let matches = cli::command()
+        .try_get_matches_from(["auction_app", "10.10.0.3", "reveal", "--user", "alice"])
+        .unwrap();
+    let (name, reveal) = matches.subcommand().unwrap();
+    assert_eq!(name, "reveal");
+    assert!(reveal.get_one::<String>("auction").is_none());
+
+    // a cancellation names the auction
+    let matches = cli::command().try_get_matches_from([
+        "auction_app",
+        "10.10.0.3",
+        "cancel",
+        "--user",
+        "alice",
+    ]);
+    assert!(matches.is_err());
+    let matches = cli::command()
+        .try_get_matches_from([
+            "auction_app",
+            "10.10.0.3",
+            "cancel",
+            "--user",
+            "alice",
+            "--auction",
+            "abc",
+        ])
+        .unwrap();
+    let (name, cancel) = matches.subcommand().unwrap();
+    assert_eq!(name, "cancel");
+    assert_eq!(cancel.get_one::<String>("auction").unwrap(), "abc");
+}
+
+#[test]
+fn no_subcommand_keeps_the_interactive_menu() {
+    let matches = cli::command()
+        .try_get_matches_from(["auction_app", "10.10.0.3"])
+        .unwrap();
+    assert!(matches.subcommand().is_none());
+    assert_eq!(matches.get_one::<String>("bootstrap").unwrap(), "10.10.0.2");
+}
@@ -0,0 +1,70 @@
+// Factories shared by the integration tests, each test binary only uses some of them
+#![allow(dead_code)]
+
+use auctions_pub_ledger::auction_app::auction::{Auction, AuctionKind, AuctionRules, Bid};
+use auctions_pub_ledger::cryptography::wallet::user_id;
+use chrono::{DateTime, Duration, Utc};
+use k256::ecdsa::{signature::Signer, Signature, SigningKey};
+use proptest::prelude::*;
+
+pub fn start_time() -> DateTime<Utc> {
+    DateTime::from_timestamp(1_700_000_000, 0).unwrap()
+}
+
+// bid of `bidder{n}` placed `second` seconds after the start
+pub fn bid(bidder: u8, amount: u16, second: i64) -> Bid {
+    Bid {
+        bidder: format!("bidder{}", bidder),
+        amount: amount as f32,
+        signature: format!("bid{}-{}-{}", bidder, amount, second),
+        auction_signature: "auction".to_string(),
+        timestamp: start_time() + Duration::seconds(second),
+        salt: String::new(),
+    }
+}
+
+// English auction of ten minutes with default rules, bids given as (bidder, amount, second)
+pub fn auction(bids: &[(u8, u16, i64)]) -> Auction {
+    let mut auction = Auction::new(
+        "item".to_string(),
+        start_time(),
+        start_time() + Duration::minutes(10),
+        1.0,
+        AuctionRules::default(),
+        AuctionKind::English,
+        "seller".to_string(),
+    );
+    auction.signature = "auction".to_string();
+    for (bidder, amount, second) in bids {
+        auction.bids.push(bid(*bidder, *amount, *second));
+    }
+    auction
+}
+
+pub fn bids() -> impl Strategy<Value = Vec<(u8, u16, i64)>> {
+    prop::collection::vec((0u8..5, 1u16..1000, 0i64..600), 0..10)
+}
+
+pub fn sign(signing_key: &SigningKey, content: &str) -> String {
+    let signature: Signature = signing_key.sign(content.as_bytes());
+    hex::encode(signature.to_bytes())
+}
+
+// bid carrying a real signature of the bidder's key
+pub fn signed_bid(
+    signing_key: &SigningKey,
+    auction_signature: &str,
+    amount: f32,
+    timestamp: DateTime<Utc>,
+) -> Bid {
+    let mut bid = Bid {
+        bidder: user_id(signing_key),
+        amount,
+        signature: String::new(),
+        auction_signature: auction_signature.to_string(),
+        timestamp,
+        salt: String::new(),
+    };
+    bid.signature = sign(signing_key, &bid.signed_content());
+    bid
+}
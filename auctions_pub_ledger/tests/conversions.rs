@@ -0,0 +1,132 @@
+use auctions_pub_ledger::auction_app::auction::{
+    Auction, AuctionKind, AuctionMetadata, AuctionRules, Bid, BidIncrement, Pricing, SoftClose,
+    Transaction,
+};
+use auctions_pub_ledger::auction_types as proto;
+use auctions_pub_ledger::conversions::DecodeError;
+use chrono::{DateTime, Duration};
+use proptest::prelude::*;
+use prost::Message;
+
+fn rules() -> impl Strategy<Value = AuctionRules> {
+    (
+        0u16..1000,
+        prop_oneof![
+            (0u16..100).prop_map(|amount| BidIncrement::Absolute(amount as f32 / 4.0)),
+            (0u16..100).prop_map(|percent| BidIncrement::Percent(percent as f32)),
+        ],
+        prop_oneof![
+            Just(Pricing::FirstPrice),
+            Just(Pricing::SecondPrice),
+            Just(Pricing::Uniform)
+        ],
+        prop::option::of((1i64..600, 1i64..600, 1i64..3600)),
+        prop::option::of(1u16..1000),
+    )
+        .prop_map(
+            |(reserve, min_increment, pricing, soft_close, buy_now)| AuctionRules {
+                reserve_price: reserve as f32,
+                min_increment,
+                pricing,
+                soft_close: soft_close.map(|(window, extension, max)| SoftClose {
+                    window_seconds: window,
+                    extension_seconds: extension,
+                    max_extension_seconds: max,
+                }),
+                buy_now_price: buy_now.map(|amount| amount as f32 + 0.5),
+            },
+        )
+}
+
+fn kind() -> impl Strategy<Value = AuctionKind> {
+    prop_oneof![
+        Just(AuctionKind::English),
+        (1u16..100, 1i64..3600).prop_map(|(deposit, reveal_seconds)| AuctionKind::SealedBid {
+            deposit: deposit as f32,
+            reveal_seconds,
+        }),
+        (100u16..1000, 1u16..50, 1i64..60).prop_map(|(start, decrement, seconds)| {
+            AuctionKind::Dutch {
+                start_price: start as f32,
+                decrement: decrement as f32,
+                decrement_seconds: seconds,
+                floor_price: 1.0,
+            }
+        }),
+    ]
+}
+
+fn auction() -> impl Strategy<Value = Auction> {
+    (
+        "[a-z ]{1,20}",
+        0i64..4_000_000_000,
+        1u32..10,
+        rules(),
+        kind(),
+        prop::collection::btree_map("[a-z]{1,8}", "[a-z0-9]{0,8}", 0..4),
+        prop::collection::vec((0u8..5, 1u16..1000, 0i64..600), 0..5),
+    )
+        .prop_map(
+            |(item_name, start, quantity, rules, kind, attributes, bids)| {
+                let start_time = DateTime::from_timestamp(start, 0).unwrap();
+                let mut auction = Auction::new(
+                    item_name,
+                    start_time,
+                    start_time + Duration::minutes(10),
+                    1.5,
+                    rules,
+                    kind,
+                    "seller".to_string(),
+                );
+                auction.quantity = quantity;
+                auction.metadata = AuctionMetadata {
+                    description: "description".to_string(),
+                    category: "category".to_string(),
+                    tags: vec!["tag".to_string()],
+                    attributes,
+                    images: Vec::new(),
+                };
+                auction.signature = "auction".to_string();
+                for (bidder, amount, second) in bids {
+                    auction.bids.push(Bid {
+                        bidder: format!("bidder{}", bidder),
+                        amount: amount as f32 / 4.0,
+                        signature: format!("bid{}-{}", bidder, second),
+                        auction_signature: auction.signature.clone(),
+                        timestamp: start_time + Duration::seconds(second),
+                        salt: String::new(),
+                    });
+                }
+                auction
+            },
+        )
+}
+
+proptest! {
+    #[test]
+    fn auctions_survive_the_wire(auction in auction()) {
+        let transaction = Transaction::Auction(Box::new(auction.clone()));
+        let bytes = proto::Transaction::from(transaction).encode_to_vec();
+        let decoded = proto::Transaction::decode(bytes.as_slice()).unwrap();
+
+        match Transaction::try_from(decoded).unwrap() {
+            Transaction::Auction(decoded) => {
+                // the seller signature must still verify on the decoded record
+                prop_assert_eq!(decoded.signed_content(), auction.signed_content());
+                prop_assert_eq!(
+                    serde_json::to_value(&decoded).unwrap(),
+                    serde_json::to_value(&auction).unwrap()
+                );
+            }
+            other => prop_assert!(false, "decoded as {:?}", other),
+        }
+    }
+}
+
+#[test]
+fn empty_transactions_are_refused() {
+    assert_eq!(
+        Transaction::try_from(proto::Transaction::default()).unwrap_err(),
+        DecodeError::MissingField("transaction")
+    );
+}
@@ -0,0 +1,102 @@
+mod common;
+
+use auctions_pub_ledger::auction_app::auction::{Auction, AuctionMetadata};
+use auctions_pub_ledger::auction_app::auction_operation::dht::{
+    find_content, store_content, store_value,
+};
+use auctions_pub_ledger::kademlia_node_search::node::{run_server, Node};
+use chrono::{DateTime, Duration};
+use proptest::prelude::*;
+use std::net::SocketAddr;
+
+fn metadata() -> impl Strategy<Value = AuctionMetadata> {
+    (
+        "[a-z ]{0,20}",
+        "[a-z]{0,8}",
+        prop::collection::vec("[a-z]{1,8}", 0..4),
+        prop::collection::btree_map("[a-z]{1,8}", "[a-z0-9]{0,8}", 0..4),
+        prop::collection::vec("[0-9a-f]{64}", 0..3),
+    )
+        .prop_map(
+            |(description, category, tags, attributes, images)| AuctionMetadata {
+                description,
+                category,
+                tags,
+                attributes,
+                images,
+            },
+        )
+}
+
+fn auction(metadata: AuctionMetadata, start: i64) -> Auction {
+    let mut auction = common::auction(&[]);
+    auction.start_time = DateTime::from_timestamp(start, 0).unwrap();
+    auction.end_time = auction.start_time + Duration::minutes(10);
+    auction.metadata = metadata;
+    auction
+}
+
+proptest! {
+    #[test]
+    fn metadata_digest_survives_the_dht_record(metadata in metadata(), start in 0i64..4_000_000_000) {
+        let auction = auction(metadata, start);
+        let record: Auction =
+            serde_json::from_slice(&serde_json::to_vec(&auction).unwrap()).unwrap();
+        prop_assert_eq!(record.signed_content(), auction.signed_content());
+    }
+
+    #[test]
+    fn the_seller_signs_metadata_and_schedule(
+        metadata in metadata(),
+        other in metadata(),
+        start in 0i64..4_000_000_000,
+        relisted in 1i64..1_000_000,
+    ) {
+        let auction = auction(metadata.clone(), start);
+        if other != metadata {
+            prop_assert_ne!(auction.signed_content(), self::auction(other, start).signed_content());
+        }
+        // relisting the same item later is a different auction
+        prop_assert_ne!(
+            auction.signed_content(),
+            self::auction(metadata, start + relisted).signed_content()
+        );
+    }
+}
+
+// A single kademlia node on the DHT port, the app always talks to peers on it
+async fn local_node() -> String {
+    let addr: SocketAddr = "127.0.0.1:50051".parse().unwrap();
+    let node = Node::new(addr, None).await.unwrap();
+    tokio::spawn(run_server(addr, node));
+    "127.0.0.1".to_string()
+}
+
+#[tokio::test]
+async fn content_is_found_by_its_hash() {
+    let peer = local_node().await;
+    let image = b"not really a png".to_vec();
+
+    let mut stored = store_content(&peer, image.clone()).await;
+    for _ in 0..50 {
+        if stored.is_ok() {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        stored = store_content(&peer, image.clone()).await;
+    }
+    let content_hash = stored.unwrap();
+    assert_eq!(content_hash, sha256::digest(image.as_slice()));
+    assert_eq!(find_content(&peer, &content_hash).await.unwrap(), image);
+
+    // a node answering with other bytes under the hash is caught
+    let forged = sha256::digest(b"another image".as_slice());
+    store_value(&peer, hex::decode(&forged).unwrap(), image.clone())
+        .await
+        .unwrap();
+    assert!(find_content(&peer, &forged).await.is_err());
+
+    let missing = sha256::digest(b"never stored".as_slice());
+    assert!(find_content(&peer, &missing).await.is_err());
+    assert!(find_content(&peer, "not hex").await.is_err());
+}
@@ -0,0 +1,178 @@
+mod common;
+
+use auctions_pub_ledger::auction_app::auction::{Endpoint, Notification};
+use auctions_pub_ledger::auction_server::auction_notifications::settlement_notifications;
+use auctions_pub_ledger::auction_server::notification_outbox::{
+    backoff, NotificationOutbox, OUTBOX_RETENTION_HOURS, RETRY_CAP,
+};
+use auctions_pub_ledger::auction_server::settlement::settle;
+use chrono::{DateTime, Duration};
+use common::bids;
+use proptest::prelude::*;
+
+fn opening(index: usize) -> Notification {
+    Notification::BiddingOpen {
+        auction_signature: format!("auction{}", index),
+        item_name: "item".to_string(),
+    }
+}
+
+proptest! {
+    #[test]
+    fn sequences_count_up_per_subscriber(
+        subscribers in prop::collection::vec(0u8..4, 1..40),
+        since in 0u64..20,
+    ) {
+        let now = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let mut outbox = NotificationOutbox::default();
+        for (index, subscriber) in subscribers.iter().enumerate() {
+            outbox.enqueue(&format!("peer{}", subscriber), opening(index), now);
+        }
+
+        for subscriber in 0u8..4 {
+            let peer = format!("peer{}", subscriber);
+            let queued = subscribers.iter().filter(|queued| **queued == subscriber).count() as u64;
+            let sequences: Vec<u64> = outbox
+                .pending(&peer)
+                .iter()
+                .map(|sequenced| sequenced.sequence)
+                .collect();
+            prop_assert_eq!(sequences, (1..=queued).collect::<Vec<u64>>());
+
+            // catching up hands out what came after `since` and settles what came before,
+            // the handed out ones are settled by the next catch up
+            let missed: Vec<u64> = outbox
+                .catch_up(&peer, since)
+                .iter()
+                .map(|sequenced| sequenced.sequence)
+                .collect();
+            prop_assert_eq!(&missed, &(since + 1..=queued).collect::<Vec<u64>>());
+            let pending: Vec<u64> = outbox
+                .pending(&peer)
+                .iter()
+                .map(|sequenced| sequenced.sequence)
+                .collect();
+            prop_assert_eq!(pending, missed);
+            outbox.catch_up(&peer, queued);
+            prop_assert!(outbox.pending(&peer).is_empty());
+        }
+    }
+
+    #[test]
+    fn returning_subscribers_never_reuse_a_sequence(rounds in prop::collection::vec(1usize..5, 1..5)) {
+        let mut now = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let mut outbox = NotificationOutbox::default();
+        let mut last = 0;
+        for queued in rounds {
+            for index in 0..queued {
+                let sequence = outbox.enqueue("peer", opening(index), now);
+                prop_assert!(sequence > last);
+                last = sequence;
+            }
+            // once everything expired the subscriber is forgotten
+            now += Duration::hours(OUTBOX_RETENTION_HOURS);
+            prop_assert!(outbox.prune(now));
+            prop_assert!(outbox.subscribers.is_empty());
+        }
+    }
+
+    #[test]
+    fn another_replica_settles_for_every_bidder(bids in bids()) {
+        // the settling replica got the bids through replication, it never saw the bidders
+        // subscribe and holds no follower for the auction
+        let mut replica = common::auction(&bids);
+        replica.subscribers.clear();
+        let now = replica.end_time;
+
+        let mut outbox = NotificationOutbox::default();
+        for (recipient, notification) in settlement_notifications(&replica, &settle(&replica)) {
+            for user_id in NotificationOutbox::resolve(&replica.followers(), &recipient) {
+                outbox.enqueue(&user_id, notification.clone(), now);
+            }
+        }
+
+        for bid in replica.ranked_bids() {
+            let pending = outbox.pending(&bid.bidder);
+            prop_assert_eq!(pending.len(), 1);
+            let settled = matches!(
+                pending[0].notification,
+                Notification::Won { .. } | Notification::Lost { .. }
+            );
+            prop_assert!(settled);
+        }
+        prop_assert_eq!(outbox.pending("seller").len(), 1);
+        // nobody told this node where they are yet, they get them when catching up
+        prop_assert!(outbox.take_due(now).is_empty());
+        prop_assert_eq!(outbox.catch_up("seller", 0).len(), 1);
+    }
+
+    #[test]
+    fn replicated_endpoints_reach_every_bidder(bids in bids()) {
+        let mut replica = common::auction(&bids);
+        let now = replica.end_time;
+        for bid in replica.bids.clone() {
+            for second in [0, 1] {
+                replica.record_endpoint(&Endpoint {
+                    user_id: bid.bidder.clone(),
+                    addr: format!("10.0.{}.{}", second, bid.bidder.len()),
+                    timestamp: now + Duration::seconds(second),
+                    signature: format!("{}-{}", bid.bidder, second),
+                });
+            }
+        }
+        // an older endpoint arriving late does not replace the newer one
+        for endpoint in replica.endpoints.clone() {
+            let stale = Endpoint {
+                timestamp: now,
+                ..endpoint
+            };
+            prop_assert!(!replica.record_endpoint(&stale));
+        }
+
+        let mut outbox = NotificationOutbox::default();
+        for endpoint in replica.endpoints.iter() {
+            prop_assert!(outbox.remember(endpoint));
+            prop_assert!(!outbox.remember(endpoint));
+        }
+        for (recipient, notification) in settlement_notifications(&replica, &settle(&replica)) {
+            for user_id in NotificationOutbox::resolve(&replica.followers(), &recipient) {
+                outbox.enqueue(&user_id, notification.clone(), now);
+            }
+        }
+
+        let due = outbox.take_due(now);
+        for bid in replica.ranked_bids() {
+            prop_assert!(due.contains(&bid.bidder));
+            prop_assert!(outbox.endpoints[&bid.bidder].addr.starts_with("10.0.1."));
+        }
+        // the seller never signed an endpoint, their notifications wait for a catch up
+        prop_assert!(!due.contains(&"seller".to_string()));
+    }
+
+    #[test]
+    fn retries_back_off_up_to_the_cap(attempts in 1u32..100) {
+        prop_assert!(backoff(attempts) >= backoff(attempts - 1));
+        prop_assert!(backoff(attempts) <= Duration::seconds(RETRY_CAP));
+    }
+}
+
+#[tokio::test]
+async fn the_outbox_survives_a_restart() {
+    let directory = std::env::temp_dir().join(format!("outbox-{}", std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+    let path = directory.join("outbox.json").to_string_lossy().to_string();
+
+    assert!(NotificationOutbox::load(&path).unwrap().entries.is_empty());
+    let mut outbox = NotificationOutbox::default();
+    let now = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+    outbox.enqueue("peer", opening(0), now);
+    outbox.save(&path).await.unwrap();
+    assert_eq!(
+        NotificationOutbox::load(&path).unwrap().pending("peer"),
+        outbox.pending("peer")
+    );
+
+    std::fs::write(&path, "{ not an outbox").unwrap();
+    assert!(NotificationOutbox::load(&path).is_err());
+    std::fs::remove_dir_all(&directory).unwrap();
+}
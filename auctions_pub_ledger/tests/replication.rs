@@ -0,0 +1,275 @@
+mod common;
+
+use auctions_pub_ledger::auction_app::auction::{
+    Auction, AuctionCancel, AuctionHouse, AuctionKind, Bid, BidCommitment, BidReveal, Subscription,
+    Transaction,
+};
+use auctions_pub_ledger::auction_server::auction_handler::{transaction_handler, Rejection, Route};
+use auctions_pub_ledger::auction_server::replication::{
+    apply_record, check_replicated_bid, merge_auction,
+};
+use auctions_pub_ledger::cryptography::wallet::user_id;
+use chrono::Duration;
+use common::{bids, sign, start_time};
+use k256::ecdsa::SigningKey;
+use proptest::prelude::*;
+use rand_core::OsRng;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+fn replica(bids: &[(u8, u16, i64)], cancelled: bool) -> Auction {
+    let mut auction = common::auction(bids);
+    if cancelled {
+        auction.cancelled = true;
+        auction.cancellation = Some(AuctionCancel {
+            seller: "seller".to_string(),
+            auction_signature: "auction".to_string(),
+            signature: "cancel".to_string(),
+        });
+    }
+    auction
+}
+
+fn subscriptions() -> impl Strategy<Value = Vec<(u8, bool, i64)>> {
+    prop::collection::vec((0u8..5, any::<bool>(), 0i64..600), 0..10)
+}
+
+fn subscription(subscriber: u8, subscribe: bool, second: i64) -> Subscription {
+    Subscription {
+        subscriber: format!("user{}", subscriber),
+        auction_signature: "auction".to_string(),
+        subscribe,
+        timestamp: start_time() + Duration::seconds(second),
+        signature: format!("subscription{}-{}-{}", subscriber, subscribe, second),
+    }
+}
+
+proptest! {
+    #[test]
+    fn replicas_converge_in_any_order(
+        a_bids in bids(),
+        b_bids in bids(),
+        a_cancelled in any::<bool>(),
+        b_cancelled in any::<bool>(),
+    ) {
+        let a = replica(&a_bids, a_cancelled);
+        let b = replica(&b_bids, b_cancelled);
+
+        let mut a_then_b = a.clone();
+        merge_auction(&mut a_then_b, &b);
+        let mut b_then_a = b.clone();
+        merge_auction(&mut b_then_a, &a);
+
+        prop_assert_eq!(&a_then_b.bids, &b_then_a.bids);
+        prop_assert_eq!(a_then_b.cancelled, a_cancelled || b_cancelled);
+        prop_assert_eq!(b_then_a.cancelled, a_then_b.cancelled);
+        // a signed cancellation wins over bids that raced it
+        if a_then_b.cancelled {
+            prop_assert!(a_then_b.bids.is_empty());
+        }
+        prop_assert_eq!(a_then_b.closing_time(), b_then_a.closing_time());
+    }
+
+    #[test]
+    fn merging_twice_changes_nothing(a_bids in bids(), b_bids in bids()) {
+        let mut a = replica(&a_bids, false);
+        let b = replica(&b_bids, false);
+
+        merge_auction(&mut a, &b);
+        prop_assert!(!merge_auction(&mut a, &b));
+        // every bid kept was allowed by the rules at its stamp, after the bids before it
+        for (position, bid) in a.bids.iter().enumerate() {
+            let earlier = Auction {
+                bids: a.bids[..position].to_vec(),
+                ..a.clone()
+            };
+            prop_assert_eq!(check_replicated_bid(&earlier, bid), Ok(()));
+        }
+    }
+
+    #[test]
+    fn flags_and_followers_are_not_taken_from_peers(bids in bids(), active in any::<bool>()) {
+        let mut local = replica(&[], false);
+        let mut remote = replica(&bids, false);
+        remote.active = active;
+        remote.cancelled = true;
+        remote.subscribers.push("intruder".to_string());
+
+        merge_auction(&mut local, &remote);
+        prop_assert!(local.active);
+        prop_assert!(!local.cancelled);
+        prop_assert!(!local.followers().contains(&"intruder".to_string()));
+        for bid in local.bids.iter() {
+            prop_assert!(local.followers().contains(&bid.bidder));
+        }
+
+        // a record new to the node starts out as the seller listed it
+        let mut auction_house = AuctionHouse::new();
+        prop_assert!(apply_record(&mut auction_house, remote));
+        prop_assert!(auction_house.auctions[0].active);
+        prop_assert!(!auction_house.auctions[0].cancelled);
+        prop_assert_eq!(&auction_house.auctions[0].bids, &local.bids);
+        prop_assert!(!auction_house.auctions[0].subscribers.contains(&"intruder".to_string()));
+    }
+
+    #[test]
+    fn the_latest_subscription_wins_in_any_order(
+        a_subscriptions in subscriptions(),
+        b_subscriptions in subscriptions(),
+    ) {
+        let mut a = replica(&[], false);
+        for (subscriber, subscribe, second) in a_subscriptions.iter() {
+            a.record_subscription(&subscription(*subscriber, *subscribe, *second));
+        }
+        let mut b = replica(&[], false);
+        for (subscriber, subscribe, second) in b_subscriptions.iter() {
+            b.record_subscription(&subscription(*subscriber, *subscribe, *second));
+        }
+
+        let mut a_then_b = a.clone();
+        merge_auction(&mut a_then_b, &b);
+        let mut b_then_a = b.clone();
+        merge_auction(&mut b_then_a, &a);
+        prop_assert_eq!(&a_then_b.subscriptions, &b_then_a.subscriptions);
+
+        // whoever last unsubscribed is not followed anymore, whoever last subscribed is
+        for known in a_then_b.subscriptions.iter() {
+            let latest = a_subscriptions
+                .iter()
+                .chain(b_subscriptions.iter())
+                .map(|(subscriber, subscribe, second)| subscription(*subscriber, *subscribe, *second))
+                .filter(|candidate| candidate.subscriber == known.subscriber)
+                .max_by(|x, y| (x.timestamp, &x.signature).cmp(&(y.timestamp, &y.signature)))
+                .unwrap();
+            prop_assert_eq!(known, &latest);
+            prop_assert_eq!(a_then_b.followers().contains(&known.subscriber), known.subscribe);
+            prop_assert_eq!(b_then_a.followers().contains(&known.subscriber), known.subscribe);
+        }
+    }
+}
+
+#[test]
+fn bids_the_rules_refuse_are_dropped() {
+    // the seller, a bid after the close and a bid below the leading one
+    let mut local = common::auction(&[(1, 50, 10)]);
+    let remote = common::auction(&[(1, 50, 10), (2, 40, 20), (3, 90, 600)]);
+    let mut seller_bid = common::bid(4, 70, 30);
+    seller_bid.bidder = "seller".to_string();
+    let mut remote_with_seller = remote.clone();
+    remote_with_seller.bids.push(seller_bid);
+
+    merge_auction(&mut local, &remote_with_seller);
+    assert_eq!(local.bids, vec![common::bid(1, 50, 10)]);
+    assert_eq!(
+        check_replicated_bid(&local, &common::bid(2, 40, 20)),
+        Err(Rejection::BelowMinimumIncrement { minimum: 50.0 })
+    );
+}
+
+#[test]
+fn dutch_accepts_pay_the_price_of_their_stamp() {
+    let mut local = common::auction(&[]);
+    local.kind = AuctionKind::Dutch {
+        start_price: 100.0,
+        decrement: 10.0,
+        decrement_seconds: 60,
+        floor_price: 10.0,
+    };
+    let mut remote = local.clone();
+    // taken after two price drops but claiming the starting price
+    remote.bids.push(common::bid(1, 1, 130));
+
+    merge_auction(&mut local, &remote);
+    assert_eq!(local.bids.len(), 1);
+    assert_eq!(local.bids[0].amount, 80.0);
+}
+
+#[test]
+fn replicated_reveals_must_open_the_commitment() {
+    let mut auction = common::auction(&[]);
+    auction.kind = AuctionKind::SealedBid {
+        deposit: 0.0,
+        reveal_seconds: 300,
+    };
+    let reveal = BidReveal {
+        bid: common::bid(1, 50, 0),
+        salt: "salt".to_string(),
+    };
+    auction.commitments.push(BidCommitment {
+        bidder: reveal.bid.bidder.clone(),
+        auction_signature: "auction".to_string(),
+        commitment: reveal.commitment(),
+        signature: "commitment".to_string(),
+    });
+    let revealed = Bid {
+        salt: reveal.salt.clone(),
+        timestamp: auction.end_time + Duration::seconds(1),
+        ..reveal.bid.clone()
+    };
+    assert_eq!(check_replicated_bid(&auction, &revealed), Ok(()));
+    // a peer raising the amount, or sending no salt, does not open the commitment
+    let raised = Bid {
+        amount: 90.0,
+        ..revealed.clone()
+    };
+    for tampered in [
+        raised.clone(),
+        Bid {
+            salt: String::new(),
+            ..revealed.clone()
+        },
+    ] {
+        assert_eq!(
+            check_replicated_bid(&auction, &tampered),
+            Err(Rejection::CommitmentMismatch)
+        );
+    }
+
+    let mut local = auction.clone();
+    let mut remote = auction.clone();
+    remote.bids = vec![raised];
+    merge_auction(&mut local, &remote);
+    assert!(local.bids.is_empty());
+    remote.bids = vec![revealed.clone()];
+    merge_auction(&mut local, &remote);
+    assert_eq!(local.bids, vec![revealed]);
+}
+
+#[tokio::test]
+async fn submitted_auctions_keep_only_what_the_seller_signed() {
+    let seller = SigningKey::random(&mut OsRng);
+    let mut auction = replica(&[(1, 50, 10), (2, 90, 20)], true);
+    auction.user_id = user_id(&seller);
+    auction.signature = sign(&seller, &auction.signed_content());
+    let route = Route {
+        ttl: 4,
+        visited: Vec::new(),
+        own_addr: "10.0.0.1".to_string(),
+        closest: Vec::new(),
+    };
+    let mut auction_house = Arc::new(Mutex::new(AuctionHouse::new()));
+
+    let submit = Transaction::Auction(Box::new(auction.clone()));
+    transaction_handler(
+        submit.clone(),
+        &mut auction_house,
+        String::new(),
+        route.clone(),
+    )
+    .await
+    .unwrap();
+    let stored = auction_house.lock().await.auctions[0].clone();
+    assert!(stored.bids.is_empty());
+    assert!(stored.cancellation.is_none());
+    assert!(stored.active && !stored.cancelled);
+
+    // replaying the signed auction does not list it again
+    assert_eq!(
+        transaction_handler(submit, &mut auction_house, String::new(), route)
+            .await
+            .map(|_| ())
+            .unwrap_err(),
+        Rejection::AlreadyListed
+    );
+    assert_eq!(auction_house.lock().await.auctions.len(), 1);
+}
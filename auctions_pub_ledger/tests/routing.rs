@@ -0,0 +1,89 @@
+use auctions_pub_ledger::auction_app::auction::{AuctionHouse, Bid, Transaction};
+use auctions_pub_ledger::auction_server::auction_handler::{transaction_handler, Rejection, Route};
+use chrono::{DateTime, Utc};
+use proptest::prelude::*;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+fn peers() -> impl Strategy<Value = Vec<String>> {
+    prop::collection::vec((1u8..8).prop_map(|host| format!("10.0.0.{}", host)), 0..8)
+}
+
+fn bid() -> Transaction {
+    Transaction::Bid(Bid {
+        bidder: "bidder".to_string(),
+        amount: 10.0,
+        signature: "bid".to_string(),
+        auction_signature: "auction".to_string(),
+        timestamp: DateTime::<Utc>::default(),
+        salt: String::new(),
+    })
+}
+
+fn forward(route: Route) -> Result<(), Rejection> {
+    tokio::runtime::Runtime::new().unwrap().block_on(async {
+        let mut auction_house = Arc::new(Mutex::new(AuctionHouse::new()));
+        transaction_handler(bid(), &mut auction_house, String::new(), route)
+            .await
+            .map(|_| ())
+    })
+}
+
+proptest! {
+    #[test]
+    fn visited_nodes_are_never_tried_again(visited in peers(), closest in peers(), own in 1u8..8) {
+        let route = Route {
+            ttl: 4,
+            visited: visited.clone(),
+            own_addr: format!("10.0.0.{}", own),
+            closest: closest.clone(),
+        };
+        let candidates = route.candidates();
+        let next_visited = route.next_visited(&candidates);
+
+        for candidate in candidates.iter() {
+            prop_assert!(closest.contains(candidate));
+            prop_assert!(!visited.contains(candidate));
+            prop_assert_ne!(candidate, &route.own_addr);
+        }
+        // every node seen so far is skipped by the next hops, so they add no candidate twice
+        for node in visited.iter().chain(candidates.iter()).chain([&route.own_addr]) {
+            prop_assert!(next_visited.contains(node));
+        }
+        let next = Route { visited: next_visited, ..route.clone() };
+        prop_assert!(next.candidates().is_empty());
+    }
+}
+
+#[test]
+fn forwarding_stops_at_the_hop_limit() {
+    let route = Route {
+        ttl: 0,
+        visited: Vec::new(),
+        own_addr: "10.0.0.1".to_string(),
+        closest: vec!["10.0.0.2".to_string()],
+    };
+    assert_eq!(forward(route), Err(Rejection::HopLimitReached));
+}
+
+#[test]
+fn unreachable_and_visited_peers_are_skipped() {
+    let route = Route {
+        ttl: 1,
+        visited: vec!["10.0.0.2".to_string()],
+        own_addr: "10.0.0.1".to_string(),
+        closest: vec![
+            "10.0.0.1".to_string(),
+            "10.0.0.2".to_string(),
+            "127.0.0.1".to_string(),
+        ],
+    };
+    assert_eq!(forward(route.clone()), Err(Rejection::AuctionNotFound));
+    assert_eq!(
+        forward(Route {
+            closest: Vec::new(),
+            ..route
+        }),
+        Err(Rejection::AuctionNotFound)
+    );
+}
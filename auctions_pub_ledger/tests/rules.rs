@@ -0,0 +1,311 @@
+mod common;
+
+use auctions_pub_ledger::auction_app::auction::{
+    Auction, AuctionCancel, AuctionKind, Bid, BidCommitment, BidIncrement, BidReveal, DutchAccept,
+};
+use auctions_pub_ledger::auction_server::auction_handler::{
+    check_accept, check_bid, check_cancel, check_commitment, check_reveal, check_terms, Rejection,
+};
+use chrono::{DateTime, Duration, Utc};
+use common::start_time as start;
+use proptest::prelude::*;
+
+fn auction(kind: AuctionKind, starting_bid: f32, min_increment: BidIncrement) -> Auction {
+    let mut auction = common::auction(&[]);
+    auction.kind = kind;
+    auction.starting_bid = starting_bid;
+    auction.rules.min_increment = min_increment;
+    auction
+}
+
+fn bid(bidder: &str, amount: f32) -> Bid {
+    Bid {
+        bidder: bidder.to_string(),
+        amount,
+        signature: format!("{}-{}", bidder, amount),
+        auction_signature: "auction".to_string(),
+        timestamp: start(),
+        salt: String::new(),
+    }
+}
+
+fn sealed() -> Auction {
+    auction(
+        AuctionKind::SealedBid {
+            deposit: 10.0,
+            reveal_seconds: 300,
+        },
+        20.0,
+        BidIncrement::Absolute(0.0),
+    )
+}
+
+fn commitment(reveal: &BidReveal) -> BidCommitment {
+    BidCommitment {
+        bidder: reveal.bid.bidder.clone(),
+        auction_signature: "auction".to_string(),
+        commitment: reveal.commitment(),
+        signature: "commitment".to_string(),
+    }
+}
+
+fn dutch() -> Auction {
+    auction(
+        AuctionKind::Dutch {
+            start_price: 100.0,
+            decrement: 10.0,
+            decrement_seconds: 60,
+            floor_price: 10.0,
+        },
+        10.0,
+        BidIncrement::Absolute(0.0),
+    )
+}
+
+fn accept(bidder: &str, timestamp: DateTime<Utc>) -> DutchAccept {
+    DutchAccept {
+        bidder: bidder.to_string(),
+        auction_signature: "auction".to_string(),
+        timestamp,
+        signature: "accept".to_string(),
+    }
+}
+
+proptest! {
+    #[test]
+    fn bids_start_at_the_starting_bid(starting_bid in 1u16..1000, amount in 0u16..2000) {
+        let auction = auction(AuctionKind::English, starting_bid as f32, BidIncrement::Absolute(0.0));
+        let checked = check_bid(&auction, &bid("bidder", amount as f32), start());
+
+        if amount < starting_bid {
+            prop_assert_eq!(checked, Err(Rejection::BelowStartingBid { starting_bid: starting_bid as f32 }));
+        } else {
+            prop_assert_eq!(checked, Ok(()));
+        }
+    }
+
+    #[test]
+    fn bids_raise_by_the_minimum_increment(leading in 1u16..1000, increment in 1u16..100, raise in 0u16..200) {
+        let mut auction = auction(AuctionKind::English, 1.0, BidIncrement::Absolute(increment as f32));
+        auction.bids.push(bid("leader", leading as f32));
+        let checked = check_bid(&auction, &bid("bidder", (leading + raise) as f32), start());
+
+        if raise < increment {
+            prop_assert_eq!(checked, Err(Rejection::BelowMinimumIncrement { minimum: (leading + increment) as f32 }));
+        } else {
+            prop_assert_eq!(checked, Ok(()));
+        }
+    }
+}
+
+#[test]
+fn bids_outside_the_rules_are_refused() {
+    let auction = auction(AuctionKind::English, 10.0, BidIncrement::Absolute(0.0));
+    assert_eq!(
+        check_bid(&auction, &bid("seller", 50.0), start()),
+        Err(Rejection::SellerBid)
+    );
+    assert_eq!(
+        check_bid(
+            &auction,
+            &bid("bidder", 50.0),
+            start() - Duration::seconds(1)
+        ),
+        Err(Rejection::NotYetOpen {
+            start_time: start()
+        })
+    );
+    assert_eq!(
+        check_bid(&auction, &bid("bidder", 50.0), auction.end_time),
+        Err(Rejection::AuctionClosed)
+    );
+    assert_eq!(
+        check_bid(&sealed(), &bid("bidder", 50.0), start()),
+        Err(Rejection::UnsupportedTransaction)
+    );
+}
+
+#[test]
+fn amounts_must_be_finite() {
+    let free = auction(AuctionKind::English, 0.0, BidIncrement::Absolute(0.0));
+    for amount in [f32::NAN, f32::INFINITY, 0.0] {
+        assert_eq!(
+            check_bid(&free, &bid("bidder", amount), start()),
+            Err(Rejection::InvalidAmount)
+        );
+    }
+
+    let mut auction = sealed();
+    let reveal = BidReveal {
+        bid: bid("bidder", f32::INFINITY),
+        salt: "salt".to_string(),
+    };
+    auction.commitments.push(commitment(&reveal));
+    assert_eq!(
+        check_reveal(&auction, &reveal, auction.end_time + Duration::seconds(1)),
+        Err(Rejection::InvalidAmount)
+    );
+
+    assert_eq!(check_terms(&sealed()), Ok(()));
+    assert_eq!(check_terms(&dutch()), Ok(()));
+    let mut terms = [sealed(), dutch(), free.clone(), free.clone(), free];
+    terms[0].kind = AuctionKind::SealedBid {
+        deposit: f32::NAN,
+        reveal_seconds: 300,
+    };
+    terms[1].kind = AuctionKind::Dutch {
+        start_price: 100.0,
+        decrement: f32::INFINITY,
+        decrement_seconds: 60,
+        floor_price: 10.0,
+    };
+    terms[2].starting_bid = -1.0;
+    terms[3].rules.min_increment = BidIncrement::Percent(f32::NAN);
+    terms[4].rules.buy_now_price = Some(f32::INFINITY);
+    for auction in terms {
+        assert_eq!(check_terms(&auction), Err(Rejection::InvalidAmount));
+    }
+}
+
+#[test]
+fn commitments_are_taken_once_while_bidding_is_open() {
+    let mut auction = sealed();
+    let reveal = BidReveal {
+        bid: bid("bidder", 50.0),
+        salt: "salt".to_string(),
+    };
+    let committed = commitment(&reveal);
+
+    assert_eq!(check_commitment(&auction, &committed, start()), Ok(()));
+    assert_eq!(
+        check_commitment(
+            &auction,
+            &BidCommitment {
+                bidder: "seller".to_string(),
+                ..committed.clone()
+            },
+            start()
+        ),
+        Err(Rejection::SellerBid)
+    );
+    assert_eq!(
+        check_commitment(&auction, &committed, auction.end_time),
+        Err(Rejection::AuctionClosed)
+    );
+    auction.commitments.push(committed.clone());
+    assert_eq!(
+        check_commitment(&auction, &committed, start()),
+        Err(Rejection::AlreadyCommitted)
+    );
+}
+
+#[test]
+fn reveals_must_open_the_commitment() {
+    let mut auction = sealed();
+    let reveal = BidReveal {
+        bid: bid("bidder", 50.0),
+        salt: "salt".to_string(),
+    };
+    auction.commitments.push(commitment(&reveal));
+    let reveal_phase = auction.end_time + Duration::seconds(1);
+
+    assert_eq!(
+        check_reveal(&auction, &reveal, start()),
+        Err(Rejection::NotInRevealPhase)
+    );
+    assert_eq!(
+        check_reveal(&auction, &reveal, auction.settlement_time()),
+        Err(Rejection::NotInRevealPhase)
+    );
+    // another amount or salt than the committed one
+    for tampered in [
+        BidReveal {
+            bid: bid("bidder", 60.0),
+            ..reveal.clone()
+        },
+        BidReveal {
+            salt: "pepper".to_string(),
+            ..reveal.clone()
+        },
+    ] {
+        assert_eq!(
+            check_reveal(&auction, &tampered, reveal_phase),
+            Err(Rejection::CommitmentMismatch)
+        );
+    }
+    assert_eq!(check_reveal(&auction, &reveal, reveal_phase), Ok(()));
+
+    let low = BidReveal {
+        bid: bid("lowballer", 5.0),
+        salt: "salt".to_string(),
+    };
+    auction.commitments.push(commitment(&low));
+    assert_eq!(
+        check_reveal(&auction, &low, reveal_phase),
+        Err(Rejection::BelowStartingBid { starting_bid: 20.0 })
+    );
+
+    auction.bids.push(reveal.bid.clone());
+    assert_eq!(
+        check_reveal(&auction, &reveal, reveal_phase),
+        Err(Rejection::AlreadyRevealed)
+    );
+}
+
+#[test]
+fn accepts_are_stamped_within_the_clock_skew() {
+    let mut auction = dutch();
+    let now = start() + Duration::minutes(1);
+
+    assert_eq!(check_accept(&auction, &accept("bidder", now), now), Ok(()));
+    // an accept from the future would take a lower price step early
+    assert_eq!(
+        check_accept(&auction, &accept("bidder", now + Duration::seconds(1)), now),
+        Err(Rejection::StaleTimestamp)
+    );
+    assert_eq!(
+        check_accept(
+            &auction,
+            &accept("bidder", start()),
+            now + Duration::hours(1)
+        ),
+        Err(Rejection::StaleTimestamp)
+    );
+    assert_eq!(
+        check_accept(&auction, &accept("seller", now), now),
+        Err(Rejection::SellerBid)
+    );
+
+    auction.bids.push(bid("bidder", 90.0));
+    assert_eq!(
+        check_accept(&auction, &accept("other", now), now),
+        Err(Rejection::AuctionClosed)
+    );
+}
+
+#[test]
+fn only_the_seller_cancels_an_auction_without_bids() {
+    let mut auction = auction(AuctionKind::English, 10.0, BidIncrement::Absolute(0.0));
+    let cancel = AuctionCancel {
+        seller: "seller".to_string(),
+        auction_signature: "auction".to_string(),
+        signature: "cancel".to_string(),
+    };
+
+    assert_eq!(check_cancel(&auction, &cancel), Ok(()));
+    assert_eq!(
+        check_cancel(
+            &auction,
+            &AuctionCancel {
+                seller: "bidder".to_string(),
+                ..cancel.clone()
+            }
+        ),
+        Err(Rejection::NotSeller)
+    );
+    auction.bids.push(bid("bidder", 10.0));
+    assert_eq!(
+        check_cancel(&auction, &cancel),
+        Err(Rejection::AuctionHasBids)
+    );
+}
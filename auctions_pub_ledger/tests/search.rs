@@ -0,0 +1,214 @@
+mod common;
+
+use auctions_pub_ledger::auction_app::auction::{Auction, Subscription};
+use auctions_pub_ledger::auction_server::search::{
+    merge_results, search, AuctionQuery, SortKey, StateFilter, MAX_PAGE_SIZE,
+};
+use chrono::{Duration, Utc};
+use proptest::prelude::*;
+
+fn auction(index: usize, starting_bid: u16, minutes: i64, active: bool, category: u8) -> Auction {
+    let mut auction = common::auction(&[]);
+    auction.item_name = format!("item{}", index);
+    auction.start_time = Utc::now();
+    auction.end_time = auction.start_time + Duration::minutes(minutes);
+    auction.starting_bid = starting_bid as f32;
+    auction.user_id = format!("seller{}", index % 3);
+    auction.signature = format!("auction{:04}", index);
+    auction.active = active;
+    auction.metadata.category = format!("category{}", category);
+    auction
+}
+
+fn auctions() -> impl Strategy<Value = Vec<Auction>> {
+    prop::collection::vec((1u16..1000, 1i64..600, any::<bool>(), 0u8..3), 0..60).prop_map(|specs| {
+        specs
+            .into_iter()
+            .enumerate()
+            .map(|(index, (starting_bid, minutes, active, category))| {
+                auction(index, starting_bid, minutes, active, category)
+            })
+            .collect()
+    })
+}
+
+fn sort_key() -> impl Strategy<Value = SortKey> {
+    prop_oneof![
+        Just(SortKey::EndingSoonest),
+        Just(SortKey::PriceLowest),
+        Just(SortKey::PriceHighest),
+        Just(SortKey::Newest)
+    ]
+}
+
+proptest! {
+    #[test]
+    fn pages_cover_every_match_once(auctions in auctions(), sort in sort_key(), page_size in 1usize..15) {
+        let mut query = AuctionQuery {
+            state: StateFilter::Active,
+            sort,
+            page_size,
+            ..AuctionQuery::default()
+        };
+        let mut seen = Vec::new();
+        loop {
+            let page = search(&auctions, &query);
+            prop_assert!(page.auctions.len() <= page_size);
+            if page.auctions.is_empty() {
+                prop_assert_eq!(query.page, page.page_count());
+                break;
+            }
+            seen.extend(page.auctions.into_iter().map(|auction| auction.signature));
+            query.page += 1;
+        }
+
+        let mut expected: Vec<String> = auctions
+            .iter()
+            .filter(|auction| auction.active)
+            .map(|auction| auction.signature.clone())
+            .collect();
+        prop_assert_eq!(seen.len(), expected.len());
+        seen.sort();
+        expected.sort();
+        prop_assert_eq!(seen, expected);
+    }
+
+    #[test]
+    fn replicas_merge_into_one_sorted_list(
+        auctions in auctions(),
+        sort in sort_key(),
+        holders in prop::collection::vec(1u8..8, 60),
+    ) {
+        // every auction is held by some of three nodes, the copy of the last one saw a bid
+        // the others missed
+        let mut nodes: Vec<Vec<Auction>> = vec![Vec::new(); 3];
+        let mut latest = Vec::new();
+        for (auction, holders) in auctions.iter().zip(holders.iter()) {
+            let mut copy = auction.clone();
+            for (node, held) in nodes.iter_mut().enumerate() {
+                if holders & (1 << node) != 0 {
+                    if node == 2 {
+                        copy.bids.push(common::bid(0, 5000, 1));
+                    }
+                    held.push(copy.clone());
+                }
+            }
+            latest.push(copy);
+        }
+        let query = AuctionQuery { sort, page_size: MAX_PAGE_SIZE, ..AuctionQuery::default() };
+        let merged = merge_results(&query, nodes.iter().map(|held| search(held, &query).auctions));
+
+        let expected = search(&latest, &query).auctions;
+        let listed = |auctions: &[Auction]| -> Vec<(String, usize)> {
+            auctions.iter().map(|auction| (auction.signature.clone(), auction.bids.len())).collect()
+        };
+        prop_assert_eq!(listed(&merged), listed(&expected));
+    }
+
+    #[test]
+    fn results_respect_the_filters(
+        auctions in auctions(),
+        category in 0u8..3,
+        min_price in 0u16..500,
+        max_price in 500u16..1000,
+    ) {
+        let query = AuctionQuery {
+            state: StateFilter::Closed,
+            seller: Some("seller1".to_string()),
+            category: Some(format!("CATEGORY{}", category)),
+            min_price: Some(min_price as f32),
+            max_price: Some(max_price as f32),
+            ending_before: Some(Utc::now() + Duration::minutes(300)),
+            page_size: 100,
+            ..AuctionQuery::default()
+        };
+        let page = search(&auctions, &query);
+
+        prop_assert_eq!(page.total, page.auctions.len());
+        for auction in page.auctions.iter() {
+            prop_assert!(!auction.active);
+            prop_assert_eq!(&auction.user_id, "seller1");
+            prop_assert_eq!(&auction.metadata.category, &format!("category{}", category));
+            prop_assert!(auction.current_price() >= min_price as f32);
+            prop_assert!(auction.current_price() <= max_price as f32);
+            prop_assert!(auction.closing_time() < query.ending_before.unwrap());
+        }
+    }
+
+    #[test]
+    fn results_are_sorted(auctions in auctions()) {
+        let page = search(&auctions, &AuctionQuery {
+            sort: SortKey::PriceHighest,
+            page_size: 100,
+            ..AuctionQuery::default()
+        });
+        for pair in page.auctions.windows(2) {
+            prop_assert!(pair[0].current_price() >= pair[1].current_price());
+        }
+
+        let page = search(&auctions, &AuctionQuery {
+            sort: SortKey::EndingSoonest,
+            page_size: 100,
+            ..AuctionQuery::default()
+        });
+        for pair in page.auctions.windows(2) {
+            prop_assert!(pair[0].closing_time() <= pair[1].closing_time());
+        }
+    }
+
+    #[test]
+    fn the_watchlist_holds_what_was_last_watched(
+        mut auctions in auctions(),
+        watches in prop::collection::vec((0usize..60, any::<bool>()), 0..20),
+    ) {
+        for (second, (index, subscribe)) in watches.iter().enumerate() {
+            if let Some(auction) = auctions.get_mut(*index) {
+                auction.record_subscription(&Subscription {
+                    subscriber: "watcher".to_string(),
+                    auction_signature: auction.signature.clone(),
+                    subscribe: *subscribe,
+                    timestamp: auction.start_time + Duration::seconds(second as i64),
+                    signature: format!("subscription{}", second),
+                });
+            }
+        }
+
+        let page = search(&auctions, &AuctionQuery {
+            watcher: Some("watcher".to_string()),
+            page_size: 100,
+            ..AuctionQuery::default()
+        });
+        let watched: Vec<&String> = auctions
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| {
+                watches
+                    .iter()
+                    .rev()
+                    .find(|(watched, _)| watched == index)
+                    .is_some_and(|(_, subscribe)| *subscribe)
+            })
+            .map(|(_, auction)| &auction.signature)
+            .collect();
+        let mut listed: Vec<&String> = page.auctions.iter().map(|auction| &auction.signature).collect();
+        listed.sort();
+        prop_assert_eq!(listed, watched);
+    }
+}
+
+#[test]
+fn pages_far_past_the_end_are_empty() {
+    let auctions: Vec<Auction> = (0..5)
+        .map(|index| auction(index, 10, 60, true, 0))
+        .collect();
+    let page = search(
+        &auctions,
+        &AuctionQuery {
+            page: usize::MAX,
+            page_size: 100,
+            ..AuctionQuery::default()
+        },
+    );
+    assert!(page.auctions.is_empty());
+    assert_eq!(page.total, 5);
+}
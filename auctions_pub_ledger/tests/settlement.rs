@@ -0,0 +1,265 @@
+mod common;
+
+use auctions_pub_ledger::auction_app::auction::{Auction, AuctionKind, Bid, Pricing, SoftClose};
+use auctions_pub_ledger::auction_server::blockchain::{Block, Blockchain};
+use auctions_pub_ledger::auction_server::settlement::{recorded_settlement, settle, settles};
+use chrono::Duration;
+use common::bids;
+use proptest::prelude::*;
+
+fn auction_with_bids(pricing: Pricing, reserve_price: f32, bids: &[(u8, u16, i64)]) -> Auction {
+    let mut auction = common::auction(bids);
+    auction.rules.pricing = pricing;
+    auction.rules.reserve_price = reserve_price;
+    auction
+}
+
+fn pricing() -> impl Strategy<Value = Pricing> {
+    prop_oneof![
+        Just(Pricing::FirstPrice),
+        Just(Pricing::SecondPrice),
+        Just(Pricing::Uniform)
+    ]
+}
+
+proptest! {
+    #[test]
+    fn winner_holds_the_highest_bid(pricing in pricing(), reserve in 0u16..1000, bids in bids()) {
+        let auction = auction_with_bids(pricing, reserve as f32, &bids);
+        let settlement = settle(&auction);
+
+        let highest = bids.iter().map(|(_, amount, _)| *amount as f32).fold(None, |best: Option<f32>, amount| {
+            Some(best.map_or(amount, |best| best.max(amount)))
+        });
+        match settlement.awards.first() {
+            Some(award) => {
+                prop_assert_eq!(settlement.awards.len(), 1);
+                prop_assert_eq!(Some(award.bid.amount), highest);
+                prop_assert!(award.bid.amount >= reserve as f32);
+            }
+            None => prop_assert!(highest.is_none_or(|amount| amount < reserve as f32)),
+        }
+    }
+
+    #[test]
+    fn first_price_charges_the_winning_bid(reserve in 0u16..1000, bids in bids()) {
+        let auction = auction_with_bids(Pricing::FirstPrice, reserve as f32, &bids);
+        let settlement = settle(&auction);
+
+        if let Some(award) = settlement.awards.first() {
+            prop_assert_eq!(award.price, award.bid.amount);
+        }
+    }
+
+    #[test]
+    fn second_price_stays_between_reserve_and_bid(reserve in 0u16..1000, bids in bids()) {
+        let auction = auction_with_bids(Pricing::SecondPrice, reserve as f32, &bids);
+        let settlement = settle(&auction);
+
+        if let Some(award) = settlement.awards.first() {
+            prop_assert!(award.price <= award.bid.amount);
+            prop_assert!(award.price >= auction.reserve_floor());
+        }
+    }
+
+    #[test]
+    fn second_price_single_bidder_pays_the_reserve(reserve in 1u16..500, amount in 500u16..1000) {
+        let auction = auction_with_bids(Pricing::SecondPrice, reserve as f32, &[(0, amount, 0)]);
+        let settlement = settle(&auction);
+
+        prop_assert_eq!(settlement.awards.len(), 1);
+        prop_assert_eq!(settlement.awards[0].price, reserve as f32);
+    }
+
+    #[test]
+    fn dutch_price_descends_to_the_floor(
+        start_price in 100u16..1000,
+        decrement in 1u16..50,
+        floor_price in 0u16..100,
+        earlier in 0i64..3600,
+        later in 0i64..3600,
+    ) {
+        let mut auction = auction_with_bids(Pricing::FirstPrice, 0.0, &[]);
+        auction.kind = AuctionKind::Dutch {
+            start_price: start_price as f32,
+            decrement: decrement as f32,
+            decrement_seconds: 60,
+            floor_price: floor_price as f32,
+        };
+        let (earlier, later) = (earlier.min(later), earlier.max(later));
+        let earlier_price = auction.dutch_price_at(auction.start_time + Duration::seconds(earlier)).unwrap();
+        let later_price = auction.dutch_price_at(auction.start_time + Duration::seconds(later)).unwrap();
+
+        prop_assert!(later_price <= earlier_price);
+        prop_assert!(later_price >= floor_price as f32);
+        prop_assert!(earlier_price <= start_price as f32);
+    }
+
+    #[test]
+    fn soft_close_extension_is_capped(
+        window_seconds in 1i64..300,
+        extension_seconds in 1i64..300,
+        max_extension_seconds in 0i64..900,
+        bid_offsets in prop::collection::vec(0i64..1200, 0..12),
+    ) {
+        let mut auction = auction_with_bids(Pricing::FirstPrice, 0.0, &[]);
+        auction.rules.soft_close = Some(SoftClose {
+            window_seconds,
+            extension_seconds,
+            max_extension_seconds,
+        });
+        let mut bid_offsets = bid_offsets;
+        bid_offsets.sort();
+        for offset in bid_offsets {
+            let closing_time = auction.closing_time();
+            let timestamp = auction.start_time + Duration::seconds(offset);
+            auction.bids.push(Bid {
+                bidder: "bidder".to_string(),
+                amount: 1.0,
+                signature: String::new(),
+                auction_signature: auction.signature.clone(),
+                timestamp,
+                salt: String::new(),
+            });
+            if timestamp < closing_time && closing_time - timestamp <= Duration::seconds(window_seconds) {
+                prop_assert!(auction.closing_time() >= closing_time);
+            } else {
+                prop_assert_eq!(auction.closing_time(), closing_time);
+            }
+        }
+
+        prop_assert!(auction.closing_time() >= auction.end_time);
+        prop_assert!(auction.closing_time() <= auction.end_time + Duration::seconds(max_extension_seconds));
+    }
+
+    #[test]
+    fn buy_now_closes_at_the_listed_price(
+        pricing in pricing(),
+        buy_now_price in 1u16..1000,
+        bids in bids(),
+        buy_now_offset in 0i64..600,
+    ) {
+        let mut auction = auction_with_bids(pricing, 0.0, &bids);
+        auction.rules.buy_now_price = Some(buy_now_price as f32);
+        auction.bids.retain(|bid| bid.amount < buy_now_price as f32);
+        let buy_now_time = auction.start_time + Duration::seconds(buy_now_offset);
+        auction.bids.push(Bid {
+            bidder: "buyer".to_string(),
+            amount: buy_now_price as f32,
+            signature: String::new(),
+            auction_signature: auction.signature.clone(),
+            timestamp: buy_now_time,
+            salt: String::new(),
+        });
+        let settlement = settle(&auction);
+
+        prop_assert_eq!(auction.closing_time(), buy_now_time);
+        prop_assert_eq!(settlement.awards.len(), 1);
+        prop_assert_eq!(&settlement.awards[0].bid.bidder, "buyer");
+        prop_assert_eq!(settlement.awards[0].price, buy_now_price as f32);
+    }
+
+    #[test]
+    fn second_price_never_exceeds_first_price(reserve in 0u16..1000, bids in bids()) {
+        let first = settle(&auction_with_bids(Pricing::FirstPrice, reserve as f32, &bids));
+        let second = settle(&auction_with_bids(Pricing::SecondPrice, reserve as f32, &bids));
+
+        prop_assert_eq!(first.awards.len(), second.awards.len());
+        for (first, second) in first.awards.iter().zip(second.awards.iter()) {
+            prop_assert_eq!(&first.bid, &second.bid);
+            prop_assert!(second.price <= first.price);
+        }
+    }
+
+    #[test]
+    fn multi_unit_awards_one_unit_to_each_top_bidder(
+        pricing in pricing(),
+        quantity in 1u32..4,
+        reserve in 0u16..1000,
+        bids in bids(),
+    ) {
+        let mut auction = auction_with_bids(pricing, reserve as f32, &bids);
+        auction.quantity = quantity;
+        let settlement = settle(&auction);
+
+        prop_assert!(settlement.awards.len() <= quantity as usize);
+        let mut winners: Vec<&str> = settlement.awards.iter().map(|award| award.bid.bidder.as_str()).collect();
+        winners.sort();
+        winners.dedup();
+        prop_assert_eq!(winners.len(), settlement.awards.len());
+
+        // nobody left without a unit outbid a winner with its best bid
+        let lowest_award = settlement.awards.iter().map(|award| award.bid.amount).fold(f32::MAX, f32::min);
+        for bid in auction.bids.iter().filter(|bid| !winners.contains(&bid.bidder.as_str())) {
+            prop_assert!(bid.amount <= lowest_award || bid.amount < reserve as f32);
+        }
+        for award in settlement.awards.iter() {
+            prop_assert!(award.bid.amount >= reserve as f32);
+            prop_assert!(award.price <= award.bid.amount);
+        }
+    }
+
+    #[test]
+    fn uniform_pricing_charges_every_unit_the_same(quantity in 1u32..4, bids in bids()) {
+        let mut auction = auction_with_bids(Pricing::Uniform, 0.0, &bids);
+        auction.quantity = quantity;
+        let settlement = settle(&auction);
+
+        if let Some(lowest) = settlement.awards.last() {
+            for award in settlement.awards.iter() {
+                prop_assert_eq!(award.price, lowest.bid.amount);
+            }
+        }
+    }
+
+    #[test]
+    fn a_settlement_is_found_pending_or_in_the_chain(bids in bids()) {
+        let auction = common::auction(&bids);
+        let pending = vec![
+            "deposit".to_string(),
+            serde_json::to_string(&settle(&auction)).unwrap(),
+        ];
+        prop_assert!(settles(&pending, &auction.signature));
+        prop_assert!(!settles(&pending[..1], &auction.signature));
+        prop_assert!(!settles(&pending, "another auction"));
+
+        let mut blockchain = Blockchain::new();
+        blockchain.add_block(Block::new(0, String::new(), 0, 0, String::new(), Vec::new()));
+        let blockchains = vec![blockchain.clone()];
+        prop_assert!(!recorded_settlement(&blockchains, &auction.signature));
+        blockchain.add_block(Block::new(1, String::new(), 0, 0, String::new(), pending));
+        prop_assert!(recorded_settlement(&[blockchains[0].clone(), blockchain], &auction.signature));
+    }
+
+    #[test]
+    fn discriminatory_pricing_charges_each_own_bid(quantity in 1u32..4, bids in bids()) {
+        let mut auction = auction_with_bids(Pricing::FirstPrice, 0.0, &bids);
+        auction.quantity = quantity;
+        let settlement = settle(&auction);
+
+        for award in settlement.awards.iter() {
+            prop_assert_eq!(award.price, award.bid.amount);
+        }
+    }
+}
+
+// reserve, bids, winner and price of a fixed settlement, the starting bid is 1
+type PriceCase = (f32, &'static [(u8, u16, i64)], &'static str, f32);
+
+#[test]
+fn second_price_is_the_best_competing_bid() {
+    let cases: [PriceCase; 5] = [
+        (0.0, &[(0, 100, 0), (1, 70, 1), (2, 40, 2)], "bidder0", 70.0),
+        // the winner's own lower bids do not compete
+        (0.0, &[(0, 100, 0), (0, 90, 1), (1, 60, 2)], "bidder0", 60.0),
+        (80.0, &[(0, 100, 0), (1, 70, 1)], "bidder0", 80.0),
+        (0.0, &[(1, 30, 0), (0, 100, 1)], "bidder0", 30.0),
+        (0.0, &[(0, 100, 0)], "bidder0", 1.0),
+    ];
+    for (reserve, bids, winner, price) in cases {
+        let settlement = settle(&auction_with_bids(Pricing::SecondPrice, reserve, bids));
+        assert_eq!(settlement.awards.len(), 1, "{:?}", bids);
+        assert_eq!(settlement.awards[0].bid.bidder, winner, "{:?}", bids);
+        assert_eq!(settlement.awards[0].price, price, "{:?}", bids);
+    }
+}
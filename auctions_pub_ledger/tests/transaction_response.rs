@@ -0,0 +1,50 @@
+use auctions_pub_ledger::auction_server::auction_handler::{
+    from_response, to_response, Outcome, Rejection,
+};
+use auctions_pub_ledger::auction_tx::SubmitTransactionResponse;
+use chrono::DateTime;
+use proptest::prelude::*;
+
+fn result() -> impl Strategy<Value = Result<Outcome, Rejection>> {
+    prop_oneof![
+        Just(Ok(Outcome::Accepted)),
+        "[0-9]{1,3}(\\.[0-9]{1,3}){3}".prop_map(|node| Ok(Outcome::Forwarded { node })),
+        Just(Err(Rejection::InvalidSignature)),
+        Just(Err(Rejection::AuctionClosed)),
+        Just(Err(Rejection::AuctionNotFound)),
+        Just(Err(Rejection::HopLimitReached)),
+        Just(Err(Rejection::AlreadyListed)),
+        Just(Err(Rejection::InvalidAmount)),
+        (0i64..4_000_000_000).prop_map(|seconds| Err(Rejection::NotYetOpen {
+            start_time: DateTime::from_timestamp(seconds, 0).unwrap(),
+        })),
+        (0u16..1000).prop_map(|amount| Err(Rejection::BelowStartingBid {
+            starting_bid: amount as f32,
+        })),
+        (0u16..1000).prop_map(|amount| Err(Rejection::BelowMinimumIncrement {
+            minimum: amount as f32 / 4.0,
+        })),
+        (0u16..1000).prop_map(|amount| Err(Rejection::DepositNotHeld {
+            deposit: amount as f32,
+        })),
+        (0u16..1000).prop_map(|amount| Err(Rejection::InsufficientCredits {
+            credits: amount as f32,
+        })),
+        "[a-z ]{1,20}".prop_map(|reason| Err(Rejection::Peer(reason))),
+    ]
+}
+
+proptest! {
+    #[test]
+    fn results_survive_the_wire(result in result()) {
+        prop_assert_eq!(from_response(&to_response(&result)), result);
+    }
+}
+
+#[test]
+fn responses_without_a_status_are_errors() {
+    assert!(matches!(
+        from_response(&SubmitTransactionResponse::default()),
+        Err(Rejection::Peer(_))
+    ));
+}
@@ -0,0 +1,371 @@
+mod common;
+
+use auctions_pub_ledger::auction_app::auction::{
+    Auction, AuctionKind, Bid, BidCommitment, Pricing, Subscription, Transaction,
+};
+use auctions_pub_ledger::auction_app::user::{Deposit, User, UserOperation};
+use auctions_pub_ledger::auction_server::settlement::settle;
+use auctions_pub_ledger::auction_server::user_ledger::{
+    apply_operation, charge_award, charge_forfeit, hold_deposit, release_deposit, AwardCharge,
+    LedgerQueue, UserOperationError,
+};
+use auctions_pub_ledger::cryptography::wallet::user_id;
+use chrono::{DateTime, Duration, Utc};
+use common::{sign, start_time};
+use k256::ecdsa::SigningKey;
+use proptest::prelude::*;
+use rand_core::OsRng;
+
+// the auctions of these tests close now
+fn now() -> DateTime<Utc> {
+    start_time() + Duration::minutes(10)
+}
+
+fn deposit(signing_key: &SigningKey, user_id: &str, amount: f32) -> UserOperation {
+    let mut deposit = Deposit {
+        user_id: user_id.to_string(),
+        amount,
+        timestamp: now(),
+        signature: String::new(),
+    };
+    deposit.signature = sign(signing_key, &deposit.signed_content());
+    UserOperation::Deposit(deposit)
+}
+
+fn bid(signing_key: &SigningKey, auction_signature: &str, amount: f32) -> Bid {
+    common::signed_bid(signing_key, auction_signature, amount, now())
+}
+
+fn auction(bids: Vec<Bid>) -> Auction {
+    let mut auction = common::auction(&[]);
+    auction.rules.pricing = Pricing::SecondPrice;
+    auction.bids = bids;
+    auction
+}
+
+fn apply(
+    user: &mut User,
+    operation: &UserOperation,
+    auctions: &[Auction],
+) -> Result<(), UserOperationError> {
+    tokio::runtime::Runtime::new()
+        .unwrap()
+        .block_on(apply_operation(user, operation, auctions, now()))
+}
+
+proptest! {
+    #[test]
+    fn only_the_owner_adds_credits_once(amounts in prop::collection::btree_set(1u16..1000, 1..5)) {
+        let alice = SigningKey::random(&mut OsRng);
+        let mallory = SigningKey::random(&mut OsRng);
+        let mut user = User::new("alice".to_string(), user_id(&alice));
+
+        for amount in amounts.iter() {
+            let operation = deposit(&alice, &user.uid, *amount as f32);
+            prop_assert_eq!(apply(&mut user, &operation, &[]), Ok(()));
+            prop_assert_eq!(
+                apply(&mut user, &operation, &[]),
+                Err(UserOperationError::AlreadyRecorded)
+            );
+        }
+        let forged = deposit(&mallory, &user.uid, 1000.0);
+        prop_assert_eq!(
+            apply(&mut user, &forged, &[]),
+            Err(UserOperationError::InvalidSignature)
+        );
+
+        let total: f32 = user.activity.iter().map(|activity| activity.amount).sum();
+        prop_assert_eq!(user.credits, total);
+        prop_assert_eq!(user.activity.len(), amounts.len());
+    }
+
+    #[test]
+    fn activity_amounts_are_the_signed_ones(amount in 1u16..1000, claimed in 1u16..1000) {
+        let alice = SigningKey::random(&mut OsRng);
+        let mut user = User::new("alice".to_string(), user_id(&alice));
+        let signed = bid(&alice, "auction", amount as f32);
+
+        if claimed != amount {
+            let tampered = Bid { amount: claimed as f32, ..signed.clone() };
+            prop_assert_eq!(
+                apply(&mut user, &UserOperation::Activity(Box::new(Transaction::Bid(tampered))), &[]),
+                Err(UserOperationError::InvalidSignature)
+            );
+        }
+        let operation = UserOperation::Activity(Box::new(Transaction::Bid(signed)));
+        prop_assert_eq!(apply(&mut user, &operation, &[]), Ok(()));
+        prop_assert_eq!(user.activity.len(), 1);
+        prop_assert_eq!(user.activity[0].amount, amount as f32);
+        prop_assert_eq!(user.credits, 0.0);
+    }
+}
+
+#[test]
+fn deposits_must_be_recent_and_positive() {
+    let alice = SigningKey::random(&mut OsRng);
+    let uid = user_id(&alice);
+    let mut user = User::new("alice".to_string(), uid.clone());
+
+    for amount in [0.0, -10.0, f32::NAN] {
+        assert_eq!(
+            apply(&mut user, &deposit(&alice, &uid, amount), &[]),
+            Err(UserOperationError::InvalidAmount)
+        );
+    }
+    let mut old = Deposit {
+        user_id: user.uid.clone(),
+        amount: 10.0,
+        timestamp: now() - Duration::hours(1),
+        signature: String::new(),
+    };
+    old.signature = sign(&alice, &old.signed_content());
+    assert_eq!(
+        apply(&mut user, &UserOperation::Deposit(old), &[]),
+        Err(UserOperationError::StaleTimestamp)
+    );
+
+    let mut subscription = Subscription {
+        subscriber: user.uid.clone(),
+        auction_signature: "auction".to_string(),
+        subscribe: true,
+        timestamp: now(),
+        signature: String::new(),
+    };
+    subscription.signature = sign(&alice, &subscription.signed_content());
+    assert_eq!(
+        apply(
+            &mut user,
+            &UserOperation::Activity(Box::new(Transaction::Subscription(subscription))),
+            &[]
+        ),
+        Err(UserOperationError::NotAnActivity)
+    );
+    assert_eq!(user.credits, 0.0);
+    assert!(user.activity.is_empty());
+}
+
+#[test]
+fn winners_pay_the_settled_price_once() {
+    let alice = SigningKey::random(&mut OsRng);
+    let bob = SigningKey::random(&mut OsRng);
+    let winning = bid(&alice, "auction", 50.0);
+    let auctions = vec![auction(vec![winning.clone(), bid(&bob, "auction", 30.0)])];
+    let mut user = User::new("alice".to_string(), user_id(&alice));
+    user.credits = 40.0;
+
+    assert_eq!(
+        charge_award(&mut user, &winning, &auctions, now() - Duration::minutes(1)),
+        Err(UserOperationError::NotSettled)
+    );
+    // the amount of the bid sent along is not what gets charged
+    let claimed = Bid {
+        amount: 1.0,
+        ..winning.clone()
+    };
+    assert_eq!(
+        charge_award(&mut user, &claimed, &auctions, now()),
+        Ok(AwardCharge::Paid(30.0))
+    );
+    assert_eq!(
+        charge_award(&mut user, &winning, &auctions, now()),
+        Err(UserOperationError::AlreadyRecorded)
+    );
+    assert_eq!(user.credits, 10.0);
+    assert_eq!(user.auctions_winner, vec!["auction".to_string()]);
+
+    let mut loser = User::new("bob".to_string(), user_id(&bob));
+    let losing = auctions[0].bids[1].clone();
+    assert_eq!(
+        charge_award(&mut loser, &losing, &auctions, now()),
+        Err(UserOperationError::NotInSettlement)
+    );
+    assert_eq!(
+        charge_award(&mut loser, &losing, &[], now()),
+        Err(UserOperationError::AuctionNotFound)
+    );
+}
+
+#[tokio::test]
+async fn winners_that_can_not_pay_forfeit_the_award() {
+    let alice = SigningKey::random(&mut OsRng);
+    let bob = SigningKey::random(&mut OsRng);
+    let winning = bid(&alice, "auction", 50.0);
+    let auctions = vec![auction(vec![winning.clone(), bid(&bob, "auction", 30.0)])];
+    let mut user = User::new("alice".to_string(), user_id(&alice));
+    user.credits = 20.0;
+
+    assert_eq!(
+        charge_award(&mut user, &winning, &auctions, now()),
+        Ok(AwardCharge::Forfeited(30.0))
+    );
+    assert_eq!(
+        charge_award(&mut user, &winning, &auctions, now()),
+        Err(UserOperationError::AlreadyRecorded)
+    );
+    assert_eq!(user.credits, 20.0);
+    assert!(user.auctions_winner.is_empty());
+
+    // the deposit held for a sealed winner pays towards the price, or is lost with the award
+    let mut sealed = auctions[0].clone();
+    sealed.kind = AuctionKind::SealedBid {
+        deposit: 4.0,
+        reveal_seconds: 60,
+    };
+    sealed.commitments = vec![commitment(&alice, "auction")];
+    let settled = now() + Duration::minutes(2);
+    for (credits, charged, left) in [
+        (30.0, AwardCharge::Paid(30.0), 0.0),
+        (29.0, AwardCharge::Forfeited(30.0), 25.0),
+    ] {
+        let mut user = User::new("alice".to_string(), user_id(&alice));
+        user.credits = credits;
+        hold_deposit(&mut user, &sealed.commitments[0], &sealed)
+            .await
+            .unwrap();
+        let auctions = [sealed.clone()];
+        assert_eq!(
+            charge_award(&mut user, &winning, &auctions, settled),
+            Ok(charged)
+        );
+        assert_eq!(
+            release_deposit(&mut user, &auctions, "auction", settled),
+            Err(UserOperationError::AlreadyRecorded)
+        );
+        assert_eq!(user.credits, left);
+    }
+}
+
+fn commitment(signing_key: &SigningKey, auction_signature: &str) -> BidCommitment {
+    let mut commitment = BidCommitment {
+        bidder: user_id(signing_key),
+        auction_signature: auction_signature.to_string(),
+        commitment: sha256::digest("sealed"),
+        signature: String::new(),
+    };
+    commitment.signature = sign(signing_key, &commitment.signed_content());
+    commitment
+}
+
+#[tokio::test]
+async fn deposits_are_held_until_the_settlement() {
+    let alice = SigningKey::random(&mut OsRng);
+    let bob = SigningKey::random(&mut OsRng);
+    let mut auction = auction(vec![bid(&bob, "auction", 30.0)]);
+    auction.kind = AuctionKind::SealedBid {
+        deposit: 4.0,
+        reveal_seconds: 60,
+    };
+    auction.commitments = vec![commitment(&alice, "auction"), commitment(&bob, "auction")];
+    let settled = now() + Duration::minutes(2);
+    let mut user = User::new("alice".to_string(), user_id(&alice));
+
+    user.credits = 3.0;
+    assert_eq!(
+        hold_deposit(&mut user, &auction.commitments[0], &auction).await,
+        Err(UserOperationError::InsufficientCredits)
+    );
+    user.credits = 10.0;
+    assert_eq!(
+        hold_deposit(&mut user, &auction.commitments[1], &auction).await,
+        Err(UserOperationError::InvalidSignature)
+    );
+    assert_eq!(
+        hold_deposit(&mut user, &auction.commitments[0], &auction).await,
+        Ok(4.0)
+    );
+    assert_eq!(
+        hold_deposit(&mut user, &auction.commitments[0], &auction).await,
+        Err(UserOperationError::AlreadyRecorded)
+    );
+    assert_eq!(user.credits, 6.0);
+
+    // alice never revealed, the held deposit is what she forfeits
+    let auctions = vec![auction.clone()];
+    assert_eq!(
+        release_deposit(&mut user, &auctions, "auction", now()),
+        Err(UserOperationError::DepositStillHeld)
+    );
+    assert_eq!(
+        release_deposit(&mut user, &auctions, "auction", settled),
+        Err(UserOperationError::DepositStillHeld)
+    );
+    let forfeit = settle(&auction).forfeits[0].clone();
+    assert_eq!(
+        charge_forfeit(&mut user, &forfeit, &auctions, settled),
+        Ok(4.0)
+    );
+    assert_eq!(user.credits, 6.0);
+    assert_eq!(
+        release_deposit(&mut user, &auctions, "auction", settled),
+        Err(UserOperationError::AlreadyRecorded)
+    );
+
+    // bob revealed and gets it back once
+    let mut revealed = User::new("bob".to_string(), user_id(&bob));
+    revealed.credits = 4.0;
+    hold_deposit(&mut revealed, &auction.commitments[1], &auction)
+        .await
+        .unwrap();
+    assert_eq!(revealed.credits, 0.0);
+    assert_eq!(
+        release_deposit(&mut revealed, &auctions, "auction", settled),
+        Ok(4.0)
+    );
+    assert_eq!(
+        release_deposit(&mut revealed, &auctions, "auction", settled),
+        Err(UserOperationError::AlreadyRecorded)
+    );
+    assert_eq!(revealed.credits, 4.0);
+
+    // a cancelled auction or a commitment that was not taken gives it back right away
+    let mut cancelled = auction.clone();
+    cancelled.cancelled = true;
+    let mut not_taken = auction.clone();
+    not_taken.commitments.remove(0);
+    for auction in [cancelled, not_taken] {
+        let mut user = User::new("alice".to_string(), user_id(&alice));
+        user.credits = 4.0;
+        hold_deposit(&mut user, &commitment(&alice, "auction"), &auction)
+            .await
+            .unwrap();
+        assert_eq!(
+            release_deposit(&mut user, &[auction], "auction", now()),
+            Ok(4.0)
+        );
+        assert_eq!(user.credits, 4.0);
+    }
+}
+
+#[tokio::test]
+async fn queued_deposits_survive_a_restart_until_a_block_holds_them() {
+    let directory = std::env::temp_dir().join(format!("ledger-{}", std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+    let path = directory.join("ledger.json").to_string_lossy().to_string();
+
+    let mut queue = LedgerQueue::load(&path).unwrap();
+    assert!(queue.entries.is_empty());
+    queue.record(&path, "first".to_string()).await.unwrap();
+    queue.record(&path, "second".to_string()).await.unwrap();
+    assert_eq!(LedgerQueue::load(&path).unwrap(), queue);
+
+    queue
+        .remove_recorded(&path, &["first".to_string(), "settlement".to_string()])
+        .await
+        .unwrap();
+    assert_eq!(
+        LedgerQueue::load(&path).unwrap().entries,
+        vec!["second".to_string()]
+    );
+
+    // an entry that can not be saved is not kept
+    let unwritable = directory.join("missing").join("ledger.json");
+    assert!(queue
+        .record(&unwritable.to_string_lossy(), "third".to_string())
+        .await
+        .is_err());
+    assert_eq!(queue.entries, vec!["second".to_string()]);
+
+    std::fs::write(&path, "{ not a queue").unwrap();
+    assert!(LedgerQueue::load(&path).is_err());
+    std::fs::remove_dir_all(&directory).unwrap();
+}
@@ -0,0 +1,121 @@
+use auctions_pub_ledger::auction_server::user_sessions::{
+    login_content, AuthError, UserSessions, CHALLENGE_TTL, MAX_CHALLENGES, MAX_PENDING_CHALLENGES,
+    SESSION_TTL,
+};
+use auctions_pub_ledger::cryptography::wallet::user_id;
+use chrono::{DateTime, Duration, Utc};
+use k256::ecdsa::{signature::Signer, Signature, SigningKey};
+use proptest::prelude::*;
+use rand_core::OsRng;
+
+fn now() -> DateTime<Utc> {
+    DateTime::from_timestamp(1_700_000_000, 0).unwrap()
+}
+
+fn answer(signing_key: &SigningKey, user_id: &str, nonce: &str) -> String {
+    let signature: Signature = signing_key.sign(login_content(user_id, nonce).as_bytes());
+    hex::encode(signature.to_bytes())
+}
+
+proptest! {
+    #[test]
+    fn only_the_key_holder_logs_in_once(answer_after in 0i64..CHALLENGE_TTL, use_after in 0i64..SESSION_TTL) {
+        let alice = SigningKey::random(&mut OsRng);
+        let mallory = SigningKey::random(&mut OsRng);
+        let (alice_id, mallory_id) = (user_id(&alice), user_id(&mallory));
+        let mut sessions = UserSessions::default();
+
+        let (nonce, _) = sessions.issue_challenge(&alice_id, now()).unwrap();
+        let forged = answer(&mallory, &alice_id, &nonce);
+        prop_assert_eq!(
+            sessions.login(&alice_id, &nonce, &forged, now()),
+            Err(AuthError::InvalidSignature)
+        );
+
+        let (nonce, _) = sessions.issue_challenge(&alice_id, now()).unwrap();
+        let signature = answer(&alice, &alice_id, &nonce);
+        let answered = now() + Duration::seconds(answer_after);
+        let (session, _) = sessions.login(&alice_id, &nonce, &signature, answered).unwrap();
+        // a nonce is good for a single login
+        prop_assert_eq!(
+            sessions.login(&alice_id, &nonce, &signature, answered),
+            Err(AuthError::UnknownChallenge)
+        );
+
+        let used = answered + Duration::seconds(use_after);
+        prop_assert_eq!(sessions.authorize(&session, &alice_id, used), Ok(()));
+        prop_assert_eq!(
+            sessions.authorize(&session, &mallory_id, used),
+            Err(AuthError::WrongUser)
+        );
+        prop_assert_eq!(
+            sessions.authorize(&session, &alice_id, answered + Duration::seconds(SESSION_TTL)),
+            Err(AuthError::UnknownSession)
+        );
+    }
+}
+
+#[test]
+fn challenges_expire_and_belong_to_one_user() {
+    let alice = SigningKey::random(&mut OsRng);
+    let bob = SigningKey::random(&mut OsRng);
+    let (alice_id, bob_id) = (user_id(&alice), user_id(&bob));
+    let mut sessions = UserSessions::default();
+
+    let (nonce, expires_at) = sessions.issue_challenge(&alice_id, now()).unwrap();
+    let signature = answer(&alice, &alice_id, &nonce);
+    assert_eq!(
+        sessions.login(&alice_id, &nonce, &signature, expires_at),
+        Err(AuthError::UnknownChallenge)
+    );
+
+    // bob can not answer a challenge issued for alice, even with his own key
+    let (nonce, _) = sessions.issue_challenge(&alice_id, now()).unwrap();
+    let signature = answer(&bob, &bob_id, &nonce);
+    assert_eq!(
+        sessions.login(&bob_id, &nonce, &signature, now()),
+        Err(AuthError::UnknownChallenge)
+    );
+}
+
+#[test]
+fn pending_challenges_are_capped() {
+    let alice = SigningKey::random(&mut OsRng);
+    let alice_id = user_id(&alice);
+    let mut sessions = UserSessions::default();
+
+    let nonces: Vec<String> = (0..MAX_PENDING_CHALLENGES as i64 + 1)
+        .map(|second| {
+            sessions
+                .issue_challenge(&alice_id, now() + Duration::seconds(second))
+                .unwrap()
+                .0
+        })
+        .collect();
+    let oldest = &nonces[0];
+    let newest = nonces.last().unwrap();
+    assert_eq!(
+        sessions.login(&alice_id, oldest, &answer(&alice, &alice_id, oldest), now()),
+        Err(AuthError::UnknownChallenge)
+    );
+    assert!(sessions
+        .login(&alice_id, newest, &answer(&alice, &alice_id, newest), now())
+        .is_ok());
+}
+
+#[test]
+fn challenges_of_all_users_are_capped() {
+    let mut sessions = UserSessions::default();
+    // made up ids, a node does not know its users before they log in
+    for user in 0..MAX_CHALLENGES {
+        sessions
+            .issue_challenge(&format!("user{}", user), now())
+            .unwrap();
+    }
+    assert_eq!(
+        sessions.issue_challenge("another", now()),
+        Err(AuthError::Busy)
+    );
+    let expired = now() + Duration::seconds(CHALLENGE_TTL);
+    assert!(sessions.issue_challenge("another", expired).is_ok());
+}
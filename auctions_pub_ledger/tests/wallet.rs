@@ -0,0 +1,131 @@
+use auctions_pub_ledger::auction_app::user::{imports_key_file, unlock_account, User};
+use auctions_pub_ledger::cryptography::ecdsa_keys::generate_ecdsa_keypair;
+use auctions_pub_ledger::cryptography::wallet::{user_id, KdfParams, Wallet, WalletError};
+use proptest::prelude::*;
+
+// cheapest Argon2 cost, the format does not depend on it
+fn wallet() -> Wallet {
+    let mut wallet = Wallet::new();
+    wallet.kdf = KdfParams {
+        memory_kib: 8,
+        iterations: 1,
+        parallelism: 1,
+    };
+    wallet
+}
+
+proptest! {
+    #[test]
+    fn keys_unlock_with_their_passphrase_only(
+        passphrase in ".{0,16}",
+        other in ".{0,16}",
+        names in prop::collection::btree_set("[a-z]{1,8}", 1..4),
+    ) {
+        let mut wallet = wallet();
+        let mut uids = Vec::new();
+        for name in names.iter() {
+            uids.push(user_id(&wallet.create_account(name, &passphrase).unwrap()));
+        }
+
+        for (name, uid) in names.iter().zip(uids.iter()) {
+            prop_assert_eq!(&user_id(&wallet.unlock(name, &passphrase).unwrap()), uid);
+            if other != passphrase {
+                prop_assert!(matches!(
+                    wallet.unlock(name, &other),
+                    Err(WalletError::WrongPassphrase)
+                ));
+            }
+        }
+    }
+
+    #[test]
+    fn exported_accounts_import_into_another_wallet(passphrase in ".{1,16}", name in "[a-z]{1,8}") {
+        let mut wallet = wallet();
+        let signing_key = wallet.create_account(&name, &passphrase).unwrap();
+        let exported = wallet.export_account(&name).unwrap();
+        prop_assert!(!exported.contains(&hex::encode(signing_key.to_bytes())));
+
+        let mut other = Wallet::new();
+        other.import_account(&exported).unwrap();
+        prop_assert_eq!(
+            other.unlock(&name, &passphrase).unwrap().to_bytes(),
+            signing_key.to_bytes()
+        );
+        prop_assert!(matches!(
+            other.import_account(&exported),
+            Err(WalletError::DuplicateAccount(_))
+        ));
+    }
+}
+
+#[test]
+fn ciphertexts_are_bound_to_their_user() {
+    let mut wallet = wallet();
+    wallet.create_account("alice", "secret").unwrap();
+    wallet.create_account("bob", "secret").unwrap();
+
+    wallet.accounts[0].ciphertext = wallet.accounts[1].ciphertext.clone();
+    wallet.accounts[0].nonce = wallet.accounts[1].nonce.clone();
+    wallet.accounts[0].salt = wallet.accounts[1].salt.clone();
+    assert!(matches!(
+        wallet.unlock("alice", "secret"),
+        Err(WalletError::WrongPassphrase)
+    ));
+}
+
+#[test]
+fn wallets_survive_a_save() {
+    let path = std::env::temp_dir().join(format!("wallet-{}.json", std::process::id()));
+    let mut wallet = wallet();
+    let signing_key = wallet.create_account("alice", "secret").unwrap();
+    wallet.save(&path).unwrap();
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    let loaded = Wallet::open(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(loaded.accounts, wallet.accounts);
+    assert_eq!(
+        loaded.unlock("alice", "secret").unwrap().to_bytes(),
+        signing_key.to_bytes()
+    );
+    assert!(loaded.accounts.iter().all(|account| !account
+        .ciphertext
+        .contains(&hex::encode(signing_key.to_bytes()))));
+}
+
+#[test]
+fn key_files_leave_once_the_wallet_holds_them() {
+    let path = std::env::temp_dir().join(format!("wallet-import-{}.json", std::process::id()));
+    let path = path.to_string_lossy().to_string();
+    wallet().save(&path).unwrap();
+    // key files from before the wallet are named after the user id in the working directory
+    let (signing_key, _) = generate_ecdsa_keypair();
+    let user = User::new("legacy".to_string(), user_id(&signing_key));
+    std::fs::write(&user.uid, signing_key.to_bytes()).unwrap();
+
+    assert!(imports_key_file(&path, &user).unwrap());
+    let unlocked = unlock_account(&path, &user, "secret").unwrap();
+    let key_file_left = std::path::Path::new(&user.uid).exists();
+    if key_file_left {
+        std::fs::remove_file(&user.uid).unwrap();
+    }
+    assert!(!key_file_left);
+    assert_eq!(unlocked.to_bytes(), signing_key.to_bytes());
+
+    assert!(!imports_key_file(&path, &user).unwrap());
+    assert!(matches!(
+        unlock_account(&path, &user, "wrong"),
+        Err(WalletError::WrongPassphrase)
+    ));
+    assert_eq!(
+        unlock_account(&path, &user, "secret").unwrap().to_bytes(),
+        signing_key.to_bytes()
+    );
+    std::fs::remove_file(&path).unwrap();
+}